@@ -114,6 +114,35 @@ const program = anchor.workspace.MutualEscrow as anchor.Program<MutualEscrow>;
         program.programId
       );
 
+    // Per-mint and per-party bookkeeping accounts, created with the first deal that needs them
+    const [mintLedgerPda] = web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("mint_ledger"), mint.toBuffer()],
+      program.programId
+    );
+    const [programStatsPda] = web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("program_stats")],
+      program.programId
+    );
+    const [mintStatsPda] = web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("mint_stats"), mint.toBuffer()],
+      program.programId
+    );
+    const [projectOwnerProfilePda] = web3.PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("project_owner_profile"),
+        projectOwnerKp.publicKey.toBuffer(),
+      ],
+      program.programId
+    );
+    const [projectOwnerVolumePda] = web3.PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("project_owner_volume"),
+        projectOwnerKp.publicKey.toBuffer(),
+        mint.toBuffer(),
+      ],
+      program.programId
+    );
+
     console.log("prepared PDAs\n", {
       dealPda: dealPda.toBase58(),
       vaultTokenAccountPda: vaultTokenAccountPda.toBase58(),
@@ -136,7 +165,7 @@ const program = anchor.workspace.MutualEscrow as anchor.Program<MutualEscrow>;
     if (escrowAccountInfo === null) {
       // Escrow account does not exist, so initialize it
       await program.methods
-        .initialize()
+        .initialize(25) // KOLs can claim 25% once partially eligible
         .accounts({
           escrow: escrowPda,
           admin: adminKp.publicKey,
@@ -159,8 +188,10 @@ const program = anchor.workspace.MutualEscrow as anchor.Program<MutualEscrow>;
         amount,
         { time: {} }, // VestingType::Time
         vestingDuration,
-        null,
-        Array.from(orderIdBuffer)
+        Array.from(orderIdBuffer),
+        null, // No referrer
+        0,
+        null // No stake required from the KOL
       )
       .accounts({
         escrow: escrowPda,
@@ -170,7 +201,13 @@ const program = anchor.workspace.MutualEscrow as anchor.Program<MutualEscrow>;
         mint: mint,
         projectOwnerTokenAccount: projectOwnerTokenAccount.address,
         vaultTokenAccount: vaultTokenAccountPda,
+        mintLedger: mintLedgerPda,
+        programStats: programStatsPda,
+        mintStats: mintStatsPda,
+        projectOwnerProfile: projectOwnerProfilePda,
+        projectOwnerVolume: projectOwnerVolumePda,
         vaultAuthority: vaultAuthorityPda,
+        referrerStats: null,
         tokenProgram: splToken.TOKEN_PROGRAM_ID,
         systemProgram: web3.SystemProgram.programId,
         rent: web3.SYSVAR_RENT_PUBKEY,
@@ -249,8 +286,13 @@ const program = anchor.workspace.MutualEscrow as anchor.Program<MutualEscrow>;
           );
           if (decoded?.name !== "createDeal") continue;
 
-          const { amount, vestingType, vestingDuration, marketcapAuthorizer } =
-            decoded.data;
+          const {
+            amount,
+            vestingType,
+            vestingDuration,
+            referrer,
+            referrerBps,
+          } = decoded.data;
           const kolPubkey = accountKeys[instruction.accountKeyIndexes[3]];
 
           dealInfo.push("\n====== Deal Details ======");
@@ -261,9 +303,9 @@ const program = anchor.workspace.MutualEscrow as anchor.Program<MutualEscrow>;
               vestingType
             )[0].toUpperCase()}, ${vestingDuration.toNumber()} seconds`
           );
-          if (marketcapAuthorizer)
+          if (referrer)
             dealInfo.push(
-              `Marketcap Authorizer: ${marketcapAuthorizer.toBase58()}`
+              `Referrer: ${referrer.toBase58()} (${referrerBps / 100}%)`
             );
 
           const dealData = await program.account.deal.fetch(dealPda);
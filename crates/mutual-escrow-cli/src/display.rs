@@ -0,0 +1,311 @@
+use mutual_escrow_client::{
+    order_id_to_string, vesting_state, ClaimableBreakdown, Deal, DealStatus, EligibilityStatus,
+    Escrow, KolEarnings, KolProfile, MintStats, ProgramStats, ProjectOwnerProfile,
+    ProjectOwnerVolume, VestingProjection, VestingType,
+};
+use solana_sdk::pubkey::Pubkey;
+
+pub fn status_name(status: &DealStatus) -> &'static str {
+    match status {
+        DealStatus::Created => "Created",
+        DealStatus::Accepted => "Accepted",
+        DealStatus::Rejected => "Rejected",
+        DealStatus::PartialCompleted => "Partially completed",
+        DealStatus::Completed => "Completed",
+        DealStatus::Disputed => "Disputed",
+        DealStatus::Resolved => "Resolved",
+        DealStatus::ClawedBack => "Clawed back",
+    }
+}
+
+fn eligibility_name(status: &EligibilityStatus) -> &'static str {
+    match status {
+        EligibilityStatus::NotEligible => "Not eligible",
+        EligibilityStatus::PartiallyEligible => "Partially eligible",
+        EligibilityStatus::FullyEligible => "Fully eligible",
+    }
+}
+
+fn vesting_description(deal: &Deal) -> String {
+    match deal.vesting_type {
+        VestingType::Time => format!(
+            "Linear over {} once fully eligible",
+            format_duration(deal.vesting_duration)
+        ),
+        VestingType::Marketcap => "Everything unlocks once the market cap target is met".into(),
+        VestingType::None => "None, everything unlocks once fully eligible".into(),
+    }
+}
+
+fn format_duration(seconds: i64) -> String {
+    let (days, rest) = (seconds / 86_400, seconds % 86_400);
+    let (hours, rest) = (rest / 3_600, rest % 3_600);
+    let (minutes, seconds) = (rest / 60, rest % 60);
+
+    match (days, hours, minutes) {
+        (0, 0, 0) => format!("{}s", seconds),
+        (0, 0, _) => format!("{}m {}s", minutes, seconds),
+        (0, _, _) => format!("{}h {}m", hours, minutes),
+        _ => format!("{}d {}h", days, hours),
+    }
+}
+
+// UTC date of a unix timestamp, "-" for the zero the program uses as "not set"
+fn format_time(timestamp: i64) -> String {
+    if timestamp == 0 {
+        return "-".into();
+    }
+
+    // Civil date from days since the epoch, after Howard Hinnant's `civil_from_days`
+    let days = timestamp.div_euclid(86_400);
+    let seconds = timestamp.rem_euclid(86_400);
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC ({})",
+        year,
+        month,
+        day,
+        seconds / 3_600,
+        seconds % 3_600 / 60,
+        seconds % 60,
+        timestamp
+    )
+}
+
+pub fn print_deal(address: &Pubkey, deal: &Deal, escrow: &Escrow, now: i64) {
+    let breakdown = mutual_vesting::breakdown(
+        &vesting_state(deal),
+        now,
+        escrow.max_claimable_after_obligation,
+    );
+
+    let rows: Vec<(&str, String)> = vec![
+        ("Deal", address.to_string()),
+        ("Order id", order_id_to_string(&deal.order_id)),
+        ("Project owner", deal.project_owner.to_string()),
+        ("KOL", deal.kol.to_string()),
+        ("Mint", deal.mint.to_string()),
+        ("Status", status_name(&deal.status).into()),
+        (
+            "Eligibility",
+            eligibility_name(&deal.eligibility_status).into(),
+        ),
+        ("Vesting", vesting_description(deal)),
+        ("Amount", deal.amount.to_string()),
+        ("Bonus", deal.bonus_amount.to_string()),
+        ("Released", deal.released_amount.to_string()),
+        (
+            "KOL stake",
+            if deal.required_kol_stake == 0 {
+                "-".into()
+            } else {
+                format!("{} of {} held", deal.kol_stake, deal.required_kol_stake)
+            },
+        ),
+        ("Claimable now", breakdown.claimable_now.to_string()),
+        ("Locked", breakdown.locked_amount.to_string()),
+        ("Next unlock", format_time(breakdown.next_unlock_time)),
+        (
+            "Next unlock amount",
+            breakdown.next_unlock_amount.to_string(),
+        ),
+        ("Fully vested", format_time(breakdown.fully_vested_time)),
+        ("Created", format_time(deal.start_time)),
+        ("Accepted", format_time(deal.accept_time)),
+        (
+            "Partially eligible",
+            format_time(deal.partially_eligible_time),
+        ),
+        ("Fully eligible", format_time(deal.fully_eligible_time)),
+        ("Payees", deal.payee_count.to_string()),
+        (
+            "Referrer",
+            if deal.referrer_bps == 0 {
+                "-".into()
+            } else {
+                format!("{} ({} bps)", deal.referrer, deal.referrer_bps)
+            },
+        ),
+        ("Receipt mint", deal.receipt_mint.to_string()),
+    ];
+
+    for (label, value) in rows {
+        println!("{:<20}{}", label, value);
+    }
+}
+
+// As computed by the program at the simulated slot
+pub fn print_breakdown(address: &Pubkey, breakdown: &ClaimableBreakdown) {
+    let rows: Vec<(&str, String)> = vec![
+        ("Deal", address.to_string()),
+        (
+            "Eligibility",
+            eligibility_name(&breakdown.eligibility_status).into(),
+        ),
+        ("Claimable now", breakdown.claimable_now.to_string()),
+        ("Vested", breakdown.total_vested.to_string()),
+        ("Released", breakdown.released_amount.to_string()),
+        ("Locked", breakdown.locked_amount.to_string()),
+        ("Next unlock", format_time(breakdown.next_unlock_time)),
+        (
+            "Next unlock amount",
+            breakdown.next_unlock_amount.to_string(),
+        ),
+        ("Fully vested", format_time(breakdown.fully_vested_time)),
+    ];
+
+    for (label, value) in rows {
+        println!("{:<20}{}", label, value);
+    }
+}
+
+pub fn print_projections(projections: &[VestingProjection]) {
+    println!(
+        "{:<20}  {:<40}  {:>20}  {:>20}",
+        "Eligibility", "At", "Vested", "Claimable"
+    );
+    for projection in projections {
+        println!(
+            "{:<20}  {:<40}  {:>20}  {:>20}",
+            eligibility_name(&projection.eligibility_status),
+            format_time(projection.timestamp),
+            projection.vested_amount,
+            projection.claimable_amount
+        );
+    }
+}
+
+pub fn print_deal_list(deals: &[(Pubkey, Deal)]) {
+    println!(
+        "{:<44}  {:<16}  {:<20}  {:>20}  {:>20}  KOL",
+        "Deal", "Order id", "Status", "Amount", "Released"
+    );
+    for (address, deal) in deals {
+        println!(
+            "{:<44}  {:<16}  {:<20}  {:>20}  {:>20}  {}",
+            address.to_string(),
+            order_id_to_string(&deal.order_id),
+            status_name(&deal.status),
+            deal.amount,
+            deal.released_amount,
+            deal.kol
+        );
+    }
+    println!("{} deal(s)", deals.len());
+}
+
+pub fn print_stats(program_stats: &ProgramStats, mint_stats: Option<&MintStats>) {
+    let mut rows: Vec<(&str, String)> = vec![
+        ("Deals created", program_stats.deals_created.to_string()),
+        ("Deals accepted", program_stats.deals_accepted.to_string()),
+        ("Deals rejected", program_stats.deals_rejected.to_string()),
+        ("Deals completed", program_stats.deals_completed.to_string()),
+    ];
+    if let Some(mint_stats) = mint_stats {
+        rows.extend([
+            ("Mint", mint_stats.mint.to_string()),
+            ("Mint deals created", mint_stats.deals_created.to_string()),
+            ("Volume escrowed", mint_stats.volume_escrowed.to_string()),
+            ("Volume released", mint_stats.volume_released.to_string()),
+        ]);
+    }
+
+    for (label, value) in rows {
+        println!("{:<20}{}", label, value);
+    }
+}
+
+pub fn print_kol(profile: &KolProfile, mint: Option<&Pubkey>, earnings: Option<&KolEarnings>) {
+    let mut rows: Vec<(&str, String)> = vec![
+        ("KOL", profile.kol.to_string()),
+        (
+            "Payout address",
+            if profile.payout_owner == Pubkey::default() {
+                "-".into()
+            } else {
+                profile.payout_owner.to_string()
+            },
+        ),
+        ("Deals accepted", profile.deals_accepted.to_string()),
+        ("Deals rejected", profile.deals_rejected.to_string()),
+        ("Deals completed", profile.deals_completed.to_string()),
+        ("Disputes lost", profile.disputes_lost.to_string()),
+        (
+            "Avg. obligation",
+            if profile.timed_obligations == 0 {
+                "-".into()
+            } else {
+                format_duration(profile.average_obligation_time())
+            },
+        ),
+        (
+            "Rating",
+            if profile.rating_count == 0 {
+                "-".into()
+            } else {
+                let average = profile.average_rating();
+                format!(
+                    "{}.{:02} / 5 ({} reviews)",
+                    average / 100,
+                    average % 100,
+                    profile.rating_count
+                )
+            },
+        ),
+    ];
+    if let Some(mint) = mint {
+        rows.push(("Mint", mint.to_string()));
+        rows.push((
+            "Earned",
+            earnings
+                .map_or(0, |earnings| earnings.total_earned)
+                .to_string(),
+        ));
+    }
+
+    for (label, value) in rows {
+        println!("{:<20}{}", label, value);
+    }
+}
+
+pub fn print_project_owner(
+    profile: &ProjectOwnerProfile,
+    mint: Option<&Pubkey>,
+    volume: Option<&ProjectOwnerVolume>,
+) {
+    let mut rows: Vec<(&str, String)> = vec![
+        ("Project owner", profile.project_owner.to_string()),
+        ("Deals funded", profile.deals_funded.to_string()),
+        ("Deals rejected", profile.deals_rejected.to_string()),
+        ("Deals completed", profile.deals_completed.to_string()),
+        ("Disputes opened", profile.disputes_opened.to_string()),
+        ("Disputes lost", profile.disputes_lost.to_string()),
+    ];
+    if let Some(mint) = mint {
+        rows.push(("Mint", mint.to_string()));
+        rows.push((
+            "Funded",
+            volume.map_or(0, |volume| volume.total_funded).to_string(),
+        ));
+        rows.push((
+            "Avg. deal size",
+            volume
+                .map_or(0, |volume| volume.average_deal_size())
+                .to_string(),
+        ));
+    }
+
+    for (label, value) in rows {
+        println!("{:<20}{}", label, value);
+    }
+}
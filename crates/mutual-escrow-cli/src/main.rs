@@ -0,0 +1,1235 @@
+//! Admin and operator CLI for the mutual escrow program.
+
+mod display;
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, bail, Context, Result};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use mutual_escrow_client::instructions::CreateDealArgs;
+use mutual_escrow_client::{
+    decode, filters, get_associated_token_address, instructions, order_id_from_str, pda,
+    ClaimableBreakdown, Deal, DealStatus, DisputeOutcome, DisputeReason, EligibilityScenario,
+    EligibilityStatus, Escrow, KolEarnings, KolProfile, MintStats, ProgramStats,
+    ProjectOwnerProfile, ProjectOwnerVolume, RevocationReason, VestingProjection, VestingTerms,
+    VestingType, PROGRAM_ID,
+};
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{read_keypair_file, Keypair, Signer};
+use solana_sdk::transaction::Transaction;
+
+#[derive(Parser)]
+#[command(name = "mutual-escrow", version, about)]
+struct Cli {
+    /// RPC endpoint, a local validator by default
+    #[arg(
+        long,
+        short = 'u',
+        global = true,
+        default_value = "http://127.0.0.1:8899"
+    )]
+    url: String,
+
+    /// Keypair signing and paying for transactions
+    #[arg(long, short = 'k', global = true)]
+    keypair: Option<String>,
+
+    /// Simulate transactions and print their logs instead of sending them
+    #[arg(long, global = true)]
+    dry_run: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Create the escrow account, making the signer its admin
+    Initialize {
+        #[arg(long)]
+        max_claimable_after_obligation: u8,
+    },
+    /// Change the share of a deal claimable on partial eligibility
+    UpdateMaxClaimablePercentage { new_percentage: u8 },
+    /// Move a deal forward to a higher eligibility status
+    SetEligibilityStatus {
+        deal: Pubkey,
+        status: EligibilityArg,
+    },
+    /// Move a deal back to a lower eligibility status
+    RevokeEligibility {
+        deal: Pubkey,
+        status: EligibilityArg,
+        reason: RevocationArg,
+    },
+    /// Move several deals to the same eligibility status in one transaction
+    BatchSetEligibilityStatus {
+        status: EligibilityArg,
+        #[arg(required = true)]
+        deals: Vec<Pubkey>,
+    },
+    /// Set the share of every cranked claim of the mint paid to the cranker, in bps (at most 100)
+    SetCrankFee { mint: Pubkey, fee_bps: u16 },
+    /// Fund a new deal from the signer's associated token account
+    CreateDeal(CreateArgs),
+    /// Accept a deal on the KOL's behalf
+    AcceptDeal { deal: Pubkey },
+    /// Reject a deal, refunding the project owner
+    RejectDeal { deal: Pubkey },
+    /// Propose new terms for a deal that hasn't been accepted yet
+    ProposeAmendment {
+        deal: Pubkey,
+        amount: u64,
+        #[arg(long, value_enum, default_value = "time")]
+        vesting: VestingArg,
+        /// Seconds of linear vesting once fully eligible, for time vesting
+        #[arg(long, default_value_t = 0)]
+        vesting_duration: i64,
+        /// Unix timestamp after which the proposal can't be accepted
+        #[arg(long)]
+        deadline: i64,
+    },
+    /// Accept the pending amendment of a deal, co-signed by the project owner when it raises the amount
+    AcceptAmendment { deal: Pubkey },
+    /// Add tokens to a deal from the signer's associated token account
+    AddToDeal {
+        deal: Pubkey,
+        amount: u64,
+        /// Claimable without vesting
+        #[arg(long)]
+        bonus: bool,
+    },
+    /// Pay out what the KOL can claim
+    ResolveDeal { deal: Pubkey },
+    /// Pay out what the signing KOL can claim on several deals of a mint in one transaction
+    ResolveMany {
+        mint: Pubkey,
+        #[arg(required = true)]
+        deals: Vec<Pubkey>,
+    },
+    /// Pay out what the KOL can claim into associated token accounts, collecting the crank fee
+    CrankClaim { deal: Pubkey },
+    /// Terminate a breached deal, refunding the unvested tokens
+    Clawback {
+        deal: Pubkey,
+        /// Pay the KOL what has already vested
+        #[arg(long)]
+        pay_vested: bool,
+    },
+    /// Freeze a deal until the admin settles it
+    OpenDispute { deal: Pubkey, reason: DisputeArg },
+    /// Settle a disputed deal
+    SettleDispute { deal: Pubkey, outcome: OutcomeArg },
+    /// Return the KOL's stake of a completed deal to their payout address
+    ReleaseKolStake { deal: Pubkey },
+    /// Set where the signing KOL's payouts go, only for one deal with --deal
+    SetPayoutAddress {
+        payout_owner: Pubkey,
+        #[arg(long)]
+        deal: Option<Pubkey>,
+    },
+    /// Rate the other party of a finished deal, from 1 to 5
+    SubmitReview {
+        deal: Pubkey,
+        rating: u8,
+        /// Hex-encoded SHA-256 of the off-chain review, zeroes when left out
+        #[arg(long, value_parser = parse_content_hash)]
+        content_hash: Option<[u8; 32]>,
+    },
+    /// Print what a deal's KOL can claim now and when the rest unlocks, always simulated
+    CheckClaimableAmount { deal: Pubkey },
+    /// Project the vested amount of a deal, or of new terms, over time, always simulated
+    SimulateVesting(SimulateArgs),
+    /// Create the ledger of a mint funded before ledgers existed, from its vault balance
+    InitMintLedger { mint: Pubkey },
+    /// Grow a deal or profile created by an older program version to its current size
+    MigrateAccount { account: Pubkey },
+    /// Check that the vault of a mint covers what its deals still owe, always simulated
+    VerifySolvency { mint: Pubkey },
+    /// Print the program-wide deal counters, and the volume of a mint when given
+    Stats { mint: Option<Pubkey> },
+    /// Print a KOL's reputation, and their earnings in a mint when given
+    Kol {
+        kol: Pubkey,
+        #[arg(long)]
+        mint: Option<Pubkey>,
+    },
+    /// Print a project owner's payment-reliability history, and their volume in a mint when given
+    ProjectOwner {
+        project_owner: Pubkey,
+        #[arg(long)]
+        mint: Option<Pubkey>,
+    },
+    /// Inspect a single deal
+    #[command(subcommand)]
+    Deal(DealCommand),
+    /// Inspect many deals
+    #[command(subcommand)]
+    Deals(DealsCommand),
+}
+
+#[derive(Args)]
+struct CreateArgs {
+    order_id: String,
+    kol: Pubkey,
+    mint: Pubkey,
+    amount: u64,
+    #[arg(long, value_enum, default_value = "time")]
+    vesting: VestingArg,
+    /// Seconds of linear vesting once fully eligible, for time vesting
+    #[arg(long, default_value_t = 0)]
+    vesting_duration: i64,
+    /// Paid --referrer-bps of every claim
+    #[arg(long, requires = "referrer_bps")]
+    referrer: Option<Pubkey>,
+    #[arg(long, default_value_t = 0, requires = "referrer")]
+    referrer_bps: u16,
+    /// Bond the KOL posts when accepting
+    #[arg(long)]
+    kol_stake: Option<u64>,
+}
+
+impl CreateArgs {
+    fn create_deal_args(&self) -> CreateDealArgs {
+        CreateDealArgs {
+            order_id: order_id_from_str(&self.order_id),
+            amount: self.amount,
+            vesting_type: self.vesting.into(),
+            vesting_duration: self.vesting_duration,
+            referrer: self.referrer,
+            referrer_bps: self.referrer_bps,
+            required_kol_stake: self.kol_stake,
+        }
+    }
+}
+
+#[derive(Args)]
+struct SimulateArgs {
+    /// Existing deal to project, instead of the terms below
+    #[arg(long, conflicts_with_all = ["amount", "vesting", "vesting_duration"])]
+    deal: Option<Pubkey>,
+    #[arg(long, required_unless_present = "deal")]
+    amount: Option<u64>,
+    #[arg(long, value_enum, default_value = "time")]
+    vesting: VestingArg,
+    #[arg(long, default_value_t = 0)]
+    vesting_duration: i64,
+    /// Eligibility status, and when it is reached, e.g. fully-eligible@1700000000. Repeat to
+    /// compare scenarios
+    #[arg(long = "scenario", required = true, value_parser = parse_scenario)]
+    scenarios: Vec<EligibilityScenario>,
+    /// Unix timestamp to project at, repeat for several
+    #[arg(long = "at", required = true)]
+    timestamps: Vec<i64>,
+}
+
+impl SimulateArgs {
+    fn instruction(&self) -> Instruction {
+        let terms = match self.deal {
+            Some(_) => None,
+            None => self.amount.map(|amount| VestingTerms {
+                amount,
+                vesting_type: self.vesting.into(),
+                vesting_duration: self.vesting_duration,
+            }),
+        };
+        instructions::simulate_vesting(
+            self.deal,
+            terms,
+            self.scenarios.clone(),
+            self.timestamps.clone(),
+        )
+    }
+}
+
+// `STATUS[@TIMESTAMP]`, the deal's own timestamps being kept without one
+fn parse_scenario(value: &str) -> Result<EligibilityScenario, String> {
+    let (status, eligible_time) = match value.split_once('@') {
+        Some((status, time)) => (
+            status,
+            time.parse()
+                .map_err(|err| format!("invalid timestamp {}: {}", time, err))?,
+        ),
+        None => (value, 0),
+    };
+    Ok(EligibilityScenario {
+        eligibility_status: EligibilityArg::from_str(status, true)?.into(),
+        eligible_time,
+    })
+}
+
+fn parse_content_hash(value: &str) -> Result<[u8; 32], String> {
+    if value.len() != 64 || !value.is_ascii() {
+        return Err("expected 64 hex characters".into());
+    }
+    let mut hash = [0u8; 32];
+    for (byte, pair) in hash.iter_mut().zip(value.as_bytes().chunks(2)) {
+        let pair = std::str::from_utf8(pair).unwrap();
+        *byte = u8::from_str_radix(pair, 16).map_err(|err| format!("{}: {}", pair, err))?;
+    }
+    Ok(hash)
+}
+
+// Deals of a `resolve_many` batch, with the receipt token account the KOL holds for each
+// deal that has a receipt, and their project owner
+fn batch_deals(kol: &Pubkey, deals: &[(Pubkey, Deal)]) -> Vec<(Pubkey, Option<Pubkey>, Pubkey)> {
+    deals
+        .iter()
+        .map(|(address, deal)| {
+            let receipt_token_account = (deal.receipt_mint != Pubkey::default())
+                .then(|| get_associated_token_address(kol, &deal.receipt_mint));
+            (*address, receipt_token_account, deal.project_owner)
+        })
+        .collect()
+}
+
+#[derive(Subcommand)]
+enum DealCommand {
+    /// Print a deal, found by order id
+    Show(ShowArgs),
+}
+
+#[derive(Args)]
+struct ShowArgs {
+    order_id: String,
+    /// With --kol and --mint, derives the deal address instead of searching for the order id
+    #[arg(long, requires_all = ["kol", "mint"])]
+    owner: Option<Pubkey>,
+    #[arg(long)]
+    kol: Option<Pubkey>,
+    #[arg(long)]
+    mint: Option<Pubkey>,
+}
+
+#[derive(Subcommand)]
+enum DealsCommand {
+    /// List deals matching every given filter
+    List {
+        #[arg(long)]
+        kol: Option<Pubkey>,
+        #[arg(long)]
+        owner: Option<Pubkey>,
+        #[arg(long)]
+        status: Option<StatusArg>,
+    },
+}
+
+// Mirrors `EligibilityStatus`
+#[allow(clippy::enum_variant_names)]
+#[derive(Clone, Copy, ValueEnum)]
+enum EligibilityArg {
+    NotEligible,
+    PartiallyEligible,
+    FullyEligible,
+}
+
+impl From<EligibilityArg> for EligibilityStatus {
+    fn from(arg: EligibilityArg) -> Self {
+        match arg {
+            EligibilityArg::NotEligible => EligibilityStatus::NotEligible,
+            EligibilityArg::PartiallyEligible => EligibilityStatus::PartiallyEligible,
+            EligibilityArg::FullyEligible => EligibilityStatus::FullyEligible,
+        }
+    }
+}
+
+// Mirrors `VestingType`
+#[derive(Clone, Copy, ValueEnum)]
+enum VestingArg {
+    Time,
+    Marketcap,
+    None,
+}
+
+impl From<VestingArg> for VestingType {
+    fn from(arg: VestingArg) -> Self {
+        match arg {
+            VestingArg::Time => VestingType::Time,
+            VestingArg::Marketcap => VestingType::Marketcap,
+            VestingArg::None => VestingType::None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum RevocationArg {
+    PostDeleted,
+    TermsBreached,
+    VerificationError,
+    Other,
+}
+
+impl From<RevocationArg> for RevocationReason {
+    fn from(arg: RevocationArg) -> Self {
+        match arg {
+            RevocationArg::PostDeleted => RevocationReason::PostDeleted,
+            RevocationArg::TermsBreached => RevocationReason::TermsBreached,
+            RevocationArg::VerificationError => RevocationReason::VerificationError,
+            RevocationArg::Other => RevocationReason::Other,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum DisputeArg {
+    Unresolved,
+    Other,
+}
+
+impl From<DisputeArg> for DisputeReason {
+    fn from(arg: DisputeArg) -> Self {
+        match arg {
+            DisputeArg::Unresolved => DisputeReason::Unresolved,
+            DisputeArg::Other => DisputeReason::Other,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum OutcomeArg {
+    KolWins,
+    ProjectOwnerWins,
+}
+
+impl From<OutcomeArg> for DisputeOutcome {
+    fn from(arg: OutcomeArg) -> Self {
+        match arg {
+            OutcomeArg::KolWins => DisputeOutcome::KolWins,
+            OutcomeArg::ProjectOwnerWins => DisputeOutcome::ProjectOwnerWins,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum StatusArg {
+    Created,
+    Accepted,
+    Rejected,
+    PartialCompleted,
+    Completed,
+    Disputed,
+    Resolved,
+    ClawedBack,
+}
+
+impl From<StatusArg> for DealStatus {
+    fn from(arg: StatusArg) -> Self {
+        match arg {
+            StatusArg::Created => DealStatus::Created,
+            StatusArg::Accepted => DealStatus::Accepted,
+            StatusArg::Rejected => DealStatus::Rejected,
+            StatusArg::PartialCompleted => DealStatus::PartialCompleted,
+            StatusArg::Completed => DealStatus::Completed,
+            StatusArg::Disputed => DealStatus::Disputed,
+            StatusArg::Resolved => DealStatus::Resolved,
+            StatusArg::ClawedBack => DealStatus::ClawedBack,
+        }
+    }
+}
+
+struct Client {
+    rpc: RpcClient,
+    keypair_path: String,
+    dry_run: bool,
+}
+
+impl Client {
+    fn signer(&self) -> Result<Keypair> {
+        read_keypair_file(&self.keypair_path)
+            .map_err(|err| anyhow!("reading keypair {}: {}", self.keypair_path, err))
+    }
+
+    fn fetch_deal(&self, address: &Pubkey) -> Result<Deal> {
+        let data = self
+            .rpc
+            .get_account_data(address)
+            .with_context(|| format!("fetching deal {}", address))?;
+        decode::deal(&data).map_err(|err| anyhow!("decoding deal {}: {}", address, err))
+    }
+
+    fn fetch_escrow(&self) -> Result<Escrow> {
+        let data = self
+            .rpc
+            .get_account_data(&pda::escrow())
+            .context("fetching the escrow account, is the program initialized?")?;
+        decode::escrow(&data).map_err(|err| anyhow!("decoding escrow: {}", err))
+    }
+
+    fn account_exists(&self, address: &Pubkey) -> Result<bool> {
+        Ok(self
+            .rpc
+            .get_account_with_commitment(address, self.rpc.commitment())?
+            .value
+            .is_some())
+    }
+
+    // Who a claim of the deal goes to, and the receipt token account proving it when the
+    // receipt changed hands. Mirrors `claim_recipient` in the program
+    fn claim_recipient(&self, deal: &Deal) -> Result<(Pubkey, Option<Pubkey>)> {
+        let mut receipt_token_account = None;
+        if deal.receipt_mint != Pubkey::default() {
+            let holders = self.rpc.get_token_largest_accounts(&deal.receipt_mint)?;
+            let holder = holders
+                .iter()
+                .find(|holder| holder.amount.amount == "1")
+                .ok_or_else(|| anyhow!("no holder found for receipt {}", deal.receipt_mint))?;
+            let address: Pubkey = holder.address.parse()?;
+            let account = self
+                .rpc
+                .get_token_account(&address)?
+                .ok_or_else(|| anyhow!("receipt token account {} not found", holder.address))?;
+            let owner: Pubkey = account.owner.parse()?;
+
+            receipt_token_account = Some(address);
+            if owner != deal.kol {
+                return Ok((owner, receipt_token_account));
+            }
+        }
+
+        Ok((self.kol_payout_owner(deal), receipt_token_account))
+    }
+
+    // Where the KOL's own payouts go. Mirrors `kol_payout_owner` in the program
+    fn kol_payout_owner(&self, deal: &Deal) -> Pubkey {
+        if deal.payout_owner != Pubkey::default() {
+            return deal.payout_owner;
+        }
+        let profile = match self.rpc.get_account_data(&pda::kol_profile(&deal.kol)) {
+            Ok(data) => decode::account::<KolProfile>(&data).ok(),
+            Err(_) => None,
+        };
+        profile
+            .map(|profile| profile.payout_owner)
+            .filter(|payout_owner| *payout_owner != Pubkey::default())
+            .unwrap_or(deal.kol)
+    }
+
+    // Return data of a view instruction, always simulated
+    fn view(&self, instruction: Instruction) -> Result<Vec<u8>> {
+        let signer = self.signer()?;
+        let blockhash = self.rpc.get_latest_blockhash()?;
+        let transaction = Transaction::new_signed_with_payer(
+            &[instruction],
+            Some(&signer.pubkey()),
+            &[&signer],
+            blockhash,
+        );
+
+        let result = self.rpc.simulate_transaction(&transaction)?.value;
+        if let Some(err) = result.err {
+            for log in result.logs.unwrap_or_default() {
+                println!("{}", log);
+            }
+            bail!("simulation failed: {}", err);
+        }
+        let return_data = result
+            .return_data
+            .ok_or_else(|| anyhow!("the program returned no data"))?;
+        Ok(STANDARD.decode(return_data.data.0)?)
+    }
+
+    fn send(&self, instructions: &[Instruction]) -> Result<()> {
+        let signer = self.signer()?;
+        let blockhash = self.rpc.get_latest_blockhash()?;
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&signer.pubkey()),
+            &[&signer],
+            blockhash,
+        );
+
+        if self.dry_run {
+            let result = self.rpc.simulate_transaction(&transaction)?.value;
+            for log in result.logs.unwrap_or_default() {
+                println!("{}", log);
+            }
+            if let Some(units) = result.units_consumed {
+                println!("Compute units consumed: {}", units);
+            }
+            return match result.err {
+                Some(err) => bail!("simulation failed: {}", err),
+                None => {
+                    println!("Simulation succeeded, nothing was sent");
+                    Ok(())
+                }
+            };
+        }
+
+        let signature = self
+            .rpc
+            .send_and_confirm_transaction_with_spinner(&transaction)?;
+        println!("Signature: {}", signature);
+        Ok(())
+    }
+}
+
+fn default_keypair_path() -> String {
+    let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
+    format!("{}/.config/solana/id.json", home)
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let ctx = Client {
+        rpc: RpcClient::new_with_commitment(cli.url, CommitmentConfig::confirmed()),
+        keypair_path: cli.keypair.unwrap_or_else(default_keypair_path),
+        dry_run: cli.dry_run,
+    };
+
+    match cli.command {
+        Command::Initialize {
+            max_claimable_after_obligation,
+        } => {
+            let admin = ctx.signer()?.pubkey();
+            ctx.send(&[instructions::initialize(
+                &admin,
+                max_claimable_after_obligation,
+            )])
+        }
+        Command::UpdateMaxClaimablePercentage { new_percentage } => {
+            let admin = ctx.signer()?.pubkey();
+            ctx.send(&[instructions::update_max_claimable_percentage(
+                &admin,
+                new_percentage,
+            )])
+        }
+        Command::SetEligibilityStatus { deal, status } => {
+            let admin = ctx.signer()?.pubkey();
+            ctx.send(&[instructions::set_eligibility_status(
+                &admin,
+                &deal,
+                status.into(),
+            )])
+        }
+        Command::RevokeEligibility {
+            deal,
+            status,
+            reason,
+        } => {
+            let admin = ctx.signer()?.pubkey();
+            ctx.send(&[instructions::revoke_eligibility(
+                &admin,
+                &deal,
+                status.into(),
+                reason.into(),
+            )])
+        }
+        Command::BatchSetEligibilityStatus { status, deals } => {
+            let admin = ctx.signer()?.pubkey();
+            let updates = deals
+                .into_iter()
+                .map(|deal| (deal, status.into()))
+                .collect();
+            ctx.send(&[instructions::batch_set_eligibility_status(&admin, updates)])
+        }
+        Command::SetCrankFee { mint, fee_bps } => {
+            let admin = ctx.signer()?.pubkey();
+            ctx.send(&[instructions::set_crank_fee(&admin, &mint, fee_bps)])
+        }
+        Command::CreateDeal(args) => {
+            let project_owner = ctx.signer()?.pubkey();
+            ctx.send(&[instructions::create_deal(
+                &project_owner,
+                &args.kol,
+                &args.mint,
+                args.create_deal_args(),
+            )])
+        }
+        Command::AcceptDeal { deal: address } => {
+            let deal = ctx.fetch_deal(&address)?;
+            let signer = ctx.signer()?.pubkey();
+            ctx.send(&[instructions::accept_deal(
+                &signer,
+                &address,
+                &deal,
+                Vec::new(),
+            )])
+        }
+        Command::RejectDeal { deal: address } => {
+            let deal = ctx.fetch_deal(&address)?;
+            let signer = ctx.signer()?.pubkey();
+            ctx.send(&[instructions::reject_deal(
+                &signer,
+                &address,
+                &deal.project_owner,
+                &deal.kol,
+                &deal.mint,
+            )])
+        }
+        Command::ProposeAmendment {
+            deal,
+            amount,
+            vesting,
+            vesting_duration,
+            deadline,
+        } => {
+            let signer = ctx.signer()?.pubkey();
+            ctx.send(&[instructions::propose_amendment(
+                &signer,
+                &deal,
+                amount,
+                vesting.into(),
+                vesting_duration,
+                deadline,
+            )])
+        }
+        Command::AcceptAmendment { deal: address } => {
+            let deal = ctx.fetch_deal(&address)?;
+            let signer = ctx.signer()?.pubkey();
+            ctx.send(&[instructions::accept_amendment(
+                &signer,
+                &address,
+                &deal.project_owner,
+                &deal.mint,
+            )])
+        }
+        Command::AddToDeal {
+            deal: address,
+            amount,
+            bonus,
+        } => {
+            let deal = ctx.fetch_deal(&address)?;
+            let project_owner = ctx.signer()?.pubkey();
+            ctx.send(&[instructions::add_to_deal(
+                &project_owner,
+                &address,
+                &deal.mint,
+                amount,
+                bonus,
+            )])
+        }
+        Command::ResolveDeal { deal: address } => {
+            let deal = ctx.fetch_deal(&address)?;
+            let (recipient, receipt_token_account) = ctx.claim_recipient(&deal)?;
+            let signer = ctx.signer()?.pubkey();
+            ctx.send(&[instructions::resolve_deal(
+                &signer,
+                &address,
+                &deal,
+                &get_associated_token_address(&recipient, &deal.mint),
+                receipt_token_account,
+            )])
+        }
+        Command::ResolveMany { mint, deals } => {
+            let deals = deals
+                .into_iter()
+                .map(|address| Ok((address, ctx.fetch_deal(&address)?)))
+                .collect::<Result<Vec<_>>>()?;
+            // The batch pays into a single token account
+            let payout_owner = ctx.kol_payout_owner(&deals[0].1);
+            if deals
+                .iter()
+                .any(|(_, deal)| ctx.kol_payout_owner(deal) != payout_owner)
+            {
+                bail!("the deals pay out to different addresses, resolve them one by one");
+            }
+            let kol = ctx.signer()?.pubkey();
+            ctx.send(&[instructions::resolve_many(
+                &kol,
+                &mint,
+                &get_associated_token_address(&payout_owner, &mint),
+                &batch_deals(&kol, &deals),
+            )])
+        }
+        Command::CrankClaim { deal: address } => {
+            let deal = ctx.fetch_deal(&address)?;
+            let (recipient, receipt_token_account) = ctx.claim_recipient(&deal)?;
+            let with_crank_fee = ctx.account_exists(&pda::crank_config(&deal.mint))?;
+            let cranker = ctx.signer()?.pubkey();
+            ctx.send(&[instructions::crank_claim(
+                &cranker,
+                &address,
+                &deal,
+                &recipient,
+                receipt_token_account,
+                with_crank_fee,
+            )])
+        }
+        Command::Clawback {
+            deal: address,
+            pay_vested,
+        } => {
+            let deal = ctx.fetch_deal(&address)?;
+            let (recipient, receipt_token_account) = ctx.claim_recipient(&deal)?;
+            let admin = ctx.signer()?.pubkey();
+            ctx.send(&[instructions::clawback(
+                &admin,
+                &address,
+                &deal,
+                &get_associated_token_address(&recipient, &deal.mint),
+                receipt_token_account,
+                pay_vested,
+            )])
+        }
+        Command::OpenDispute {
+            deal: address,
+            reason,
+        } => {
+            let deal = ctx.fetch_deal(&address)?;
+            let signer = ctx.signer()?.pubkey();
+            ctx.send(&[instructions::open_dispute(
+                &signer,
+                &address,
+                &deal.project_owner,
+                reason.into(),
+            )])
+        }
+        Command::SettleDispute {
+            deal: address,
+            outcome,
+        } => {
+            let deal = ctx.fetch_deal(&address)?;
+            let admin = ctx.signer()?.pubkey();
+            ctx.send(&[instructions::settle_dispute(
+                &admin,
+                &address,
+                &deal,
+                outcome.into(),
+            )])
+        }
+        Command::ReleaseKolStake { deal: address } => {
+            let deal = ctx.fetch_deal(&address)?;
+            let recipient = ctx.kol_payout_owner(&deal);
+            let signer = ctx.signer()?.pubkey();
+            ctx.send(&[instructions::release_kol_stake(
+                &signer, &address, &deal, &recipient,
+            )])
+        }
+        Command::SetPayoutAddress { payout_owner, deal } => {
+            let kol = ctx.signer()?.pubkey();
+            ctx.send(&[instructions::set_payout_address(&kol, deal, payout_owner)])
+        }
+        Command::SubmitReview {
+            deal: address,
+            rating,
+            content_hash,
+        } => {
+            let deal = ctx.fetch_deal(&address)?;
+            let reviewer = ctx.signer()?.pubkey();
+            ctx.send(&[instructions::submit_review(
+                &reviewer,
+                &address,
+                &deal,
+                rating,
+                content_hash.unwrap_or_default(),
+            )])
+        }
+        Command::CheckClaimableAmount { deal } => {
+            let data = ctx.view(instructions::check_claimable_amount(&deal))?;
+            let breakdown: ClaimableBreakdown = decode::return_data(&data)?;
+            display::print_breakdown(&deal, &breakdown);
+            Ok(())
+        }
+        Command::SimulateVesting(args) => {
+            let data = ctx.view(args.instruction())?;
+            let projections: Vec<VestingProjection> = decode::return_data(&data)?;
+            display::print_projections(&projections);
+            Ok(())
+        }
+        Command::InitMintLedger { mint } => {
+            let payer = ctx.signer()?.pubkey();
+            ctx.send(&[instructions::init_mint_ledger(&payer, &mint)])
+        }
+        Command::MigrateAccount { account } => {
+            let payer = ctx.signer()?.pubkey();
+            ctx.send(&[instructions::migrate_account(&payer, &account)])
+        }
+        Command::VerifySolvency { mint } => Client {
+            dry_run: true,
+            ..ctx
+        }
+        .send(&[instructions::verify_solvency(&mint)]),
+        Command::Stats { mint } => show_stats(&ctx, mint),
+        Command::Kol { kol, mint } => show_kol(&ctx, kol, mint),
+        Command::ProjectOwner {
+            project_owner,
+            mint,
+        } => show_project_owner(&ctx, project_owner, mint),
+        Command::Deal(DealCommand::Show(args)) => show_deal(&ctx, args),
+        Command::Deals(DealsCommand::List { kol, owner, status }) => {
+            list_deals(&ctx, kol, owner, status.map(Into::into))
+        }
+    }
+}
+
+fn deal_accounts(ctx: &Client, filters: Vec<RpcFilterType>) -> Result<Vec<(Pubkey, Deal)>> {
+    let mut all_filters = vec![RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
+        0,
+        filters::deal_discriminator().to_vec(),
+    ))];
+    all_filters.extend(filters);
+
+    let accounts = ctx.rpc.get_program_accounts_with_config(
+        &PROGRAM_ID,
+        RpcProgramAccountsConfig {
+            filters: Some(all_filters),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                ..RpcAccountInfoConfig::default()
+            },
+            ..RpcProgramAccountsConfig::default()
+        },
+    )?;
+
+    // Accounts that no longer decode, e.g. created by an older layout, are skipped but named
+    // on stderr so they don't silently vanish from listings
+    let mut deals = Vec::new();
+    for (address, account) in accounts {
+        match decode::deal(&account.data) {
+            Ok(deal) => deals.push((address, deal)),
+            Err(err) => eprintln!("Skipping deal {}: {} (see migrate-account)", address, err),
+        }
+    }
+    Ok(deals)
+}
+
+fn show_stats(ctx: &Client, mint: Option<Pubkey>) -> Result<()> {
+    let data = ctx
+        .rpc
+        .get_account_data(&pda::program_stats())
+        .context("fetching program stats, has a deal been created yet?")?;
+    let program_stats: ProgramStats =
+        decode::account(&data).map_err(|err| anyhow!("decoding program stats: {}", err))?;
+
+    let mint_stats: Option<MintStats> = match mint {
+        Some(mint) => {
+            let data = ctx
+                .rpc
+                .get_account_data(&pda::mint_stats(&mint))
+                .with_context(|| format!("fetching stats of mint {}", mint))?;
+            Some(decode::account(&data).map_err(|err| anyhow!("decoding mint stats: {}", err))?)
+        }
+        None => None,
+    };
+
+    display::print_stats(&program_stats, mint_stats.as_ref());
+    Ok(())
+}
+
+fn show_kol(ctx: &Client, kol: Pubkey, mint: Option<Pubkey>) -> Result<()> {
+    let data = ctx
+        .rpc
+        .get_account_data(&pda::kol_profile(&kol))
+        .with_context(|| format!("fetching the profile of {}, has it any deal yet?", kol))?;
+    let profile: KolProfile =
+        decode::account(&data).map_err(|err| anyhow!("decoding KOL profile: {}", err))?;
+
+    let earnings: Option<KolEarnings> = match mint {
+        Some(mint) => match ctx.rpc.get_account_data(&pda::kol_earnings(&kol, &mint)) {
+            Ok(data) => Some(
+                decode::account(&data).map_err(|err| anyhow!("decoding KOL earnings: {}", err))?,
+            ),
+            // Nothing claimed in that mint yet
+            Err(_) => None,
+        },
+        None => None,
+    };
+
+    display::print_kol(&profile, mint.as_ref(), earnings.as_ref());
+    Ok(())
+}
+
+fn show_project_owner(ctx: &Client, project_owner: Pubkey, mint: Option<Pubkey>) -> Result<()> {
+    let data = ctx
+        .rpc
+        .get_account_data(&pda::project_owner_profile(&project_owner))
+        .with_context(|| {
+            format!(
+                "fetching the profile of {}, has it funded any deal yet?",
+                project_owner
+            )
+        })?;
+    let profile: ProjectOwnerProfile =
+        decode::account(&data).map_err(|err| anyhow!("decoding project owner profile: {}", err))?;
+
+    let volume: Option<ProjectOwnerVolume> = match mint {
+        Some(mint) => match ctx
+            .rpc
+            .get_account_data(&pda::project_owner_volume(&project_owner, &mint))
+        {
+            Ok(data) => Some(
+                decode::account(&data)
+                    .map_err(|err| anyhow!("decoding project owner volume: {}", err))?,
+            ),
+            // Nothing funded in that mint yet
+            Err(_) => None,
+        },
+        None => None,
+    };
+
+    display::print_project_owner(&profile, mint.as_ref(), volume.as_ref());
+    Ok(())
+}
+
+fn show_deal(ctx: &Client, args: ShowArgs) -> Result<()> {
+    let order_id = order_id_from_str(&args.order_id);
+
+    let (address, deal) = match (args.owner, args.kol, args.mint) {
+        (Some(owner), Some(kol), Some(mint)) => {
+            let address = pda::deal(&order_id, &owner, &kol, &mint);
+            (address, ctx.fetch_deal(&address)?)
+        }
+        _ => {
+            let mut deals = deal_accounts(
+                ctx,
+                vec![RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
+                    filters::ORDER_ID_OFFSET,
+                    order_id.to_vec(),
+                ))],
+            )?;
+            match deals.len() {
+                0 => bail!("no deal found for order id {}", args.order_id),
+                1 => deals.remove(0),
+                _ => bail!(
+                    "{} deals share order id {}, pass --owner, --kol and --mint",
+                    deals.len(),
+                    args.order_id
+                ),
+            }
+        }
+    };
+
+    let escrow = ctx.fetch_escrow()?;
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs() as i64);
+
+    display::print_deal(&address, &deal, &escrow, now);
+    Ok(())
+}
+
+fn list_deals(
+    ctx: &Client,
+    kol: Option<Pubkey>,
+    owner: Option<Pubkey>,
+    status: Option<DealStatus>,
+) -> Result<()> {
+    let mut filters = Vec::new();
+    if let Some(kol) = kol {
+        filters.push(RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
+            filters::KOL_OFFSET,
+            kol.to_bytes().to_vec(),
+        )));
+    }
+    if let Some(owner) = owner {
+        filters.push(RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
+            filters::PROJECT_OWNER_OFFSET,
+            owner.to_bytes().to_vec(),
+        )));
+    }
+    if let Some(status) = status {
+        filters.push(RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
+            filters::STATUS_OFFSET,
+            vec![status as u8],
+        )));
+    }
+
+    let mut deals = deal_accounts(ctx, filters)?;
+    deals.sort_by_key(|(_, deal)| deal.start_time);
+    display::print_deal_list(&deals);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::AccountDeserialize;
+
+    fn parse(args: &[&str]) -> Result<Command, clap::Error> {
+        Cli::try_parse_from(std::iter::once("mutual-escrow").chain(args.iter().copied()))
+            .map(|cli| cli.command)
+    }
+
+    #[test]
+    fn create_deal_builds_the_parsed_terms() {
+        let (project_owner, kol, mint, referrer) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let (kol_arg, mint_arg, referrer_arg) =
+            (kol.to_string(), mint.to_string(), referrer.to_string());
+        let command = parse(&[
+            "create-deal",
+            "order-1",
+            &kol_arg,
+            &mint_arg,
+            "1000",
+            "--vesting-duration",
+            "86400",
+            "--referrer",
+            &referrer_arg,
+            "--referrer-bps",
+            "250",
+            "--kol-stake",
+            "100",
+        ])
+        .unwrap();
+        let Command::CreateDeal(args) = command else {
+            panic!("expected create-deal");
+        };
+
+        let instruction = instructions::create_deal(
+            &project_owner,
+            &args.kol,
+            &args.mint,
+            args.create_deal_args(),
+        );
+        let expected = instructions::create_deal(
+            &project_owner,
+            &kol,
+            &mint,
+            CreateDealArgs {
+                order_id: order_id_from_str("order-1"),
+                amount: 1_000,
+                vesting_type: VestingType::Time,
+                vesting_duration: 86_400,
+                referrer: Some(referrer),
+                referrer_bps: 250,
+                required_kol_stake: Some(100),
+            },
+        );
+        assert_eq!(instruction, expected);
+        assert_eq!(
+            instruction.accounts[1].pubkey,
+            pda::deal(&order_id_from_str("order-1"), &project_owner, &kol, &mint)
+        );
+
+        // A referral fee needs a referrer
+        assert!(parse(&[
+            "create-deal",
+            "order-1",
+            &kol_arg,
+            &mint_arg,
+            "1000",
+            "--referrer-bps",
+            "250"
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn simulate_vesting_takes_a_deal_or_terms() {
+        let Command::SimulateVesting(args) = parse(&[
+            "simulate-vesting",
+            "--amount",
+            "1000",
+            "--vesting",
+            "none",
+            "--scenario",
+            "fully-eligible@100",
+            "--scenario",
+            "partially-eligible",
+            "--at",
+            "50",
+            "--at",
+            "150",
+        ])
+        .unwrap() else {
+            panic!("expected simulate-vesting");
+        };
+        let expected = instructions::simulate_vesting(
+            None,
+            Some(VestingTerms {
+                amount: 1_000,
+                vesting_type: VestingType::None,
+                vesting_duration: 0,
+            }),
+            vec![
+                EligibilityScenario {
+                    eligibility_status: EligibilityStatus::FullyEligible,
+                    eligible_time: 100,
+                },
+                EligibilityScenario {
+                    eligibility_status: EligibilityStatus::PartiallyEligible,
+                    eligible_time: 0,
+                },
+            ],
+            vec![50, 150],
+        );
+        assert_eq!(args.instruction(), expected);
+
+        let deal = Pubkey::new_unique().to_string();
+        let with_deal = ["--scenario", "not-eligible", "--at", "1"];
+        assert!(parse(&[&["simulate-vesting", "--deal", &deal][..], &with_deal].concat()).is_ok());
+        assert!(parse(&[&["simulate-vesting"][..], &with_deal].concat()).is_err());
+        assert!(parse(
+            &[
+                &["simulate-vesting", "--deal", &deal, "--amount", "1"][..],
+                &with_deal
+            ]
+            .concat()
+        )
+        .is_err());
+        assert!(parse(&[
+            "simulate-vesting",
+            "--amount",
+            "1",
+            "--scenario",
+            "eligible",
+            "--at",
+            "1"
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn review_content_hashes_are_hex() {
+        let deal = Pubkey::new_unique().to_string();
+        let hash = "ab".repeat(32);
+        let Command::SubmitReview { content_hash, .. } =
+            parse(&["submit-review", &deal, "5", "--content-hash", &hash]).unwrap()
+        else {
+            panic!("expected submit-review");
+        };
+        assert_eq!(content_hash, Some([0xab; 32]));
+
+        assert!(parse(&["submit-review", &deal, "5", "--content-hash", "abcd"]).is_err());
+        assert!(parse(&[
+            "submit-review",
+            &deal,
+            "5",
+            "--content-hash",
+            &"zz".repeat(32)
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn resolve_many_passes_receipts_and_project_owners() {
+        let mint = Pubkey::new_unique().to_string();
+        assert!(parse(&["resolve-many", &mint]).is_err());
+
+        let kol = Pubkey::new_unique();
+        let zeroed = vec![0; 8 + std::mem::size_of::<Deal>()];
+        let mut deal = Deal::try_deserialize_unchecked(&mut zeroed.as_slice()).unwrap();
+        deal.kol = kol;
+        deal.project_owner = Pubkey::new_unique();
+        let mut with_receipt = deal.clone();
+        with_receipt.receipt_mint = Pubkey::new_unique();
+        let deals = [
+            (Pubkey::new_unique(), deal),
+            (Pubkey::new_unique(), with_receipt),
+        ];
+
+        let batch = batch_deals(&kol, &deals);
+        assert_eq!(batch[0], (deals[0].0, None, deals[0].1.project_owner));
+        assert_eq!(
+            batch[1],
+            (
+                deals[1].0,
+                Some(get_associated_token_address(&kol, &deals[1].1.receipt_mint)),
+                deals[1].1.project_owner
+            )
+        );
+    }
+}
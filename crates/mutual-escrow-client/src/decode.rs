@@ -0,0 +1,188 @@
+//! Decoders for program accounts, events and instruction return data.
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::{AccountDeserialize, AnchorDeserialize, Discriminator};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+
+use crate::*;
+
+// Any program account, checking its discriminator
+pub fn account<T: AccountDeserialize>(mut data: &[u8]) -> anchor_lang::Result<T> {
+    T::try_deserialize(&mut data)
+}
+
+pub fn deal(data: &[u8]) -> anchor_lang::Result<Deal> {
+    account(data)
+}
+
+pub fn escrow(data: &[u8]) -> anchor_lang::Result<Escrow> {
+    account(data)
+}
+
+// Value returned by a view instruction such as `check_claimable_amount` or `simulate_vesting`
+pub fn return_data<T: AnchorDeserialize>(data: &[u8]) -> std::io::Result<T> {
+    T::try_from_slice(data)
+}
+
+macro_rules! program_events {
+    ($($name:ident),* $(,)?) => {
+        // Every event the program emits
+        pub enum Event {
+            $($name($name),)*
+        }
+
+        impl Event {
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $(Event::$name(_) => stringify!($name),)*
+                }
+            }
+        }
+
+        // Event from the discriminator-prefixed bytes of a `Program data:` log
+        pub fn event(data: &[u8]) -> Option<Event> {
+            if data.len() < 8 {
+                return None;
+            }
+            let (discriminator, mut body) = data.split_at(8);
+
+            $(
+                if discriminator == $name::DISCRIMINATOR {
+                    return $name::deserialize(&mut body).ok().map(Event::$name);
+                }
+            )*
+            None
+        }
+    };
+}
+
+program_events!(
+    DealCreated,
+    DealStatusChanged,
+    MaxClaimablePercentageUpdated,
+    DealResolved,
+    CrankFeeUpdated,
+    DealReceiptMinted,
+    EligibilityStatusUpdated,
+    EligibilityRevoked,
+    AmendmentProposed,
+    AmendmentAccepted,
+    DealToppedUp,
+    DealClawedBack,
+    PayoutAddressUpdated,
+    DisputeOpened,
+    DisputeSettled,
+    ProjectOwnerProfileUpdated,
+    ProjectOwnerVolumeUpdated,
+    ReviewSubmitted,
+    KolStakePosted,
+    KolStakeReleased,
+    KolStakeSlashed,
+);
+
+// Events emitted by the program in the logs of a transaction, in order. Logs of other
+// programs invoked in the same transaction are skipped
+pub fn events_from_logs<S: AsRef<str>>(logs: &[S]) -> Vec<Event> {
+    let program_id = PROGRAM_ID.to_string();
+    let mut invocations: Vec<bool> = Vec::new();
+    let mut events = Vec::new();
+
+    for log in logs.iter().map(AsRef::as_ref) {
+        if let Some(data) = log.strip_prefix("Program data: ") {
+            if invocations.last() != Some(&true) {
+                continue;
+            }
+            if let Some(event) = STANDARD.decode(data).ok().and_then(|bytes| event(&bytes)) {
+                events.push(event);
+            }
+            continue;
+        }
+
+        // Track which program is running, `msg!` output can't be confused with these
+        let Some(status) = log.strip_prefix("Program ") else {
+            continue;
+        };
+        if status.starts_with("log: ") || status.starts_with("return: ") {
+            continue;
+        }
+        if let Some((program, _)) = status.split_once(" invoke [") {
+            invocations.push(program == program_id);
+        } else if status.ends_with(" success") || status.contains(" failed: ") {
+            invocations.pop();
+        }
+    }
+
+    events
+}
+
+// Deal PDA an event refers to, when it refers to one
+pub fn event_deal(event: &Event) -> Option<Pubkey> {
+    match event {
+        Event::DealCreated(e) => Some(e.deal),
+        Event::DealStatusChanged(e) => Some(e.deal),
+        Event::DealResolved(e) => Some(e.deal),
+        Event::DealReceiptMinted(e) => Some(e.deal),
+        Event::EligibilityStatusUpdated(e) => Some(e.deal),
+        Event::EligibilityRevoked(e) => Some(e.deal),
+        Event::AmendmentProposed(e) => Some(e.deal),
+        Event::AmendmentAccepted(e) => Some(e.deal),
+        Event::DealToppedUp(e) => Some(e.deal),
+        Event::DealClawedBack(e) => Some(e.deal),
+        Event::PayoutAddressUpdated(e) => e.deal,
+        Event::DisputeOpened(e) => Some(e.deal),
+        Event::DisputeSettled(e) => Some(e.deal),
+        Event::ReviewSubmitted(e) => Some(e.deal),
+        Event::KolStakePosted(e) => Some(e.deal),
+        Event::KolStakeReleased(e) => Some(e.deal),
+        Event::KolStakeSlashed(e) => Some(e.deal),
+        Event::MaxClaimablePercentageUpdated(_)
+        | Event::CrankFeeUpdated(_)
+        | Event::ProjectOwnerProfileUpdated(_)
+        | Event::ProjectOwnerVolumeUpdated(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::AnchorSerialize;
+
+    fn program_log(event: &impl anchor_lang::Event) -> String {
+        format!("Program data: {}", STANDARD.encode(event.data()))
+    }
+
+    #[test]
+    fn decodes_program_events_only() {
+        let created = DealCreated {
+            order_id: [7; 16],
+            deal: Pubkey::new_unique(),
+            project_owner: Pubkey::new_unique(),
+            kol: Pubkey::new_unique(),
+            amount: 500,
+        };
+        let other_program = Pubkey::new_unique();
+        let logs = vec![
+            format!("Program {} invoke [1]", PROGRAM_ID),
+            "Program log: Instruction: CreateDeal".to_string(),
+            format!("Program {} invoke [2]", other_program),
+            program_log(&MaxClaimablePercentageUpdated {
+                old_percentage: 1,
+                new_percentage: 2,
+            }),
+            format!("Program {} success", other_program),
+            program_log(&created),
+            format!("Program {} success", PROGRAM_ID),
+        ];
+
+        let events = events_from_logs(&logs);
+        assert_eq!(events.len(), 1);
+        match &events[0] {
+            Event::DealCreated(event) => {
+                assert_eq!(event.try_to_vec().unwrap(), created.try_to_vec().unwrap());
+            }
+            other => panic!("unexpected event {}", other.name()),
+        }
+        assert_eq!(event_deal(&events[0]), Some(created.deal));
+    }
+}
@@ -0,0 +1,18 @@
+//! Byte offsets of `Deal` fields in account data, for `getProgramAccounts` memcmp filters.
+
+use anchor_lang::Discriminator;
+
+use crate::Deal;
+
+pub const ORDER_ID_OFFSET: usize = 8;
+pub const PROJECT_OWNER_OFFSET: usize = ORDER_ID_OFFSET + 16;
+pub const KOL_OFFSET: usize = PROJECT_OWNER_OFFSET + 32;
+pub const MINT_OFFSET: usize = KOL_OFFSET + 32;
+// Past amount, released_amount, vesting_type, vesting_duration, start_time, accept_time
+// and done_obligation_time
+pub const STATUS_OFFSET: usize = MINT_OFFSET + 32 + 8 + 8 + 1 + 8 + 8 + 8 + 8;
+
+// Every `Deal` account starts with this
+pub fn deal_discriminator() -> [u8; 8] {
+    Deal::DISCRIMINATOR
+}
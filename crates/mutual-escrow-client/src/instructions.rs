@@ -0,0 +1,978 @@
+//! One builder per program instruction. Program derived accounts are filled in from the
+//! seeds, project owner, referrer and payee token accounts are their associated token accounts.
+
+use anchor_lang::prelude::{AccountMeta, Pubkey};
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::sysvar;
+use anchor_lang::{system_program, InstructionData, ToAccountMetas};
+use anchor_spl::associated_token::{self, get_associated_token_address};
+use anchor_spl::token;
+use mutual_escrow::{accounts, instruction};
+
+use crate::{
+    pda, Deal, DisputeOutcome, DisputeReason, EligibilityScenario, EligibilityStatus, PayeeShare,
+    RevocationReason, VestingTerms, VestingType, PROGRAM_ID,
+};
+
+fn build(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    build_with_remaining(accounts, data, Vec::new())
+}
+
+fn build_with_remaining(
+    accounts: impl ToAccountMetas,
+    data: impl InstructionData,
+    remaining_accounts: Vec<AccountMeta>,
+) -> Instruction {
+    let mut metas = accounts.to_account_metas(None);
+    metas.extend(remaining_accounts);
+
+    Instruction {
+        program_id: PROGRAM_ID,
+        accounts: metas,
+        data: data.data(),
+    }
+}
+
+// Payee token accounts `resolve_deal`, `crank_claim` and `clawback` expect after the named accounts
+fn payee_token_accounts(deal: &Deal) -> Vec<AccountMeta> {
+    deal.payees[..deal.payee_count as usize]
+        .iter()
+        .map(|payee| {
+            AccountMeta::new(
+                get_associated_token_address(&payee.wallet, &deal.mint),
+                false,
+            )
+        })
+        .collect()
+}
+
+// Referrer token account and stats, only passed when the deal has a referrer
+fn referrer_accounts(deal: &Deal) -> (Option<Pubkey>, Option<Pubkey>) {
+    if deal.referrer_bps == 0 {
+        return (None, None);
+    }
+
+    (
+        Some(get_associated_token_address(&deal.referrer, &deal.mint)),
+        Some(pda::referrer_stats(&deal.referrer, &deal.mint)),
+    )
+}
+
+pub fn initialize(admin: &Pubkey, max_claimable_after_obligation: u8) -> Instruction {
+    build(
+        accounts::Initialize {
+            escrow: pda::escrow(),
+            admin: *admin,
+            system_program: system_program::ID,
+        },
+        instruction::Initialize {
+            max_claimable_after_obligation,
+        },
+    )
+}
+
+pub struct CreateDealArgs {
+    pub order_id: [u8; 16],
+    pub amount: u64,
+    pub vesting_type: VestingType,
+    pub vesting_duration: i64,
+    pub referrer: Option<Pubkey>,
+    pub referrer_bps: u16,
+    pub required_kol_stake: Option<u64>,
+}
+
+pub fn create_deal(
+    project_owner: &Pubkey,
+    kol: &Pubkey,
+    mint: &Pubkey,
+    args: CreateDealArgs,
+) -> Instruction {
+    build(
+        accounts::CreateDeal {
+            escrow: pda::escrow(),
+            deal: pda::deal(&args.order_id, project_owner, kol, mint),
+            project_owner: *project_owner,
+            kol: *kol,
+            mint: *mint,
+            project_owner_token_account: get_associated_token_address(project_owner, mint),
+            vault_token_account: pda::vault_token_account(mint),
+            mint_ledger: pda::mint_ledger(mint),
+            program_stats: pda::program_stats(),
+            mint_stats: pda::mint_stats(mint),
+            project_owner_profile: pda::project_owner_profile(project_owner),
+            project_owner_volume: pda::project_owner_volume(project_owner, mint),
+            vault_authority: pda::vault_authority(),
+            referrer_stats: args
+                .referrer
+                .map(|referrer| pda::referrer_stats(&referrer, mint)),
+            token_program: token::ID,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+        },
+        instruction::CreateDeal {
+            amount: args.amount,
+            vesting_type: args.vesting_type,
+            vesting_duration: args.vesting_duration,
+            order_id: args.order_id,
+            referrer: args.referrer,
+            referrer_bps: args.referrer_bps,
+            required_kol_stake: args.required_kol_stake,
+        },
+    )
+}
+
+// `signer` is the KOL, or the admin when no payees are registered and no stake is required.
+// The stake comes from the KOL's associated token account
+pub fn accept_deal(
+    signer: &Pubkey,
+    deal_address: &Pubkey,
+    deal: &Deal,
+    payees: Vec<PayeeShare>,
+) -> Instruction {
+    let receipt_mint = pda::receipt_mint(deal_address);
+
+    build(
+        accounts::AcceptDeal {
+            deal: *deal_address,
+            signer: *signer,
+            escrow: pda::escrow(),
+            program_stats: pda::program_stats(),
+            kol: deal.kol,
+            kol_profile: pda::kol_profile(&deal.kol),
+            receipt_mint,
+            kol_receipt_account: get_associated_token_address(&deal.kol, &receipt_mint),
+            kol_stake_token_account: (deal.required_kol_stake > 0)
+                .then(|| get_associated_token_address(&deal.kol, &deal.mint)),
+            vault_token_account: pda::vault_token_account(&deal.mint),
+            mint_ledger: pda::mint_ledger(&deal.mint),
+            vault_authority: pda::vault_authority(),
+            token_program: token::ID,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+        },
+        instruction::AcceptDeal { payees },
+    )
+}
+
+pub fn reject_deal(
+    signer: &Pubkey,
+    deal: &Pubkey,
+    project_owner: &Pubkey,
+    kol: &Pubkey,
+    mint: &Pubkey,
+) -> Instruction {
+    build(
+        accounts::RejectDeal {
+            deal: *deal,
+            signer: *signer,
+            escrow: pda::escrow(),
+            kol_profile: pda::kol_profile(kol),
+            project_owner_profile: pda::project_owner_profile(project_owner),
+            program_stats: pda::program_stats(),
+            project_owner: *project_owner,
+            vault_token_account: pda::vault_token_account(mint),
+            mint_ledger: pda::mint_ledger(mint),
+            vault_authority: pda::vault_authority(),
+            project_owner_token_account: get_associated_token_address(project_owner, mint),
+            mint: *mint,
+            token_program: token::ID,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+        },
+        instruction::RejectDeal {},
+    )
+}
+
+pub fn propose_amendment(
+    signer: &Pubkey,
+    deal: &Pubkey,
+    amount: u64,
+    vesting_type: VestingType,
+    vesting_duration: i64,
+    deadline: i64,
+) -> Instruction {
+    build(
+        accounts::ProposeAmendment {
+            deal: *deal,
+            signer: *signer,
+        },
+        instruction::ProposeAmendment {
+            amount,
+            vesting_type,
+            vesting_duration,
+            deadline,
+        },
+    )
+}
+
+// The project owner has to co-sign when the amendment raises the amount
+pub fn accept_amendment(
+    signer: &Pubkey,
+    deal: &Pubkey,
+    project_owner: &Pubkey,
+    mint: &Pubkey,
+) -> Instruction {
+    build(
+        accounts::AcceptAmendment {
+            deal: *deal,
+            signer: *signer,
+            project_owner: *project_owner,
+            project_owner_token_account: get_associated_token_address(project_owner, mint),
+            vault_token_account: pda::vault_token_account(mint),
+            mint_ledger: pda::mint_ledger(mint),
+            mint_stats: pda::mint_stats(mint),
+            project_owner_volume: pda::project_owner_volume(project_owner, mint),
+            vault_authority: pda::vault_authority(),
+            token_program: token::ID,
+        },
+        instruction::AcceptAmendment {},
+    )
+}
+
+pub fn add_to_deal(
+    project_owner: &Pubkey,
+    deal: &Pubkey,
+    mint: &Pubkey,
+    amount: u64,
+    is_bonus: bool,
+) -> Instruction {
+    build(
+        accounts::AddToDeal {
+            deal: *deal,
+            project_owner: *project_owner,
+            project_owner_token_account: get_associated_token_address(project_owner, mint),
+            vault_token_account: pda::vault_token_account(mint),
+            mint_ledger: pda::mint_ledger(mint),
+            mint_stats: pda::mint_stats(mint),
+            project_owner_volume: pda::project_owner_volume(project_owner, mint),
+            token_program: token::ID,
+        },
+        instruction::AddToDeal { amount, is_bonus },
+    )
+}
+
+// `recipient_token_account` must belong to the receipt holder or the KOL's payout address
+pub fn resolve_deal(
+    signer: &Pubkey,
+    deal_address: &Pubkey,
+    deal: &Deal,
+    recipient_token_account: &Pubkey,
+    receipt_token_account: Option<Pubkey>,
+) -> Instruction {
+    let (referrer_token_account, referrer_stats) = referrer_accounts(deal);
+
+    build_with_remaining(
+        accounts::ResolveDeal {
+            deal: *deal_address,
+            signer: *signer,
+            escrow: pda::escrow(),
+            vault_token_account: pda::vault_token_account(&deal.mint),
+            mint_ledger: pda::mint_ledger(&deal.mint),
+            program_stats: pda::program_stats(),
+            mint_stats: pda::mint_stats(&deal.mint),
+            kol_token_account: *recipient_token_account,
+            kol_profile: pda::kol_profile(&deal.kol),
+            project_owner_profile: pda::project_owner_profile(&deal.project_owner),
+            kol_earnings: pda::kol_earnings(&deal.kol, &deal.mint),
+            receipt_token_account,
+            vault_authority: pda::vault_authority(),
+            referrer_token_account,
+            referrer_stats,
+            token_program: token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::ResolveDeal {},
+        payee_token_accounts(deal),
+    )
+}
+
+// `with_crank_fee` is set when a crank config exists for the mint, the fee then goes
+// to the cranker's associated token account
+pub fn crank_claim(
+    cranker: &Pubkey,
+    deal_address: &Pubkey,
+    deal: &Deal,
+    recipient: &Pubkey,
+    receipt_token_account: Option<Pubkey>,
+    with_crank_fee: bool,
+) -> Instruction {
+    let (referrer_token_account, referrer_stats) = referrer_accounts(deal);
+
+    build_with_remaining(
+        accounts::CrankClaim {
+            deal: *deal_address,
+            cranker: *cranker,
+            escrow: pda::escrow(),
+            mint: deal.mint,
+            vault_token_account: pda::vault_token_account(&deal.mint),
+            mint_ledger: pda::mint_ledger(&deal.mint),
+            program_stats: pda::program_stats(),
+            mint_stats: pda::mint_stats(&deal.mint),
+            vault_authority: pda::vault_authority(),
+            recipient: *recipient,
+            recipient_token_account: get_associated_token_address(recipient, &deal.mint),
+            kol_profile: pda::kol_profile(&deal.kol),
+            project_owner_profile: pda::project_owner_profile(&deal.project_owner),
+            kol_earnings: pda::kol_earnings(&deal.kol, &deal.mint),
+            receipt_token_account,
+            referrer_token_account,
+            referrer_stats,
+            crank_config: with_crank_fee.then(|| pda::crank_config(&deal.mint)),
+            cranker_token_account: with_crank_fee
+                .then(|| get_associated_token_address(cranker, &deal.mint)),
+            token_program: token::ID,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::CrankClaim {},
+        payee_token_accounts(deal),
+    )
+}
+
+// Each deal is paired with the KOL's receipt token account when the deal has a receipt,
+// and with its project owner
+pub fn resolve_many(
+    kol: &Pubkey,
+    mint: &Pubkey,
+    kol_token_account: &Pubkey,
+    deals: &[(Pubkey, Option<Pubkey>, Pubkey)],
+) -> Instruction {
+    let remaining_accounts = deals
+        .iter()
+        .flat_map(|(deal, receipt_token_account, project_owner)| {
+            std::iter::once(AccountMeta::new(*deal, false))
+                .chain(
+                    receipt_token_account.map(|receipt| AccountMeta::new_readonly(receipt, false)),
+                )
+                .chain(std::iter::once(AccountMeta::new(
+                    pda::project_owner_profile(project_owner),
+                    false,
+                )))
+        })
+        .collect();
+
+    build_with_remaining(
+        accounts::ResolveMany {
+            kol: *kol,
+            escrow: pda::escrow(),
+            mint: *mint,
+            vault_token_account: pda::vault_token_account(mint),
+            mint_ledger: pda::mint_ledger(mint),
+            program_stats: pda::program_stats(),
+            mint_stats: pda::mint_stats(mint),
+            kol_token_account: *kol_token_account,
+            kol_profile: pda::kol_profile(kol),
+            kol_earnings: pda::kol_earnings(kol, mint),
+            vault_authority: pda::vault_authority(),
+            token_program: token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::ResolveMany {},
+        remaining_accounts,
+    )
+}
+
+pub fn set_crank_fee(admin: &Pubkey, mint: &Pubkey, fee_bps: u16) -> Instruction {
+    build(
+        accounts::SetCrankFee {
+            escrow: pda::escrow(),
+            admin: *admin,
+            mint: *mint,
+            crank_config: pda::crank_config(mint),
+            system_program: system_program::ID,
+        },
+        instruction::SetCrankFee { fee_bps },
+    )
+}
+
+pub fn clawback(
+    admin: &Pubkey,
+    deal_address: &Pubkey,
+    deal: &Deal,
+    recipient_token_account: &Pubkey,
+    receipt_token_account: Option<Pubkey>,
+    pay_vested: bool,
+) -> Instruction {
+    let (referrer_token_account, referrer_stats) = referrer_accounts(deal);
+
+    build_with_remaining(
+        accounts::Clawback {
+            deal: *deal_address,
+            admin: *admin,
+            escrow: pda::escrow(),
+            vault_token_account: pda::vault_token_account(&deal.mint),
+            mint_ledger: pda::mint_ledger(&deal.mint),
+            vault_authority: pda::vault_authority(),
+            kol_token_account: *recipient_token_account,
+            kol_profile: pda::kol_profile(&deal.kol),
+            kol_earnings: pda::kol_earnings(&deal.kol, &deal.mint),
+            receipt_token_account,
+            project_owner_token_account: get_associated_token_address(
+                &deal.project_owner,
+                &deal.mint,
+            ),
+            referrer_token_account,
+            referrer_stats,
+            token_program: token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::Clawback { pay_vested },
+        payee_token_accounts(deal),
+    )
+}
+
+pub fn open_dispute(
+    signer: &Pubkey,
+    deal: &Pubkey,
+    project_owner: &Pubkey,
+    reason: DisputeReason,
+) -> Instruction {
+    build(
+        accounts::OpenDispute {
+            deal: *deal,
+            signer: *signer,
+            project_owner_profile: pda::project_owner_profile(project_owner),
+            system_program: system_program::ID,
+        },
+        instruction::OpenDispute { reason },
+    )
+}
+
+pub fn settle_dispute(
+    admin: &Pubkey,
+    deal_address: &Pubkey,
+    deal: &Deal,
+    outcome: DisputeOutcome,
+) -> Instruction {
+    build(
+        accounts::SettleDispute {
+            deal: *deal_address,
+            admin: *admin,
+            escrow: pda::escrow(),
+            kol_profile: pda::kol_profile(&deal.kol),
+            project_owner_profile: pda::project_owner_profile(&deal.project_owner),
+            vault_token_account: pda::vault_token_account(&deal.mint),
+            mint_ledger: pda::mint_ledger(&deal.mint),
+            vault_authority: pda::vault_authority(),
+            project_owner_token_account: get_associated_token_address(
+                &deal.project_owner,
+                &deal.mint,
+            ),
+            token_program: token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::SettleDispute { outcome },
+    )
+}
+
+// Returns the KOL's stake of a completed deal, `recipient` being the KOL's payout address
+pub fn release_kol_stake(
+    signer: &Pubkey,
+    deal_address: &Pubkey,
+    deal: &Deal,
+    recipient: &Pubkey,
+) -> Instruction {
+    build(
+        accounts::ReleaseKolStake {
+            deal: *deal_address,
+            signer: *signer,
+            mint: deal.mint,
+            kol_profile: pda::kol_profile(&deal.kol),
+            recipient: *recipient,
+            recipient_token_account: get_associated_token_address(recipient, &deal.mint),
+            vault_token_account: pda::vault_token_account(&deal.mint),
+            mint_ledger: pda::mint_ledger(&deal.mint),
+            vault_authority: pda::vault_authority(),
+            token_program: token::ID,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::ReleaseKolStake {},
+    )
+}
+
+// Sets the KOL-wide payout address, or only the one of `deal` when given
+pub fn set_payout_address(kol: &Pubkey, deal: Option<Pubkey>, payout_owner: Pubkey) -> Instruction {
+    build(
+        accounts::SetPayoutAddress {
+            kol_profile: pda::kol_profile(kol),
+            deal,
+            kol: *kol,
+            system_program: system_program::ID,
+        },
+        instruction::SetPayoutAddress { payout_owner },
+    )
+}
+
+// Rates the other party of a finished deal, `reviewer` being its project owner or KOL
+pub fn submit_review(
+    reviewer: &Pubkey,
+    deal_address: &Pubkey,
+    deal: &Deal,
+    rating: u8,
+    content_hash: [u8; 32],
+) -> Instruction {
+    build(
+        accounts::SubmitReview {
+            deal: *deal_address,
+            reviewer: *reviewer,
+            review: pda::review(deal_address, reviewer),
+            kol_profile: pda::kol_profile(&deal.kol),
+            project_owner_profile: pda::project_owner_profile(&deal.project_owner),
+            system_program: system_program::ID,
+        },
+        instruction::SubmitReview {
+            rating,
+            content_hash,
+        },
+    )
+}
+
+pub fn set_eligibility_status(
+    admin: &Pubkey,
+    deal: &Pubkey,
+    new_status: EligibilityStatus,
+) -> Instruction {
+    build(
+        accounts::SetEligibilityStatus {
+            deal: *deal,
+            signer: *admin,
+            escrow: pda::escrow(),
+        },
+        instruction::SetEligibilityStatus { new_status },
+    )
+}
+
+pub fn revoke_eligibility(
+    admin: &Pubkey,
+    deal: &Pubkey,
+    new_status: EligibilityStatus,
+    reason: RevocationReason,
+) -> Instruction {
+    build(
+        accounts::SetEligibilityStatus {
+            deal: *deal,
+            signer: *admin,
+            escrow: pda::escrow(),
+        },
+        instruction::RevokeEligibility { new_status, reason },
+    )
+}
+
+pub fn batch_set_eligibility_status(
+    admin: &Pubkey,
+    updates: Vec<(Pubkey, EligibilityStatus)>,
+) -> Instruction {
+    let (deals, new_statuses): (Vec<_>, Vec<_>) = updates.into_iter().unzip();
+
+    build_with_remaining(
+        accounts::BatchSetEligibilityStatus {
+            signer: *admin,
+            escrow: pda::escrow(),
+        },
+        instruction::BatchSetEligibilityStatus { new_statuses },
+        deals
+            .into_iter()
+            .map(|deal| AccountMeta::new(deal, false))
+            .collect(),
+    )
+}
+
+pub fn update_max_claimable_percentage(admin: &Pubkey, new_percentage: u8) -> Instruction {
+    build(
+        accounts::UpdateMaxClaimablePercentage {
+            escrow: pda::escrow(),
+            admin: *admin,
+        },
+        instruction::UpdateMaxClaimablePercentage { new_percentage },
+    )
+}
+
+// Pass either an existing deal or the terms of a new one, meant for simulateTransaction
+pub fn simulate_vesting(
+    deal: Option<Pubkey>,
+    terms: Option<VestingTerms>,
+    scenarios: Vec<EligibilityScenario>,
+    timestamps: Vec<i64>,
+) -> Instruction {
+    build(
+        accounts::SimulateVesting {
+            deal,
+            escrow: pda::escrow(),
+        },
+        instruction::SimulateVesting {
+            terms,
+            scenarios,
+            timestamps,
+        },
+    )
+}
+
+pub fn check_claimable_amount(deal: &Pubkey) -> Instruction {
+    build(
+        accounts::CheckClaimableAmount {
+            deal: *deal,
+            escrow: pda::escrow(),
+        },
+        instruction::CheckClaimableAmount {},
+    )
+}
+
+// Creates the ledger of a mint whose vault predates ledgers
+pub fn init_mint_ledger(payer: &Pubkey, mint: &Pubkey) -> Instruction {
+    build(
+        accounts::InitMintLedger {
+            payer: *payer,
+            mint: *mint,
+            vault_token_account: pda::vault_token_account(mint),
+            mint_ledger: pda::mint_ledger(mint),
+            system_program: system_program::ID,
+        },
+        instruction::InitMintLedger {},
+    )
+}
+
+pub fn migrate_account(payer: &Pubkey, account: &Pubkey) -> Instruction {
+    build(
+        accounts::MigrateAccount {
+            payer: *payer,
+            account: *account,
+            system_program: system_program::ID,
+        },
+        instruction::MigrateAccount {},
+    )
+}
+
+// Fails in simulation when the vault of the mint holds less than its deals still owe
+pub fn verify_solvency(mint: &Pubkey) -> Instruction {
+    build(
+        accounts::VerifySolvency {
+            mint: *mint,
+            mint_ledger: pda::mint_ledger(mint),
+            vault_token_account: pda::vault_token_account(mint),
+        },
+        instruction::VerifySolvency {},
+    )
+}
+
+// Expected accounts are spelled out from the program's `#[derive(Accounts)]` structs, with
+// PDAs derived from literal seeds, so a drift on either side fails here
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::AccountDeserialize;
+
+    fn derive(seeds: &[&[u8]]) -> Pubkey {
+        Pubkey::find_program_address(seeds, &PROGRAM_ID).0
+    }
+
+    fn writable(pubkey: Pubkey) -> AccountMeta {
+        AccountMeta::new(pubkey, false)
+    }
+
+    fn readonly(pubkey: Pubkey) -> AccountMeta {
+        AccountMeta::new_readonly(pubkey, false)
+    }
+
+    fn signer(pubkey: Pubkey) -> AccountMeta {
+        AccountMeta::new(pubkey, true)
+    }
+
+    // What Anchor expects in place of an optional account that is left out
+    fn absent() -> AccountMeta {
+        readonly(PROGRAM_ID)
+    }
+
+    fn deal() -> Deal {
+        let zeroed = vec![0; 8 + std::mem::size_of::<Deal>()];
+        let mut deal = Deal::try_deserialize_unchecked(&mut zeroed.as_slice()).unwrap();
+        deal.project_owner = Pubkey::new_unique();
+        deal.kol = Pubkey::new_unique();
+        deal.mint = Pubkey::new_unique();
+        deal
+    }
+
+    #[test]
+    fn create_deal_accounts() {
+        let (project_owner, kol, mint, referrer) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let order_id = [3; 16];
+        let instruction = create_deal(
+            &project_owner,
+            &kol,
+            &mint,
+            CreateDealArgs {
+                order_id,
+                amount: 1_000,
+                vesting_type: VestingType::Time,
+                vesting_duration: 100,
+                referrer: Some(referrer),
+                referrer_bps: 500,
+                required_kol_stake: None,
+            },
+        );
+
+        assert_eq!(
+            instruction.accounts,
+            vec![
+                writable(derive(&[b"escrow"])),
+                writable(derive(&[
+                    b"deal",
+                    &order_id,
+                    project_owner.as_ref(),
+                    kol.as_ref(),
+                    mint.as_ref(),
+                ])),
+                signer(project_owner),
+                readonly(kol),
+                readonly(mint),
+                writable(get_associated_token_address(&project_owner, &mint)),
+                writable(derive(&[b"vault_token_account", mint.as_ref()])),
+                writable(derive(&[b"mint_ledger", mint.as_ref()])),
+                writable(derive(&[b"program_stats"])),
+                writable(derive(&[b"mint_stats", mint.as_ref()])),
+                writable(derive(&[b"project_owner_profile", project_owner.as_ref()])),
+                writable(derive(&[
+                    b"project_owner_volume",
+                    project_owner.as_ref(),
+                    mint.as_ref(),
+                ])),
+                readonly(derive(&[b"vault_authority"])),
+                writable(derive(&[
+                    b"referrer_stats",
+                    referrer.as_ref(),
+                    mint.as_ref()
+                ])),
+                readonly(token::ID),
+                readonly(system_program::ID),
+                readonly(sysvar::rent::ID),
+            ]
+        );
+    }
+
+    #[test]
+    fn accept_deal_accounts() {
+        let mut deal = deal();
+        deal.required_kol_stake = 100;
+        let (address, admin) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let receipt_mint = derive(&[b"receipt", address.as_ref()]);
+        let instruction = accept_deal(&admin, &address, &deal, Vec::new());
+
+        assert_eq!(
+            instruction.accounts,
+            vec![
+                writable(address),
+                signer(admin),
+                readonly(derive(&[b"escrow"])),
+                writable(derive(&[b"program_stats"])),
+                readonly(deal.kol),
+                writable(derive(&[b"kol_profile", deal.kol.as_ref()])),
+                writable(receipt_mint),
+                writable(get_associated_token_address(&deal.kol, &receipt_mint)),
+                writable(get_associated_token_address(&deal.kol, &deal.mint)),
+                writable(derive(&[b"vault_token_account", deal.mint.as_ref()])),
+                writable(derive(&[b"mint_ledger", deal.mint.as_ref()])),
+                readonly(derive(&[b"vault_authority"])),
+                readonly(token::ID),
+                readonly(associated_token::ID),
+                readonly(system_program::ID),
+                readonly(sysvar::rent::ID),
+            ]
+        );
+
+        // No stake account when none is required
+        deal.required_kol_stake = 0;
+        let instruction = accept_deal(&admin, &address, &deal, Vec::new());
+        assert_eq!(instruction.accounts[8], absent());
+    }
+
+    #[test]
+    fn resolve_deal_accounts() {
+        let mut deal = deal();
+        deal.referrer = Pubkey::new_unique();
+        deal.referrer_bps = 500;
+        deal.payees[0] = PayeeShare {
+            wallet: Pubkey::new_unique(),
+            share_bps: 2_000,
+        };
+        deal.payee_count = 1;
+        let (address, kol_token_account, receipt) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let instruction = resolve_deal(
+            &deal.kol,
+            &address,
+            &deal,
+            &kol_token_account,
+            Some(receipt),
+        );
+
+        assert_eq!(
+            instruction.accounts,
+            vec![
+                writable(address),
+                signer(deal.kol),
+                readonly(derive(&[b"escrow"])),
+                writable(derive(&[b"vault_token_account", deal.mint.as_ref()])),
+                writable(derive(&[b"mint_ledger", deal.mint.as_ref()])),
+                writable(derive(&[b"program_stats"])),
+                writable(derive(&[b"mint_stats", deal.mint.as_ref()])),
+                writable(kol_token_account),
+                writable(derive(&[b"kol_profile", deal.kol.as_ref()])),
+                writable(derive(&[
+                    b"project_owner_profile",
+                    deal.project_owner.as_ref()
+                ])),
+                writable(derive(&[
+                    b"kol_earnings",
+                    deal.kol.as_ref(),
+                    deal.mint.as_ref()
+                ])),
+                readonly(receipt),
+                readonly(derive(&[b"vault_authority"])),
+                writable(get_associated_token_address(&deal.referrer, &deal.mint)),
+                writable(derive(&[
+                    b"referrer_stats",
+                    deal.referrer.as_ref(),
+                    deal.mint.as_ref()
+                ])),
+                readonly(token::ID),
+                readonly(system_program::ID),
+                // Payee token accounts follow the named accounts
+                writable(get_associated_token_address(
+                    &deal.payees[0].wallet,
+                    &deal.mint
+                )),
+            ]
+        );
+    }
+
+    #[test]
+    fn crank_claim_accounts() {
+        let deal = deal();
+        let (address, cranker, recipient) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let instruction = crank_claim(&cranker, &address, &deal, &recipient, None, true);
+
+        assert_eq!(
+            instruction.accounts,
+            vec![
+                writable(address),
+                signer(cranker),
+                readonly(derive(&[b"escrow"])),
+                readonly(deal.mint),
+                writable(derive(&[b"vault_token_account", deal.mint.as_ref()])),
+                writable(derive(&[b"mint_ledger", deal.mint.as_ref()])),
+                writable(derive(&[b"program_stats"])),
+                writable(derive(&[b"mint_stats", deal.mint.as_ref()])),
+                readonly(derive(&[b"vault_authority"])),
+                readonly(recipient),
+                writable(get_associated_token_address(&recipient, &deal.mint)),
+                writable(derive(&[b"kol_profile", deal.kol.as_ref()])),
+                writable(derive(&[
+                    b"project_owner_profile",
+                    deal.project_owner.as_ref()
+                ])),
+                writable(derive(&[
+                    b"kol_earnings",
+                    deal.kol.as_ref(),
+                    deal.mint.as_ref()
+                ])),
+                absent(),
+                absent(),
+                absent(),
+                readonly(derive(&[b"crank_config", deal.mint.as_ref()])),
+                writable(get_associated_token_address(&cranker, &deal.mint)),
+                readonly(token::ID),
+                readonly(associated_token::ID),
+                readonly(system_program::ID),
+            ]
+        );
+
+        // Without a crank config there is no fee to collect
+        let instruction = crank_claim(&cranker, &address, &deal, &recipient, None, false);
+        assert_eq!(instruction.accounts[17..19], [absent(), absent()]);
+    }
+
+    #[test]
+    fn resolve_many_accounts() {
+        let (kol, mint, kol_token_account) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let deals = [
+            (Pubkey::new_unique(), None, Pubkey::new_unique()),
+            (
+                Pubkey::new_unique(),
+                Some(Pubkey::new_unique()),
+                Pubkey::new_unique(),
+            ),
+        ];
+        let instruction = resolve_many(&kol, &mint, &kol_token_account, &deals);
+
+        let profile = |project_owner: &Pubkey| {
+            writable(derive(&[b"project_owner_profile", project_owner.as_ref()]))
+        };
+        assert_eq!(
+            instruction.accounts,
+            vec![
+                signer(kol),
+                readonly(derive(&[b"escrow"])),
+                readonly(mint),
+                writable(derive(&[b"vault_token_account", mint.as_ref()])),
+                writable(derive(&[b"mint_ledger", mint.as_ref()])),
+                writable(derive(&[b"program_stats"])),
+                writable(derive(&[b"mint_stats", mint.as_ref()])),
+                writable(kol_token_account),
+                writable(derive(&[b"kol_profile", kol.as_ref()])),
+                writable(derive(&[b"kol_earnings", kol.as_ref(), mint.as_ref()])),
+                readonly(derive(&[b"vault_authority"])),
+                readonly(token::ID),
+                readonly(system_program::ID),
+                // Each deal, its receipt token account when it has one, its project owner's profile
+                writable(deals[0].0),
+                profile(&deals[0].2),
+                writable(deals[1].0),
+                readonly(deals[1].1.unwrap()),
+                profile(&deals[1].2),
+            ]
+        );
+    }
+
+    #[test]
+    fn submit_review_accounts() {
+        let deal = deal();
+        let address = Pubkey::new_unique();
+        let instruction = submit_review(&deal.project_owner, &address, &deal, 4, [0; 32]);
+
+        assert_eq!(
+            instruction.accounts,
+            vec![
+                readonly(address),
+                signer(deal.project_owner),
+                writable(derive(&[
+                    b"review",
+                    address.as_ref(),
+                    deal.project_owner.as_ref()
+                ])),
+                writable(derive(&[b"kol_profile", deal.kol.as_ref()])),
+                writable(derive(&[
+                    b"project_owner_profile",
+                    deal.project_owner.as_ref()
+                ])),
+                readonly(system_program::ID),
+            ]
+        );
+    }
+}
@@ -0,0 +1,47 @@
+//! Rust client for the mutual escrow program: typed instruction builders, PDA derivation
+//! and decoders for its accounts and events.
+
+pub mod decode;
+pub mod filters;
+pub mod instructions;
+pub mod pda;
+
+pub use mutual_escrow::mutual_escrow::{
+    AmendmentAccepted, AmendmentProposed, CrankFeeUpdated, DealClawedBack, DealCreated,
+    DealReceiptMinted, DealResolved, DealStatusChanged, DealToppedUp, DisputeOpened,
+    DisputeSettled, EligibilityRevoked, EligibilityStatusUpdated, KolStakePosted, KolStakeReleased,
+    KolStakeSlashed, MaxClaimablePercentageUpdated, PayoutAddressUpdated,
+    ProjectOwnerProfileUpdated, ProjectOwnerVolumeUpdated, ReviewSubmitted,
+};
+pub use mutual_escrow::{
+    ClaimableBreakdown, CrankConfig, Deal, DealAmendment, DealStatus, DisputeOutcome,
+    DisputeReason, EligibilityScenario, EligibilityStatus, Escrow, KolEarnings, KolProfile,
+    MintLedger, MintStats, PayeePayout, PayeeShare, ProgramStats, ProjectOwnerProfile,
+    ProjectOwnerVolume, ReferrerStats, Review, RevocationReason, VestingProjection, VestingTerms,
+    VestingType, ID as PROGRAM_ID,
+};
+
+pub use anchor_spl::associated_token::get_associated_token_address;
+
+// Same vesting inputs the program uses, to compute claimable amounts without RPC
+pub use mutual_escrow::vesting_state;
+
+// Deals in a terminal status hold no more funds in the vault
+pub use mutual_escrow::state_machine::is_terminal;
+
+// Order id as the frontend stores it: UTF-8 bytes, truncated or zero-padded to 16 bytes
+pub fn order_id_from_str(order_id: &str) -> [u8; 16] {
+    let mut bytes = [0u8; 16];
+    let len = order_id.len().min(16);
+    bytes[..len].copy_from_slice(&order_id.as_bytes()[..len]);
+    bytes
+}
+
+// Readable form of an order id, hex when it isn't text
+pub fn order_id_to_string(order_id: &[u8; 16]) -> String {
+    let len = order_id.iter().rposition(|b| *b != 0).map_or(0, |i| i + 1);
+    match std::str::from_utf8(&order_id[..len]) {
+        Ok(text) if text.chars().all(|c| !c.is_control()) => text.to_string(),
+        _ => order_id.iter().map(|b| format!("{:02x}", b)).collect(),
+    }
+}
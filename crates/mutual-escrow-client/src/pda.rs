@@ -0,0 +1,83 @@
+//! Addresses of the program derived accounts, using the same seeds as the program.
+
+use anchor_lang::prelude::Pubkey;
+
+use crate::PROGRAM_ID;
+
+fn find(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &PROGRAM_ID).0
+}
+
+pub fn escrow() -> Pubkey {
+    find(&[b"escrow"])
+}
+
+pub fn deal(order_id: &[u8; 16], project_owner: &Pubkey, kol: &Pubkey, mint: &Pubkey) -> Pubkey {
+    find(&[
+        b"deal",
+        order_id,
+        project_owner.as_ref(),
+        kol.as_ref(),
+        mint.as_ref(),
+    ])
+}
+
+// One vault per mint, shared by every deal of that mint
+pub fn vault_token_account(mint: &Pubkey) -> Pubkey {
+    find(&[b"vault_token_account", mint.as_ref()])
+}
+
+// Running totals of what the vault of a mint holds and owes
+pub fn mint_ledger(mint: &Pubkey) -> Pubkey {
+    find(&[b"mint_ledger", mint.as_ref()])
+}
+
+pub fn program_stats() -> Pubkey {
+    find(&[b"program_stats"])
+}
+
+pub fn mint_stats(mint: &Pubkey) -> Pubkey {
+    find(&[b"mint_stats", mint.as_ref()])
+}
+
+pub fn vault_authority() -> Pubkey {
+    find(&[b"vault_authority"])
+}
+
+// Mint of the receipt NFT issued when the deal is accepted
+pub fn receipt_mint(deal: &Pubkey) -> Pubkey {
+    find(&[b"receipt", deal.as_ref()])
+}
+
+pub fn kol_profile(kol: &Pubkey) -> Pubkey {
+    find(&[b"kol_profile", kol.as_ref()])
+}
+
+pub fn kol_earnings(kol: &Pubkey, mint: &Pubkey) -> Pubkey {
+    find(&[b"kol_earnings", kol.as_ref(), mint.as_ref()])
+}
+
+pub fn project_owner_profile(project_owner: &Pubkey) -> Pubkey {
+    find(&[b"project_owner_profile", project_owner.as_ref()])
+}
+
+pub fn project_owner_volume(project_owner: &Pubkey, mint: &Pubkey) -> Pubkey {
+    find(&[
+        b"project_owner_volume",
+        project_owner.as_ref(),
+        mint.as_ref(),
+    ])
+}
+
+// Review `reviewer` left on `deal`
+pub fn review(deal: &Pubkey, reviewer: &Pubkey) -> Pubkey {
+    find(&[b"review", deal.as_ref(), reviewer.as_ref()])
+}
+
+pub fn referrer_stats(referrer: &Pubkey, mint: &Pubkey) -> Pubkey {
+    find(&[b"referrer_stats", referrer.as_ref(), mint.as_ref()])
+}
+
+pub fn crank_config(mint: &Pubkey) -> Pubkey {
+    find(&[b"crank_config", mint.as_ref()])
+}
@@ -0,0 +1,483 @@
+//! SQLite tables the indexer maintains, and how each event updates them.
+
+use anyhow::Result;
+use mutual_escrow_client::decode::{event_deal, events_from_logs, Event};
+use mutual_escrow_client::{order_id_to_string, DealStatus, EligibilityStatus};
+use rusqlite::{params, Connection, OptionalExtension};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS deals (
+    address TEXT PRIMARY KEY,
+    order_id TEXT NOT NULL,
+    project_owner TEXT NOT NULL,
+    kol TEXT NOT NULL,
+    amount INTEGER NOT NULL,
+    released_amount INTEGER NOT NULL DEFAULT 0,
+    status TEXT NOT NULL,
+    eligibility_status TEXT NOT NULL,
+    created_slot INTEGER NOT NULL,
+    created_signature TEXT NOT NULL,
+    updated_slot INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS claims (
+    signature TEXT NOT NULL,
+    event_index INTEGER NOT NULL,
+    slot INTEGER NOT NULL,
+    deal TEXT NOT NULL,
+    claim_amount INTEGER NOT NULL,
+    kol_amount INTEGER NOT NULL,
+    referrer_amount INTEGER NOT NULL,
+    crank_fee INTEGER NOT NULL,
+    recipient TEXT NOT NULL,
+    released_amount INTEGER NOT NULL,
+    PRIMARY KEY (signature, event_index)
+);
+
+CREATE TABLE IF NOT EXISTS history (
+    signature TEXT NOT NULL,
+    event_index INTEGER NOT NULL,
+    slot INTEGER NOT NULL,
+    deal TEXT,
+    event TEXT NOT NULL,
+    detail TEXT,
+    PRIMARY KEY (signature, event_index)
+);
+
+CREATE TABLE IF NOT EXISTS settings (
+    name TEXT PRIMARY KEY,
+    value INTEGER NOT NULL,
+    updated_slot INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS transactions (
+    signature TEXT PRIMARY KEY,
+    slot INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS cursor (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    signature TEXT NOT NULL,
+    slot INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS deals_kol ON deals (kol);
+CREATE INDEX IF NOT EXISTS deals_project_owner ON deals (project_owner);
+CREATE INDEX IF NOT EXISTS claims_deal ON claims (deal);
+CREATE INDEX IF NOT EXISTS history_deal ON history (deal);
+";
+
+// A confirmed program transaction, as read from a validator or a replay file
+pub struct Transaction {
+    pub signature: String,
+    pub slot: u64,
+    pub logs: Vec<String>,
+}
+
+// A deal as last indexed
+pub struct IndexedDeal {
+    pub address: String,
+    pub order_id: String,
+    pub status: String,
+}
+
+pub struct Store {
+    conn: Connection,
+}
+
+impl Store {
+    pub fn open(path: &str) -> Result<Self> {
+        let conn = Connection::open(path)?;
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self { conn })
+    }
+
+    // Last processed transaction, where a sync resumes from
+    pub fn cursor(&self) -> Result<Option<(String, u64)>> {
+        Ok(self
+            .conn
+            .query_row(
+                "SELECT signature, slot FROM cursor WHERE id = 1",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?)
+    }
+
+    pub fn deals(&self) -> Result<Vec<IndexedDeal>> {
+        let mut statement = self
+            .conn
+            .prepare("SELECT address, order_id, status FROM deals ORDER BY created_slot")?;
+        let deals = statement
+            .query_map([], |row| {
+                Ok(IndexedDeal {
+                    address: row.get(0)?,
+                    order_id: row.get(1)?,
+                    status: row.get(2)?,
+                })
+            })?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(deals)
+    }
+
+    // Applies the events of a transaction. Returns false, changing nothing, when it
+    // was already processed
+    pub fn apply(&mut self, transaction: &Transaction) -> Result<bool> {
+        let tx = self.conn.transaction()?;
+        let slot = transaction.slot as i64;
+        let signature = transaction.signature.as_str();
+
+        let inserted = tx.execute(
+            "INSERT OR IGNORE INTO transactions (signature, slot) VALUES (?1, ?2)",
+            params![signature, slot],
+        )?;
+        if inserted == 0 {
+            return Ok(false);
+        }
+
+        for (index, event) in events_from_logs(&transaction.logs).iter().enumerate() {
+            tx.execute(
+                "INSERT INTO history (signature, event_index, slot, deal, event, detail)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    signature,
+                    index as i64,
+                    slot,
+                    event_deal(event).map(|deal| deal.to_string()),
+                    event.name(),
+                    detail(event),
+                ],
+            )?;
+
+            // Deal rows only move forward, an event older than the row is history only
+            match event {
+                Event::DealCreated(e) => {
+                    tx.execute(
+                        "INSERT INTO deals (address, order_id, project_owner, kol, amount,
+                             released_amount, status, eligibility_status, created_slot,
+                             created_signature, updated_slot)
+                         VALUES (?1, ?2, ?3, ?4, ?5, 0, ?6, ?7, ?8, ?9, ?8)
+                         ON CONFLICT (address) DO UPDATE SET
+                             order_id = excluded.order_id,
+                             project_owner = excluded.project_owner,
+                             kol = excluded.kol,
+                             amount = excluded.amount,
+                             released_amount = 0,
+                             status = excluded.status,
+                             eligibility_status = excluded.eligibility_status,
+                             created_slot = excluded.created_slot,
+                             created_signature = excluded.created_signature,
+                             updated_slot = excluded.updated_slot
+                         WHERE deals.updated_slot <= excluded.created_slot",
+                        params![
+                            e.deal.to_string(),
+                            order_id_to_string(&e.order_id),
+                            e.project_owner.to_string(),
+                            e.kol.to_string(),
+                            e.amount as i64,
+                            status_name(&DealStatus::Created),
+                            eligibility_name(&EligibilityStatus::NotEligible),
+                            slot,
+                            signature,
+                        ],
+                    )?;
+                }
+                Event::DealStatusChanged(e) => {
+                    tx.execute(
+                        "UPDATE deals SET status = ?2, updated_slot = ?3
+                         WHERE address = ?1 AND updated_slot <= ?3",
+                        params![e.deal.to_string(), status_name(&e.status), slot],
+                    )?;
+                }
+                Event::DealResolved(e) => {
+                    tx.execute(
+                        "INSERT INTO claims (signature, event_index, slot, deal, claim_amount,
+                             kol_amount, referrer_amount, crank_fee, recipient, released_amount)
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                        params![
+                            signature,
+                            index as i64,
+                            slot,
+                            e.deal.to_string(),
+                            e.claim_amount as i64,
+                            e.kol_amount as i64,
+                            e.referrer_amount as i64,
+                            e.crank_fee as i64,
+                            e.recipient.to_string(),
+                            e.released_amount as i64,
+                        ],
+                    )?;
+                    tx.execute(
+                        "UPDATE deals SET status = ?2, released_amount = ?3, updated_slot = ?4
+                         WHERE address = ?1 AND updated_slot <= ?4",
+                        params![
+                            e.deal.to_string(),
+                            status_name(&e.status),
+                            e.released_amount as i64,
+                            slot,
+                        ],
+                    )?;
+                }
+                Event::DealToppedUp(e) => {
+                    tx.execute(
+                        "UPDATE deals SET amount = ?2, updated_slot = ?3
+                         WHERE address = ?1 AND updated_slot <= ?3",
+                        params![e.deal.to_string(), e.new_amount as i64, slot],
+                    )?;
+                }
+                Event::AmendmentAccepted(e) => {
+                    tx.execute(
+                        "UPDATE deals SET amount = ?2, updated_slot = ?3
+                         WHERE address = ?1 AND updated_slot <= ?3",
+                        params![e.deal.to_string(), e.new_amount as i64, slot],
+                    )?;
+                }
+                Event::EligibilityStatusUpdated(e) => {
+                    tx.execute(
+                        "UPDATE deals SET eligibility_status = ?2, updated_slot = ?3
+                         WHERE address = ?1 AND updated_slot <= ?3",
+                        params![e.deal.to_string(), eligibility_name(&e.new_status), slot],
+                    )?;
+                }
+                Event::MaxClaimablePercentageUpdated(e) => {
+                    tx.execute(
+                        "INSERT INTO settings (name, value, updated_slot)
+                         VALUES ('max_claimable_percentage', ?1, ?2)
+                         ON CONFLICT (name) DO UPDATE SET
+                             value = excluded.value,
+                             updated_slot = excluded.updated_slot
+                         WHERE settings.updated_slot <= excluded.updated_slot",
+                        params![e.new_percentage, slot],
+                    )?;
+                }
+                _ => {}
+            }
+        }
+
+        tx.execute(
+            "INSERT INTO cursor (id, signature, slot) VALUES (1, ?1, ?2)
+             ON CONFLICT (id) DO UPDATE SET
+                 signature = excluded.signature,
+                 slot = excluded.slot
+             WHERE cursor.slot <= excluded.slot",
+            params![signature, slot],
+        )?;
+        tx.commit()?;
+        Ok(true)
+    }
+}
+
+pub fn status_name(status: &DealStatus) -> String {
+    format!("{:?}", status)
+}
+
+fn eligibility_name(status: &EligibilityStatus) -> &'static str {
+    match status {
+        EligibilityStatus::NotEligible => "NotEligible",
+        EligibilityStatus::PartiallyEligible => "PartiallyEligible",
+        EligibilityStatus::FullyEligible => "FullyEligible",
+    }
+}
+
+// What changed, for the events the deal tables are built from
+fn detail(event: &Event) -> Option<String> {
+    match event {
+        Event::DealCreated(e) => Some(format!("amount {}", e.amount)),
+        Event::DealStatusChanged(e) => Some(status_name(&e.status)),
+        Event::DealResolved(e) => Some(format!(
+            "claimed {}, released {}, {}",
+            e.claim_amount,
+            e.released_amount,
+            status_name(&e.status)
+        )),
+        Event::DealToppedUp(e) => {
+            Some(format!("added {}, amount {}", e.added_amount, e.new_amount))
+        }
+        Event::AmendmentAccepted(e) => Some(format!("amount {} -> {}", e.old_amount, e.new_amount)),
+        Event::EligibilityStatusUpdated(e) => Some(eligibility_name(&e.new_status).into()),
+        Event::MaxClaimablePercentageUpdated(e) => {
+            Some(format!("{}% -> {}%", e.old_percentage, e.new_percentage))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::Event as _;
+    use base64::engine::general_purpose::STANDARD;
+    use base64::Engine;
+    use mutual_escrow_client::{
+        AmendmentAccepted, DealCreated, DealStatusChanged, DealToppedUp,
+        MaxClaimablePercentageUpdated, VestingType, PROGRAM_ID,
+    };
+    use solana_sdk::pubkey::Pubkey;
+
+    fn transaction(signature: &str, slot: u64, events: &[Vec<u8>]) -> Transaction {
+        let mut logs = vec![format!("Program {} invoke [1]", PROGRAM_ID)];
+        logs.extend(
+            events
+                .iter()
+                .map(|data| format!("Program data: {}", STANDARD.encode(data))),
+        );
+        logs.push(format!("Program {} success", PROGRAM_ID));
+        Transaction {
+            signature: signature.into(),
+            slot,
+            logs,
+        }
+    }
+
+    fn deal_status(store: &Store, deal: &Pubkey) -> String {
+        store
+            .conn
+            .query_row(
+                "SELECT status FROM deals WHERE address = ?1",
+                [deal.to_string()],
+                |row| row.get(0),
+            )
+            .unwrap()
+    }
+
+    fn deal_amount(store: &Store, deal: &Pubkey) -> i64 {
+        store
+            .conn
+            .query_row(
+                "SELECT amount FROM deals WHERE address = ?1",
+                [deal.to_string()],
+                |row| row.get(0),
+            )
+            .unwrap()
+    }
+
+    #[test]
+    fn replays_are_idempotent_and_stale_events_are_history_only() {
+        let mut store = Store::open(":memory:").unwrap();
+        let deal = Pubkey::new_unique();
+        let (project_owner, kol) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let changed = |status| {
+            DealStatusChanged {
+                order_id: [1; 16],
+                deal,
+                project_owner,
+                kol,
+                status,
+            }
+            .data()
+        };
+
+        let created = transaction(
+            "created",
+            10,
+            &[DealCreated {
+                order_id: [1; 16],
+                deal,
+                project_owner,
+                kol,
+                amount: 1_000,
+            }
+            .data()],
+        );
+        let accepted = transaction("accepted", 12, &[changed(DealStatus::Accepted)]);
+        let rejected = transaction("rejected", 11, &[changed(DealStatus::Rejected)]);
+
+        assert!(store.apply(&created).unwrap());
+        assert!(store.apply(&accepted).unwrap());
+        assert!(!store.apply(&accepted).unwrap());
+        assert!(store.apply(&rejected).unwrap());
+
+        assert_eq!(deal_status(&store, &deal), "Accepted");
+        assert_eq!(store.cursor().unwrap(), Some(("accepted".into(), 12)));
+        let history: i64 = store
+            .conn
+            .query_row("SELECT COUNT(*) FROM history", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(history, 3);
+    }
+
+    #[test]
+    fn top_ups_and_amendments_update_the_amount() {
+        let mut store = Store::open(":memory:").unwrap();
+        let deal = Pubkey::new_unique();
+        let (project_owner, kol) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let amended = |old_amount, new_amount| {
+            AmendmentAccepted {
+                order_id: [1; 16],
+                deal,
+                project_owner,
+                kol,
+                old_amount,
+                new_amount,
+                vesting_type: VestingType::Time,
+                vesting_duration: 100,
+            }
+            .data()
+        };
+
+        let created = DealCreated {
+            order_id: [1; 16],
+            deal,
+            project_owner,
+            kol,
+            amount: 1_000,
+        };
+        let topped_up = DealToppedUp {
+            order_id: [1; 16],
+            deal,
+            project_owner,
+            kol,
+            added_amount: 500,
+            is_bonus: false,
+            new_amount: 1_500,
+        };
+        store
+            .apply(&transaction("created", 10, &[created.data()]))
+            .unwrap();
+        store
+            .apply(&transaction("topped_up", 12, &[topped_up.data()]))
+            .unwrap();
+        assert_eq!(deal_amount(&store, &deal), 1_500);
+
+        // Older than the top-up
+        store
+            .apply(&transaction("stale", 11, &[amended(1_000, 800)]))
+            .unwrap();
+        assert_eq!(deal_amount(&store, &deal), 1_500);
+
+        store
+            .apply(&transaction("amended", 13, &[amended(1_500, 900)]))
+            .unwrap();
+        assert_eq!(deal_amount(&store, &deal), 900);
+    }
+    #[test]
+    fn max_claimable_history_shows_the_previous_percentage() {
+        let mut store = Store::open(":memory:").unwrap();
+        let updated = MaxClaimablePercentageUpdated {
+            old_percentage: 50,
+            new_percentage: 80,
+        };
+        store
+            .apply(&transaction("updated", 10, &[updated.data()]))
+            .unwrap();
+
+        let detail: String = store
+            .conn
+            .query_row(
+                "SELECT detail FROM history WHERE event = 'MaxClaimablePercentageUpdated'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(detail, "50% -> 80%");
+        let value: i64 = store
+            .conn
+            .query_row(
+                "SELECT value FROM settings WHERE name = 'max_claimable_percentage'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(value, 80);
+    }
+}
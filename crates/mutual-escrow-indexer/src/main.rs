@@ -0,0 +1,128 @@
+//! Indexes mutual escrow program events into SQLite deal, claim and history tables.
+
+mod db;
+mod reconcile;
+mod source;
+
+use std::fs;
+use std::thread;
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use clap::{Parser, Subcommand};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
+
+use db::Store;
+
+#[derive(Parser)]
+#[command(name = "mutual-escrow-indexer", version, about)]
+struct Cli {
+    /// SQLite database, created on first use
+    #[arg(long, short = 'd', global = true, default_value = "mutual-escrow.db")]
+    db: String,
+
+    /// RPC endpoint, a local validator by default
+    #[arg(
+        long,
+        short = 'u',
+        global = true,
+        default_value = "http://127.0.0.1:8899"
+    )]
+    url: String,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Index the program's transactions since the last processed one
+    Sync {
+        /// Keep polling for new transactions
+        #[arg(long)]
+        follow: bool,
+
+        /// Seconds between polls when following
+        #[arg(long, default_value_t = 5)]
+        interval: u64,
+    },
+    /// Index transactions from a file with one `{"signature", "slot", "logs"}` object per line
+    Replay { file: String },
+    /// Compare the index and vault balances against the deal accounts on chain, printing a
+    /// JSON report and exiting with an error when anything disagrees. Sync first, statuses
+    /// that changed since the last sync are reported as mismatches
+    Reconcile {
+        /// Write the report to this file instead of standard output
+        #[arg(long, short = 'o')]
+        output: Option<String>,
+    },
+}
+
+fn sync(store: &mut Store, rpc: &RpcClient) -> Result<usize> {
+    let cursor = store.cursor()?;
+    let signatures = source::signatures_since(rpc, cursor.as_ref().map(|(s, _)| s.as_str()))?;
+
+    // Fetched and applied one at a time so an interrupted sync resumes where it stopped
+    let mut indexed = 0;
+    for (signature, slot) in signatures {
+        let transaction = source::fetch_transaction(rpc, &signature, slot)?;
+        if store.apply(&transaction)? {
+            indexed += 1;
+        }
+    }
+    Ok(indexed)
+}
+
+fn report(store: &Store, indexed: usize) -> Result<()> {
+    match store.cursor()? {
+        Some((signature, slot)) => println!(
+            "Indexed {} transaction(s), up to {} at slot {}",
+            indexed, signature, slot
+        ),
+        None => println!("No program transactions found"),
+    }
+    Ok(())
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let mut store = Store::open(&cli.db)?;
+    let rpc = RpcClient::new_with_commitment(cli.url, CommitmentConfig::confirmed());
+
+    match cli.command {
+        Command::Sync { follow, interval } => loop {
+            let indexed = sync(&mut store, &rpc)?;
+            if !follow || indexed > 0 {
+                report(&store, indexed)?;
+            }
+            if !follow {
+                return Ok(());
+            }
+            thread::sleep(Duration::from_secs(interval));
+        },
+        Command::Replay { file } => {
+            let mut indexed = 0;
+            for transaction in source::replay_file(&file)? {
+                if store.apply(&transaction)? {
+                    indexed += 1;
+                }
+            }
+            report(&store, indexed)
+        }
+        Command::Reconcile { output } => {
+            let report = reconcile::reconcile(&store, &rpc)?;
+            let json = serde_json::to_string_pretty(&report)?;
+            match output {
+                Some(path) => {
+                    fs::write(&path, json).with_context(|| format!("writing {}", path))?
+                }
+                None => println!("{}", json),
+            }
+            if !report.is_consistent() {
+                bail!("the index or the vaults disagree with the chain");
+            }
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,260 @@
+//! Compares the index and the vault balances against the `Deal` accounts on chain.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use anyhow::{Context, Result};
+use mutual_escrow_client::{
+    decode, filters, is_terminal, order_id_to_string, pda, Deal, PROGRAM_ID,
+};
+use serde::Serialize;
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_sdk::pubkey::Pubkey;
+
+use crate::db::{status_name, Store};
+
+#[derive(Serialize)]
+pub struct Report {
+    pub slot: u64,
+    pub deals_on_chain: usize,
+    pub deals_indexed: usize,
+    pub status_mismatches: Vec<StatusMismatch>,
+    pub unindexed_deals: Vec<DealRef>, // On chain but missing from the index
+    pub orphaned_deals: Vec<DealRef>,  // In the index with no account on chain
+    pub undecodable_deals: Vec<UndecodableDeal>, // Left out of the vault expectations
+    pub vaults: Vec<VaultBalance>,
+}
+
+#[derive(Serialize)]
+pub struct StatusMismatch {
+    pub deal: String,
+    pub order_id: String,
+    pub chain_status: String,
+    pub indexed_status: String,
+}
+
+#[derive(Serialize)]
+pub struct DealRef {
+    pub deal: String,
+    pub order_id: String,
+}
+
+// A `Deal` account that doesn't decode, e.g. created by an older layout and not migrated yet
+#[derive(Serialize)]
+pub struct UndecodableDeal {
+    pub deal: String,
+    pub error: String,
+}
+
+#[derive(Serialize)]
+pub struct VaultBalance {
+    pub mint: String,
+    pub vault: String,
+    pub expected: u64, // `amount - released_amount` of non-terminal deals, plus held KOL stakes
+    pub balance: u64,
+    pub difference: i128, // Negative on a shortfall, positive on a surplus
+}
+
+impl Report {
+    pub fn is_consistent(&self) -> bool {
+        self.status_mismatches.is_empty()
+            && self.unindexed_deals.is_empty()
+            && self.orphaned_deals.is_empty()
+            && self.undecodable_deals.is_empty()
+            && self.vaults.iter().all(|vault| vault.difference == 0)
+    }
+}
+
+type ChainDeals = (Vec<(Pubkey, Deal)>, Vec<UndecodableDeal>);
+
+fn chain_deals(rpc: &RpcClient) -> Result<ChainDeals> {
+    let accounts = rpc.get_program_accounts_with_config(
+        &PROGRAM_ID,
+        RpcProgramAccountsConfig {
+            filters: Some(vec![RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
+                0,
+                filters::deal_discriminator().to_vec(),
+            ))]),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                ..RpcAccountInfoConfig::default()
+            },
+            ..RpcProgramAccountsConfig::default()
+        },
+    )?;
+
+    Ok(decode_deals(
+        accounts
+            .into_iter()
+            .map(|(address, account)| (address, account.data)),
+    ))
+}
+
+fn decode_deals(accounts: impl IntoIterator<Item = (Pubkey, Vec<u8>)>) -> ChainDeals {
+    let mut deals = Vec::new();
+    let mut undecodable = Vec::new();
+    for (address, data) in accounts {
+        match decode::deal(&data) {
+            Ok(deal) => deals.push((address, deal)),
+            Err(err) => undecodable.push(UndecodableDeal {
+                deal: address.to_string(),
+                error: err.to_string(),
+            }),
+        }
+    }
+    (deals, undecodable)
+}
+
+// What the vault of each mint should hold: `amount - released_amount` of the non-terminal
+// deals, plus the KOL stakes still held
+fn expected_by_mint(deals: &[(Pubkey, Deal)]) -> BTreeMap<Pubkey, u64> {
+    let mut expected_by_mint: BTreeMap<Pubkey, u64> = BTreeMap::new();
+    for (_, deal) in deals {
+        let expected = expected_by_mint.entry(deal.mint).or_default();
+        if !is_terminal(&deal.status) {
+            *expected += deal.amount.saturating_sub(deal.released_amount);
+        }
+        // A completed deal keeps the stake until it is released
+        *expected += deal.kol_stake;
+    }
+    expected_by_mint
+}
+
+// Token balance of a vault, zero when it was never created
+fn vault_balance(rpc: &RpcClient, vault: &Pubkey) -> Result<u64> {
+    let exists = rpc
+        .get_account_with_commitment(vault, rpc.commitment())?
+        .value
+        .is_some();
+    if !exists {
+        return Ok(0);
+    }
+    let balance = rpc
+        .get_token_account_balance(vault)
+        .with_context(|| format!("fetching vault {}", vault))?;
+    Ok(balance.amount.parse()?)
+}
+
+pub fn reconcile(store: &Store, rpc: &RpcClient) -> Result<Report> {
+    let slot = rpc.get_slot()?;
+    let (deals, undecodable_deals) = chain_deals(rpc)?;
+    let indexed = store.deals()?;
+
+    let indexed_by_address: HashMap<&str, &str> = indexed
+        .iter()
+        .map(|deal| (deal.address.as_str(), deal.status.as_str()))
+        .collect();
+    let mut status_mismatches = Vec::new();
+    let mut unindexed_deals = Vec::new();
+
+    for (address, deal) in &deals {
+        let address_key = address.to_string();
+        let chain_status = status_name(&deal.status);
+        match indexed_by_address.get(address_key.as_str()) {
+            Some(indexed_status) if *indexed_status != chain_status => {
+                status_mismatches.push(StatusMismatch {
+                    deal: address_key,
+                    order_id: order_id_to_string(&deal.order_id),
+                    chain_status,
+                    indexed_status: indexed_status.to_string(),
+                })
+            }
+            Some(_) => {}
+            None => unindexed_deals.push(DealRef {
+                deal: address_key,
+                order_id: order_id_to_string(&deal.order_id),
+            }),
+        }
+    }
+
+    let on_chain: HashSet<String> = deals
+        .iter()
+        .map(|(address, _)| address.to_string())
+        .collect();
+    let orphaned_deals = indexed
+        .iter()
+        .filter(|deal| !on_chain.contains(&deal.address))
+        .map(|deal| DealRef {
+            deal: deal.address.clone(),
+            order_id: deal.order_id.clone(),
+        })
+        .collect();
+
+    let mut vaults = Vec::new();
+    for (mint, expected) in expected_by_mint(&deals) {
+        let vault = pda::vault_token_account(&mint);
+        let balance = vault_balance(rpc, &vault)?;
+        vaults.push(VaultBalance {
+            mint: mint.to_string(),
+            vault: vault.to_string(),
+            expected,
+            balance,
+            difference: i128::from(balance) - i128::from(expected),
+        });
+    }
+
+    Ok(Report {
+        slot,
+        deals_on_chain: deals.len(),
+        deals_indexed: indexed.len(),
+        status_mismatches,
+        unindexed_deals,
+        orphaned_deals,
+        undecodable_deals,
+        vaults,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::{AccountDeserialize, AccountSerialize};
+    use mutual_escrow_client::DealStatus;
+
+    fn deal(mint: Pubkey, status: DealStatus, amount: u64, released: u64, stake: u64) -> Deal {
+        let zeroed = vec![0; 8 + std::mem::size_of::<Deal>()];
+        let mut deal = Deal::try_deserialize_unchecked(&mut zeroed.as_slice()).unwrap();
+        deal.mint = mint;
+        deal.status = status;
+        deal.amount = amount;
+        deal.released_amount = released;
+        deal.kol_stake = stake;
+        deal
+    }
+
+    #[test]
+    fn vaults_owe_unreleased_amounts_and_held_stakes() {
+        let (mint, other_mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let deals = [
+            deal(mint, DealStatus::Created, 1_000, 0, 0),
+            deal(mint, DealStatus::Accepted, 1_000, 400, 250),
+            // Terminal deals only keep the stake not released yet
+            deal(mint, DealStatus::Completed, 1_000, 1_000, 250),
+            deal(mint, DealStatus::Rejected, 1_000, 0, 0),
+            deal(other_mint, DealStatus::ClawedBack, 1_000, 300, 0),
+        ]
+        .map(|deal| (Pubkey::new_unique(), deal));
+
+        let expected = expected_by_mint(&deals);
+        assert_eq!(expected[&mint], 1_000 + 600 + 250 + 250);
+        assert_eq!(expected[&other_mint], 0);
+    }
+
+    #[test]
+    fn undecodable_deals_are_reported() {
+        let mut data = Vec::new();
+        deal(Pubkey::new_unique(), DealStatus::Accepted, 1_000, 0, 0)
+            .try_serialize(&mut data)
+            .unwrap();
+        let (decoded, legacy) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let truncated = data[..data.len() / 2].to_vec();
+
+        let (deals, undecodable) = decode_deals([(decoded, data), (legacy, truncated)]);
+        assert_eq!(deals.len(), 1);
+        assert_eq!(deals[0].0, decoded);
+        assert_eq!(undecodable.len(), 1);
+        assert_eq!(undecodable[0].deal, legacy.to_string());
+    }
+}
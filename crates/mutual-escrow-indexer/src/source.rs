@@ -0,0 +1,109 @@
+//! Where program transactions come from: a validator over RPC, or a replay file.
+
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::str::FromStr;
+
+use anyhow::{anyhow, Context, Result};
+use mutual_escrow_client::PROGRAM_ID;
+use serde::Deserialize;
+use solana_client::rpc_client::{GetConfirmedSignaturesForAddress2Config, RpcClient};
+use solana_client::rpc_config::RpcTransactionConfig;
+use solana_sdk::signature::Signature;
+use solana_transaction_status::UiTransactionEncoding;
+
+use crate::db::Transaction;
+
+// One line of a replay file
+#[derive(Deserialize)]
+struct ReplayTransaction {
+    signature: String,
+    slot: u64,
+    logs: Vec<String>,
+    #[serde(default)]
+    failed: bool,
+}
+
+// Successful transactions of a replay file, one JSON object per line, in slot order
+pub fn replay_file(path: &str) -> Result<Vec<Transaction>> {
+    let file = File::open(path).with_context(|| format!("opening {}", path))?;
+    let mut transactions = Vec::new();
+
+    for (number, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let replayed: ReplayTransaction = serde_json::from_str(&line)
+            .with_context(|| format!("{}:{}: invalid transaction", path, number + 1))?;
+        if replayed.failed {
+            continue;
+        }
+        transactions.push(Transaction {
+            signature: replayed.signature,
+            slot: replayed.slot,
+            logs: replayed.logs,
+        });
+    }
+
+    // Stable, so transactions of the same slot keep their order in the file
+    transactions.sort_by_key(|transaction| transaction.slot);
+    Ok(transactions)
+}
+
+// Signatures of the successful program transactions after `until`, oldest first
+pub fn signatures_since(rpc: &RpcClient, until: Option<&str>) -> Result<Vec<(Signature, u64)>> {
+    let until = until.map(Signature::from_str).transpose()?;
+    let mut signatures = Vec::new();
+    let mut before = None;
+
+    // Pages come newest first, walk back until the cursor or the program's first transaction
+    loop {
+        let page = rpc.get_signatures_for_address_with_config(
+            &PROGRAM_ID,
+            GetConfirmedSignaturesForAddress2Config {
+                before,
+                until,
+                limit: None,
+                commitment: Some(rpc.commitment()),
+            },
+        )?;
+        let Some(last) = page.last() else {
+            break;
+        };
+        before = Some(Signature::from_str(&last.signature)?);
+
+        for status in page {
+            if status.err.is_none() {
+                signatures.push((Signature::from_str(&status.signature)?, status.slot));
+            }
+        }
+    }
+
+    signatures.reverse();
+    signatures.sort_by_key(|(_, slot)| *slot);
+    Ok(signatures)
+}
+
+pub fn fetch_transaction(rpc: &RpcClient, signature: &Signature, slot: u64) -> Result<Transaction> {
+    let transaction = rpc.get_transaction_with_config(
+        signature,
+        RpcTransactionConfig {
+            encoding: Some(UiTransactionEncoding::Json),
+            commitment: Some(rpc.commitment()),
+            max_supported_transaction_version: Some(0),
+        },
+    )?;
+    let logs: Option<Vec<String>> = transaction
+        .transaction
+        .meta
+        .ok_or_else(|| anyhow!("transaction {} has no status metadata", signature))?
+        .log_messages
+        .into();
+
+    Ok(Transaction {
+        signature: signature.to_string(),
+        slot,
+        logs: logs.unwrap_or_default(),
+    })
+}
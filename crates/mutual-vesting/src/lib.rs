@@ -0,0 +1,196 @@
+//! Vesting and fee math of the mutual escrow program, free of any Solana types so the
+//! program, the backend and the CLI all compute the same numbers.
+#![no_std]
+
+pub const BPS_DENOMINATOR: u64 = 10_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VestingKind {
+    Time,      // Linear vesting over `duration` once fully eligible
+    Marketcap, // Everything unlocks once fully eligible
+    None,      // No vesting, everything unlocks once fully eligible, nothing before
+}
+
+// Variants are ordered from least to most eligible
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Eligibility {
+    NotEligible,
+    PartiallyEligible, // Can claim up to `max_claimable_after_obligation` percent
+    FullyEligible,
+}
+
+// Everything the vesting math needs to know about a deal
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VestingState {
+    pub amount: u64, // Total deal amount, bonus included
+    pub released_amount: u64,
+    pub bonus_amount: u64, // Part of `amount` that is claimable without vesting
+    pub released_bonus_amount: u64, // Part of `released_amount` paid out of the bonus
+    pub kind: VestingKind,
+    pub duration: i64,
+    pub eligibility: Eligibility,
+    pub start_time: i64, // When the deal became fully eligible, start of time vesting
+}
+
+// Vesting schedule of a deal as seen at a given time
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Breakdown {
+    pub claimable_now: u64,
+    pub total_vested: u64, // Released plus claimable now
+    pub released_amount: u64,
+    pub locked_amount: u64,
+    pub next_unlock_time: i64, // 0 when nothing unlocks without an eligibility update
+    pub next_unlock_amount: u64,
+    pub fully_vested_time: i64, // 0 while the deal isn't fully eligible
+}
+
+// Part of `amount` worth `bps` basis points, rounded down
+pub fn bps_share(amount: u64, bps: u16) -> u64 {
+    (amount as u128 * bps as u128 / BPS_DENOMINATOR as u128) as u64
+}
+
+// Amount the KOL can claim at `current_time`, on top of what was already released
+pub fn claimable_amount(
+    state: &VestingState,
+    current_time: i64,
+    max_claimable_after_obligation: u8,
+) -> u64 {
+    // Bonus top-ups are claimable straight away and sit outside the vesting schedule
+    let unclaimed_bonus = state
+        .bonus_amount
+        .saturating_sub(state.released_bonus_amount);
+    let vesting_amount = state.amount.saturating_sub(state.bonus_amount);
+    let vesting_released = state
+        .released_amount
+        .saturating_sub(state.released_bonus_amount);
+
+    let vested = schedule_claimable_amount(
+        state,
+        vesting_amount,
+        vesting_released,
+        current_time,
+        max_claimable_after_obligation,
+    );
+
+    unclaimed_bonus.saturating_add(vested)
+}
+
+// Full view of the schedule at `current_time`, every figure derived from `claimable_amount`
+pub fn breakdown(
+    state: &VestingState,
+    current_time: i64,
+    max_claimable_after_obligation: u8,
+) -> Breakdown {
+    let claimable_now = claimable_amount(state, current_time, max_claimable_after_obligation);
+    let total_vested = state.released_amount.saturating_add(claimable_now);
+    let locked_amount = state.amount.saturating_sub(total_vested);
+
+    let mut breakdown = Breakdown {
+        claimable_now,
+        total_vested,
+        released_amount: state.released_amount,
+        locked_amount,
+        ..Breakdown::default()
+    };
+
+    // Before full eligibility every further unlock waits on the admin
+    if state.eligibility != Eligibility::FullyEligible {
+        return breakdown;
+    }
+
+    if state.kind != VestingKind::Time {
+        breakdown.fully_vested_time = state.start_time;
+        return breakdown;
+    }
+
+    breakdown.fully_vested_time = state.start_time.saturating_add(state.duration.max(0));
+
+    // Only the part above the early-claim cap vests linearly
+    let vesting_amount = state.amount.saturating_sub(state.bonus_amount);
+    let max_claimable = early_claim_cap(vesting_amount, max_claimable_after_obligation);
+    let linear_amount = (vesting_amount - max_claimable) as u128;
+    let duration = state.duration.max(0) as u128;
+
+    // Claims may already be ahead of the linear schedule, those tokens don't unlock again
+    let linear_vested = linear_vested_amount(
+        linear_amount as u64,
+        current_time.saturating_sub(state.start_time),
+        state.duration,
+    ) as u128;
+    let linear_released = state
+        .released_amount
+        .saturating_sub(state.released_bonus_amount)
+        .saturating_sub(max_claimable) as u128;
+    let unlocked = core::cmp::max(linear_vested, linear_released);
+
+    if unlocked < linear_amount && current_time < breakdown.fully_vested_time {
+        // Vested amount is floored, so find the first second it goes up by at least one token
+        let next_elapsed = ((unlocked + 1) * duration).div_ceil(linear_amount);
+        let next_unlock_time = state.start_time.saturating_add(next_elapsed as i64);
+
+        let next_claimable =
+            claimable_amount(state, next_unlock_time, max_claimable_after_obligation);
+        breakdown.next_unlock_time = next_unlock_time;
+        breakdown.next_unlock_amount = next_claimable.saturating_sub(claimable_now);
+    }
+
+    breakdown
+}
+
+// Claimable amount of the vesting schedule itself, excluding any bonus
+fn schedule_claimable_amount(
+    state: &VestingState,
+    amount: u64,
+    released_amount: u64,
+    current_time: i64,
+    max_claimable_after_obligation: u8,
+) -> u64 {
+    let remaining_amount = amount.saturating_sub(released_amount);
+    let max_claimable = early_claim_cap(amount, max_claimable_after_obligation);
+
+    let claimable = match (state.kind, state.eligibility) {
+        (_, Eligibility::NotEligible) => 0,
+
+        // No vesting: nothing until fully eligible, then everything
+        (VestingKind::None, Eligibility::PartiallyEligible) => 0,
+        (VestingKind::None | VestingKind::Marketcap, Eligibility::FullyEligible) => {
+            remaining_amount
+        }
+
+        // Partial eligibility only unlocks the early-claim cap
+        (VestingKind::Time | VestingKind::Marketcap, Eligibility::PartiallyEligible) => {
+            max_claimable.saturating_sub(released_amount)
+        }
+
+        // Whatever is left of the cap, plus the rest of the amount vested proportional to time
+        (VestingKind::Time, Eligibility::FullyEligible) => {
+            let elapsed_time = current_time.saturating_sub(state.start_time);
+            let vested_remaining =
+                linear_vested_amount(amount - max_claimable, elapsed_time, state.duration);
+
+            max_claimable
+                .saturating_add(vested_remaining)
+                .saturating_sub(released_amount)
+        }
+    };
+
+    // Ensure the claimable amount never exceeds what is left of the deal
+    claimable.min(remaining_amount)
+}
+
+// Portion of `amount` the KOL may claim before the schedule starts, never more than `amount`
+fn early_claim_cap(amount: u64, max_claimable_after_obligation: u8) -> u64 {
+    let percentage = max_claimable_after_obligation.min(100);
+    (amount as u128 * percentage as u128 / 100) as u64
+}
+
+fn linear_vested_amount(amount: u64, elapsed_time: i64, duration: i64) -> u64 {
+    // A zero duration vests everything at once, nothing vests before the start
+    if duration <= 0 {
+        return amount;
+    }
+    let elapsed_time = elapsed_time.max(0) as u128;
+
+    let vested = amount as u128 * elapsed_time / duration as u128;
+    vested.min(amount as u128) as u64
+}
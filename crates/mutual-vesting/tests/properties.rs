@@ -0,0 +1,128 @@
+use mutual_vesting::{
+    bps_share, breakdown, claimable_amount, Eligibility, VestingKind, VestingState, BPS_DENOMINATOR,
+};
+use proptest::prelude::*;
+
+fn kind() -> impl Strategy<Value = VestingKind> {
+    prop_oneof![
+        Just(VestingKind::Time),
+        Just(VestingKind::Marketcap),
+        Just(VestingKind::None),
+    ]
+}
+
+fn eligibility() -> impl Strategy<Value = Eligibility> {
+    prop_oneof![
+        Just(Eligibility::NotEligible),
+        Just(Eligibility::PartiallyEligible),
+        Just(Eligibility::FullyEligible),
+    ]
+}
+
+// Deals as the program can produce them: bonus within the amount and releases within both parts
+fn state() -> impl Strategy<Value = VestingState> {
+    (any::<u64>(), 0..=100u8, 0..=100u8, 0..=100u8).prop_flat_map(
+        |(amount, bonus_pct, bonus_released_pct, vesting_released_pct)| {
+            let bonus_amount = fraction(amount, bonus_pct);
+            let released_bonus_amount = fraction(bonus_amount, bonus_released_pct);
+            let released_vesting = fraction(amount - bonus_amount, vesting_released_pct);
+
+            (
+                kind(),
+                eligibility(),
+                -1_000_000_000i64..1_000_000_000,
+                -10i64..100_000_000,
+            )
+                .prop_map(move |(kind, eligibility, start_time, duration)| {
+                    VestingState {
+                        amount,
+                        released_amount: released_bonus_amount + released_vesting,
+                        bonus_amount,
+                        released_bonus_amount,
+                        kind,
+                        duration,
+                        eligibility,
+                        start_time,
+                    }
+                })
+        },
+    )
+}
+
+fn fraction(amount: u64, pct: u8) -> u64 {
+    (amount as u128 * pct as u128 / 100) as u64
+}
+
+proptest! {
+    #[test]
+    fn never_exceeds_amount(
+        state in state(),
+        current_time in any::<i64>(),
+        max_pct in any::<u8>(),
+    ) {
+        let claimable = claimable_amount(&state, current_time, max_pct);
+        prop_assert!(claimable <= state.amount);
+        prop_assert!(state.released_amount as u128 + claimable as u128 <= state.amount as u128);
+    }
+
+    #[test]
+    fn monotonic_in_time(
+        state in state(),
+        t1 in -2_000_000_000i64..2_000_000_000,
+        delta in 0i64..200_000_000,
+        max_pct in 0..=100u8,
+    ) {
+        let before = claimable_amount(&state, t1, max_pct);
+        let after = claimable_amount(&state, t1 + delta, max_pct);
+        prop_assert!(before <= after);
+    }
+
+    #[test]
+    fn monotonic_in_eligibility(
+        state in state(),
+        current_time in any::<i64>(),
+        max_pct in 0..=100u8,
+    ) {
+        let at = |eligibility| {
+            claimable_amount(&VestingState { eligibility, ..state }, current_time, max_pct)
+        };
+        prop_assert!(at(Eligibility::NotEligible) <= at(Eligibility::PartiallyEligible));
+        prop_assert!(at(Eligibility::PartiallyEligible) <= at(Eligibility::FullyEligible));
+    }
+
+    #[test]
+    fn fully_vested_after_schedule_ends(state in state(), max_pct in 0..=100u8) {
+        let state = VestingState { eligibility: Eligibility::FullyEligible, ..state };
+        let end = state.start_time + state.duration.max(0);
+        let claimable = claimable_amount(&state, end, max_pct);
+        prop_assert_eq!(state.released_amount + claimable, state.amount);
+    }
+
+    #[test]
+    fn breakdown_is_consistent(
+        state in state(),
+        current_time in -2_000_000_000i64..2_000_000_000,
+        max_pct in 0..=100u8,
+    ) {
+        let breakdown = breakdown(&state, current_time, max_pct);
+        prop_assert_eq!(breakdown.claimable_now, claimable_amount(&state, current_time, max_pct));
+        prop_assert_eq!(breakdown.total_vested + breakdown.locked_amount, state.amount);
+
+        if breakdown.next_unlock_time != 0 {
+            prop_assert!(breakdown.next_unlock_time > current_time);
+            prop_assert!(breakdown.next_unlock_time <= breakdown.fully_vested_time);
+            prop_assert!(breakdown.next_unlock_amount > 0);
+            prop_assert_eq!(
+                claimable_amount(&state, breakdown.next_unlock_time - 1, max_pct),
+                breakdown.claimable_now
+            );
+        }
+    }
+
+    #[test]
+    fn shares_never_exceed_amount(amount in any::<u64>(), shares in prop::collection::vec(1..=2_500u16, 0..=4)) {
+        let total: u128 = shares.iter().map(|bps| bps_share(amount, *bps) as u128).sum();
+        prop_assert!(total <= amount as u128);
+        prop_assert_eq!(bps_share(amount, BPS_DENOMINATOR as u16), amount);
+    }
+}
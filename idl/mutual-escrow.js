@@ -1,4 +1,4 @@
-const MUTUAL_ESCROW_IDL ={"version":"0.1.0","name":"mutual_escrow","instructions":[{"name":"initialize","accounts":[{"name":"escrow","isMut":true,"isSigner":false},{"name":"admin","isMut":true,"isSigner":true},{"name":"systemProgram","isMut":false,"isSigner":false}],"args":[{"name":"maxClaimableAfterObligation","type":"u8"}]},{"name":"createDeal","accounts":[{"name":"escrow","isMut":true,"isSigner":false},{"name":"deal","isMut":true,"isSigner":false},{"name":"projectOwner","isMut":true,"isSigner":true},{"name":"kol","isMut":false,"isSigner":false},{"name":"mint","isMut":false,"isSigner":false},{"name":"projectOwnerTokenAccount","isMut":true,"isSigner":false},{"name":"vaultTokenAccount","isMut":true,"isSigner":false},{"name":"vaultAuthority","isMut":false,"isSigner":false},{"name":"tokenProgram","isMut":false,"isSigner":false},{"name":"systemProgram","isMut":false,"isSigner":false},{"name":"rent","isMut":false,"isSigner":false}],"args":[{"name":"amount","type":"u64"},{"name":"vestingType","type":{"defined":"VestingType"}},{"name":"vestingDuration","type":"i64"},{"name":"orderId","type":{"array":["u8",16]}}]},{"name":"acceptDeal","accounts":[{"name":"deal","isMut":true,"isSigner":false},{"name":"signer","isMut":false,"isSigner":true},{"name":"escrow","isMut":false,"isSigner":false}],"args":[]},{"name":"rejectDeal","accounts":[{"name":"deal","isMut":true,"isSigner":false},{"name":"signer","isMut":false,"isSigner":true},{"name":"escrow","isMut":false,"isSigner":false},{"name":"projectOwner","isMut":true,"isSigner":false},{"name":"vaultTokenAccount","isMut":true,"isSigner":false},{"name":"vaultAuthority","isMut":false,"isSigner":false},{"name":"projectOwnerTokenAccount","isMut":true,"isSigner":false},{"name":"mint","isMut":false,"isSigner":false},{"name":"tokenProgram","isMut":false,"isSigner":false},{"name":"systemProgram","isMut":false,"isSigner":false},{"name":"rent","isMut":false,"isSigner":false}],"args":[]},{"name":"resolveDeal","accounts":[{"name":"deal","isMut":true,"isSigner":false},{"name":"signer","isMut":false,"isSigner":true},{"name":"escrow","isMut":false,"isSigner":false},{"name":"vaultTokenAccount","isMut":true,"isSigner":false},{"name":"kolTokenAccount","isMut":true,"isSigner":false},{"name":"vaultAuthority","isMut":false,"isSigner":false},{"name":"tokenProgram","isMut":false,"isSigner":false}],"args":[]},{"name":"setEligibilityStatus","accounts":[{"name":"deal","isMut":true,"isSigner":false},{"name":"signer","isMut":false,"isSigner":true},{"name":"escrow","isMut":false,"isSigner":false}],"args":[{"name":"newStatus","type":{"defined":"EligibilityStatus"}}]},{"name":"updateMaxClaimablePercentage","accounts":[{"name":"escrow","isMut":true,"isSigner":false},{"name":"admin","isMut":false,"isSigner":true}],"args":[{"name":"newPercentage","type":"u8"}]},{"name":"checkClaimableAmount","accounts":[{"name":"deal","isMut":false,"isSigner":false},{"name":"escrow","isMut":false,"isSigner":false}],"args":[],"returns":"u64"}],"accounts":[{"name":"Escrow","type":{"kind":"struct","fields":[{"name":"admin","type":"publicKey"},{"name":"escrowBump","type":"u8"},{"name":"maxClaimableAfterObligation","type":"u8"}]}},{"name":"Deal","type":{"kind":"struct","fields":[{"name":"orderId","type":{"array":["u8",16]}},{"name":"projectOwner","type":"publicKey"},{"name":"kol","type":"publicKey"},{"name":"mint","type":"publicKey"},{"name":"amount","type":"u64"},{"name":"releasedAmount","type":"u64"},{"name":"vestingType","type":{"defined":"VestingType"}},{"name":"vestingDuration","type":"i64"},{"name":"startTime","type":"i64"},{"name":"acceptTime","type":"i64"},{"name":"doneObligationTime","type":"i64"},{"name":"status","type":{"defined":"DealStatus"}},{"name":"disputeReason","type":{"defined":"DisputeReason"}},{"name":"dealBump","type":"u8"},{"name":"eligibilityStatus","type":{"defined":"EligibilityStatus"}}]}}],"types":[{"name":"VestingType","type":{"kind":"enum","variants":[{"name":"Time"},{"name":"Marketcap"},{"name":"None"}]}},{"name":"DealStatus","type":{"kind":"enum","variants":[{"name":"Created"},{"name":"Accepted"},{"name":"Rejected"},{"name":"PartialCompleted"},{"name":"Completed"},{"name":"Disputed"},{"name":"Resolved"}]}},{"name":"EligibilityStatus","type":{"kind":"enum","variants":[{"name":"NotEligible"},{"name":"PartiallyEligible"},{"name":"FullyEligible"}]}},{"name":"DisputeReason","type":{"kind":"enum","variants":[{"name":"None"},{"name":"Unresolved"},{"name":"Other"}]}}],"events":[{"name":"DealCreated","fields":[{"name":"orderId","type":{"array":["u8",16]},"index":false},{"name":"deal","type":"publicKey","index":false},{"name":"projectOwner","type":"publicKey","index":false},{"name":"kol","type":"publicKey","index":false},{"name":"amount","type":"u64","index":false}]},{"name":"DealStatusChanged","fields":[{"name":"orderId","type":{"array":["u8",16]},"index":false},{"name":"deal","type":"publicKey","index":false},{"name":"projectOwner","type":"publicKey","index":false},{"name":"kol","type":"publicKey","index":false},{"name":"status","type":{"defined":"DealStatus"},"index":false}]},{"name":"MaxClaimablePercentageUpdated","fields":[{"name":"oldPercentage","type":"u8","index":false},{"name":"newPercentage","type":"u8","index":false}]},{"name":"DealResolved","fields":[{"name":"orderId","type":{"array":["u8",16]},"index":false},{"name":"deal","type":"publicKey","index":false},{"name":"projectOwner","type":"publicKey","index":false},{"name":"kol","type":"publicKey","index":false},{"name":"releasedAmount","type":"u64","index":false},{"name":"claimAmount","type":"u64","index":false},{"name":"status","type":{"defined":"DealStatus"},"index":false}]},{"name":"EligibilityStatusUpdated","fields":[{"name":"orderId","type":{"array":["u8",16]},"index":false},{"name":"deal","type":"publicKey","index":false},{"name":"kol","type":"publicKey","index":false},{"name":"newStatus","type":{"defined":"EligibilityStatus"},"index":false}]}],"errors":[{"code":6000,"name":"InvalidDealStatus","msg":"Invalid deal status"},{"code":6001,"name":"UnauthorizedSigner","msg":"Unauthorized signer"},{"code":6002,"name":"ExceedsVestedAmount","msg":"Exceeds vested amount"},{"code":6003,"name":"InvalidVestingType","msg":"Invalid vesting type"},{"code":6004,"name":"InvalidPercentage","msg":"Invalid percentage value"}]}
+const MUTUAL_ESCROW_IDL ={"version":"0.1.0","name":"mutual_escrow","instructions":[{"name":"initialize","accounts":[{"name":"escrow","isMut":true,"isSigner":false},{"name":"admin","isMut":true,"isSigner":true},{"name":"systemProgram","isMut":false,"isSigner":false}],"args":[{"name":"maxClaimableAfterObligation","type":"u8"}]},{"name":"createDeal","accounts":[{"name":"escrow","isMut":true,"isSigner":false},{"name":"deal","isMut":true,"isSigner":false},{"name":"projectOwner","isMut":true,"isSigner":true},{"name":"kol","isMut":false,"isSigner":false},{"name":"mint","isMut":false,"isSigner":false},{"name":"projectOwnerTokenAccount","isMut":true,"isSigner":false},{"name":"vaultTokenAccount","isMut":true,"isSigner":false},{"name":"mintLedger","isMut":true,"isSigner":false},{"name":"programStats","isMut":true,"isSigner":false},{"name":"mintStats","isMut":true,"isSigner":false},{"name":"projectOwnerProfile","isMut":true,"isSigner":false},{"name":"projectOwnerVolume","isMut":true,"isSigner":false},{"name":"vaultAuthority","isMut":false,"isSigner":false},{"name":"referrerStats","isMut":true,"isSigner":false,"isOptional":true},{"name":"tokenProgram","isMut":false,"isSigner":false},{"name":"systemProgram","isMut":false,"isSigner":false},{"name":"rent","isMut":false,"isSigner":false}],"args":[{"name":"amount","type":"u64"},{"name":"vestingType","type":{"defined":"VestingType"}},{"name":"vestingDuration","type":"i64"},{"name":"orderId","type":{"array":["u8",16]}},{"name":"referrer","type":{"option":"publicKey"}},{"name":"referrerBps","type":"u16"},{"name":"requiredKolStake","type":{"option":"u64"}}]},{"name":"acceptDeal","accounts":[{"name":"deal","isMut":true,"isSigner":false},{"name":"signer","isMut":true,"isSigner":true},{"name":"escrow","isMut":false,"isSigner":false},{"name":"programStats","isMut":true,"isSigner":false},{"name":"kol","isMut":false,"isSigner":false},{"name":"kolProfile","isMut":true,"isSigner":false},{"name":"receiptMint","isMut":true,"isSigner":false},{"name":"kolReceiptAccount","isMut":true,"isSigner":false},{"name":"kolStakeTokenAccount","isMut":true,"isSigner":false,"isOptional":true},{"name":"vaultTokenAccount","isMut":true,"isSigner":false},{"name":"mintLedger","isMut":true,"isSigner":false},{"name":"vaultAuthority","isMut":false,"isSigner":false},{"name":"tokenProgram","isMut":false,"isSigner":false},{"name":"associatedTokenProgram","isMut":false,"isSigner":false},{"name":"systemProgram","isMut":false,"isSigner":false},{"name":"rent","isMut":false,"isSigner":false}],"args":[{"name":"payees","type":{"vec":{"defined":"PayeeShare"}}}]},{"name":"rejectDeal","accounts":[{"name":"deal","isMut":true,"isSigner":false},{"name":"signer","isMut":true,"isSigner":true},{"name":"escrow","isMut":false,"isSigner":false},{"name":"kolProfile","isMut":true,"isSigner":false},{"name":"projectOwnerProfile","isMut":true,"isSigner":false},{"name":"programStats","isMut":true,"isSigner":false},{"name":"projectOwner","isMut":true,"isSigner":false},{"name":"vaultTokenAccount","isMut":true,"isSigner":false},{"name":"mintLedger","isMut":true,"isSigner":false},{"name":"vaultAuthority","isMut":false,"isSigner":false},{"name":"projectOwnerTokenAccount","isMut":true,"isSigner":false},{"name":"mint","isMut":false,"isSigner":false},{"name":"tokenProgram","isMut":false,"isSigner":false},{"name":"systemProgram","isMut":false,"isSigner":false},{"name":"rent","isMut":false,"isSigner":false}],"args":[]},{"name":"proposeAmendment","accounts":[{"name":"deal","isMut":true,"isSigner":false},{"name":"signer","isMut":false,"isSigner":true}],"args":[{"name":"amount","type":"u64"},{"name":"vestingType","type":{"defined":"VestingType"}},{"name":"vestingDuration","type":"i64"},{"name":"deadline","type":"i64"}]},{"name":"acceptAmendment","accounts":[{"name":"deal","isMut":true,"isSigner":false},{"name":"signer","isMut":false,"isSigner":true},{"name":"projectOwner","isMut":false,"isSigner":false},{"name":"projectOwnerTokenAccount","isMut":true,"isSigner":false},{"name":"vaultTokenAccount","isMut":true,"isSigner":false},{"name":"mintLedger","isMut":true,"isSigner":false},{"name":"mintStats","isMut":true,"isSigner":false},{"name":"projectOwnerVolume","isMut":true,"isSigner":false},{"name":"vaultAuthority","isMut":false,"isSigner":false},{"name":"tokenProgram","isMut":false,"isSigner":false}],"args":[]},{"name":"addToDeal","accounts":[{"name":"deal","isMut":true,"isSigner":false},{"name":"projectOwner","isMut":true,"isSigner":true},{"name":"projectOwnerTokenAccount","isMut":true,"isSigner":false},{"name":"vaultTokenAccount","isMut":true,"isSigner":false},{"name":"mintLedger","isMut":true,"isSigner":false},{"name":"mintStats","isMut":true,"isSigner":false},{"name":"projectOwnerVolume","isMut":true,"isSigner":false},{"name":"tokenProgram","isMut":false,"isSigner":false}],"args":[{"name":"amount","type":"u64"},{"name":"isBonus","type":"bool"}]},{"name":"resolveDeal","accounts":[{"name":"deal","isMut":true,"isSigner":false},{"name":"signer","isMut":true,"isSigner":true},{"name":"escrow","isMut":false,"isSigner":false},{"name":"vaultTokenAccount","isMut":true,"isSigner":false},{"name":"mintLedger","isMut":true,"isSigner":false},{"name":"programStats","isMut":true,"isSigner":false},{"name":"mintStats","isMut":true,"isSigner":false},{"name":"kolTokenAccount","isMut":true,"isSigner":false},{"name":"kolProfile","isMut":true,"isSigner":false},{"name":"projectOwnerProfile","isMut":true,"isSigner":false},{"name":"kolEarnings","isMut":true,"isSigner":false},{"name":"receiptTokenAccount","isMut":false,"isSigner":false,"isOptional":true},{"name":"vaultAuthority","isMut":false,"isSigner":false},{"name":"referrerTokenAccount","isMut":true,"isSigner":false,"isOptional":true},{"name":"referrerStats","isMut":true,"isSigner":false,"isOptional":true},{"name":"tokenProgram","isMut":false,"isSigner":false},{"name":"systemProgram","isMut":false,"isSigner":false}],"args":[]},{"name":"crankClaim","accounts":[{"name":"deal","isMut":true,"isSigner":false},{"name":"cranker","isMut":true,"isSigner":true},{"name":"escrow","isMut":false,"isSigner":false},{"name":"mint","isMut":false,"isSigner":false},{"name":"vaultTokenAccount","isMut":true,"isSigner":false},{"name":"mintLedger","isMut":true,"isSigner":false},{"name":"programStats","isMut":true,"isSigner":false},{"name":"mintStats","isMut":true,"isSigner":false},{"name":"vaultAuthority","isMut":false,"isSigner":false},{"name":"recipient","isMut":false,"isSigner":false},{"name":"recipientTokenAccount","isMut":true,"isSigner":false},{"name":"kolProfile","isMut":true,"isSigner":false},{"name":"projectOwnerProfile","isMut":true,"isSigner":false},{"name":"kolEarnings","isMut":true,"isSigner":false},{"name":"receiptTokenAccount","isMut":false,"isSigner":false,"isOptional":true},{"name":"referrerTokenAccount","isMut":true,"isSigner":false,"isOptional":true},{"name":"referrerStats","isMut":true,"isSigner":false,"isOptional":true},{"name":"crankConfig","isMut":false,"isSigner":false,"isOptional":true},{"name":"crankerTokenAccount","isMut":true,"isSigner":false,"isOptional":true},{"name":"tokenProgram","isMut":false,"isSigner":false},{"name":"associatedTokenProgram","isMut":false,"isSigner":false},{"name":"systemProgram","isMut":false,"isSigner":false}],"args":[]},{"name":"resolveMany","accounts":[{"name":"kol","isMut":true,"isSigner":true},{"name":"escrow","isMut":false,"isSigner":false},{"name":"mint","isMut":false,"isSigner":false},{"name":"vaultTokenAccount","isMut":true,"isSigner":false},{"name":"mintLedger","isMut":true,"isSigner":false},{"name":"programStats","isMut":true,"isSigner":false},{"name":"mintStats","isMut":true,"isSigner":false},{"name":"kolTokenAccount","isMut":true,"isSigner":false},{"name":"kolProfile","isMut":true,"isSigner":false},{"name":"kolEarnings","isMut":true,"isSigner":false},{"name":"vaultAuthority","isMut":false,"isSigner":false},{"name":"tokenProgram","isMut":false,"isSigner":false},{"name":"systemProgram","isMut":false,"isSigner":false}],"args":[]},{"name":"setCrankFee","accounts":[{"name":"escrow","isMut":false,"isSigner":false},{"name":"admin","isMut":true,"isSigner":true},{"name":"mint","isMut":false,"isSigner":false},{"name":"crankConfig","isMut":true,"isSigner":false},{"name":"systemProgram","isMut":false,"isSigner":false}],"args":[{"name":"feeBps","type":"u16"}]},{"name":"clawback","accounts":[{"name":"deal","isMut":true,"isSigner":false},{"name":"admin","isMut":true,"isSigner":true},{"name":"escrow","isMut":false,"isSigner":false},{"name":"vaultTokenAccount","isMut":true,"isSigner":false},{"name":"mintLedger","isMut":true,"isSigner":false},{"name":"vaultAuthority","isMut":false,"isSigner":false},{"name":"kolTokenAccount","isMut":true,"isSigner":false},{"name":"kolProfile","isMut":true,"isSigner":false},{"name":"kolEarnings","isMut":true,"isSigner":false},{"name":"receiptTokenAccount","isMut":false,"isSigner":false,"isOptional":true},{"name":"projectOwnerTokenAccount","isMut":true,"isSigner":false},{"name":"referrerTokenAccount","isMut":true,"isSigner":false,"isOptional":true},{"name":"referrerStats","isMut":true,"isSigner":false,"isOptional":true},{"name":"tokenProgram","isMut":false,"isSigner":false},{"name":"systemProgram","isMut":false,"isSigner":false}],"args":[{"name":"payVested","type":"bool"}]},{"name":"openDispute","accounts":[{"name":"deal","isMut":true,"isSigner":false},{"name":"signer","isMut":true,"isSigner":true},{"name":"projectOwnerProfile","isMut":true,"isSigner":false},{"name":"systemProgram","isMut":false,"isSigner":false}],"args":[{"name":"reason","type":{"defined":"DisputeReason"}}]},{"name":"settleDispute","accounts":[{"name":"deal","isMut":true,"isSigner":false},{"name":"admin","isMut":true,"isSigner":true},{"name":"escrow","isMut":false,"isSigner":false},{"name":"kolProfile","isMut":true,"isSigner":false},{"name":"projectOwnerProfile","isMut":true,"isSigner":false},{"name":"vaultTokenAccount","isMut":true,"isSigner":false},{"name":"mintLedger","isMut":true,"isSigner":false},{"name":"vaultAuthority","isMut":false,"isSigner":false},{"name":"projectOwnerTokenAccount","isMut":true,"isSigner":false},{"name":"tokenProgram","isMut":false,"isSigner":false},{"name":"systemProgram","isMut":false,"isSigner":false}],"args":[{"name":"outcome","type":{"defined":"DisputeOutcome"}}]},{"name":"releaseKolStake","accounts":[{"name":"deal","isMut":true,"isSigner":false},{"name":"signer","isMut":true,"isSigner":true},{"name":"mint","isMut":false,"isSigner":false},{"name":"kolProfile","isMut":false,"isSigner":false},{"name":"recipient","isMut":false,"isSigner":false},{"name":"recipientTokenAccount","isMut":true,"isSigner":false},{"name":"vaultTokenAccount","isMut":true,"isSigner":false},{"name":"mintLedger","isMut":true,"isSigner":false},{"name":"vaultAuthority","isMut":false,"isSigner":false},{"name":"tokenProgram","isMut":false,"isSigner":false},{"name":"associatedTokenProgram","isMut":false,"isSigner":false},{"name":"systemProgram","isMut":false,"isSigner":false}],"args":[]},{"name":"setPayoutAddress","accounts":[{"name":"kolProfile","isMut":true,"isSigner":false},{"name":"deal","isMut":true,"isSigner":false,"isOptional":true},{"name":"kol","isMut":true,"isSigner":true},{"name":"systemProgram","isMut":false,"isSigner":false}],"args":[{"name":"payoutOwner","type":"publicKey"}]},{"name":"submitReview","accounts":[{"name":"deal","isMut":false,"isSigner":false},{"name":"reviewer","isMut":true,"isSigner":true},{"name":"review","isMut":true,"isSigner":false},{"name":"kolProfile","isMut":true,"isSigner":false},{"name":"projectOwnerProfile","isMut":true,"isSigner":false},{"name":"systemProgram","isMut":false,"isSigner":false}],"args":[{"name":"rating","type":"u8"},{"name":"contentHash","type":{"array":["u8",32]}}]},{"name":"setEligibilityStatus","accounts":[{"name":"deal","isMut":true,"isSigner":false},{"name":"signer","isMut":false,"isSigner":true},{"name":"escrow","isMut":false,"isSigner":false}],"args":[{"name":"newStatus","type":{"defined":"EligibilityStatus"}}]},{"name":"revokeEligibility","accounts":[{"name":"deal","isMut":true,"isSigner":false},{"name":"signer","isMut":false,"isSigner":true},{"name":"escrow","isMut":false,"isSigner":false}],"args":[{"name":"newStatus","type":{"defined":"EligibilityStatus"}},{"name":"reason","type":{"defined":"RevocationReason"}}]},{"name":"batchSetEligibilityStatus","accounts":[{"name":"signer","isMut":false,"isSigner":true},{"name":"escrow","isMut":false,"isSigner":false}],"args":[{"name":"newStatuses","type":{"vec":{"defined":"EligibilityStatus"}}}]},{"name":"updateMaxClaimablePercentage","accounts":[{"name":"escrow","isMut":true,"isSigner":false},{"name":"admin","isMut":false,"isSigner":true}],"args":[{"name":"newPercentage","type":"u8"}]},{"name":"simulateVesting","accounts":[{"name":"deal","isMut":false,"isSigner":false,"isOptional":true},{"name":"escrow","isMut":false,"isSigner":false}],"args":[{"name":"terms","type":{"option":{"defined":"VestingTerms"}}},{"name":"scenarios","type":{"vec":{"defined":"EligibilityScenario"}}},{"name":"timestamps","type":{"vec":"i64"}}],"returns":{"vec":{"defined":"VestingProjection"}}},{"name":"checkClaimableAmount","accounts":[{"name":"deal","isMut":false,"isSigner":false},{"name":"escrow","isMut":false,"isSigner":false}],"args":[],"returns":{"defined":"ClaimableBreakdown"}},{"name":"initMintLedger","accounts":[{"name":"payer","isMut":true,"isSigner":true},{"name":"mint","isMut":false,"isSigner":false},{"name":"vaultTokenAccount","isMut":false,"isSigner":false},{"name":"mintLedger","isMut":true,"isSigner":false},{"name":"systemProgram","isMut":false,"isSigner":false}],"args":[]},{"name":"migrateAccount","accounts":[{"name":"payer","isMut":true,"isSigner":true},{"name":"account","isMut":true,"isSigner":false},{"name":"systemProgram","isMut":false,"isSigner":false}],"args":[]},{"name":"verifySolvency","accounts":[{"name":"mint","isMut":false,"isSigner":false},{"name":"mintLedger","isMut":false,"isSigner":false},{"name":"vaultTokenAccount","isMut":false,"isSigner":false}],"args":[]}],"accounts":[{"name":"Escrow","type":{"kind":"struct","fields":[{"name":"admin","type":"publicKey"},{"name":"escrowBump","type":"u8"},{"name":"maxClaimableAfterObligation","type":"u8"}]}},{"name":"Deal","type":{"kind":"struct","fields":[{"name":"orderId","type":{"array":["u8",16]}},{"name":"projectOwner","type":"publicKey"},{"name":"kol","type":"publicKey"},{"name":"mint","type":"publicKey"},{"name":"amount","type":"u64"},{"name":"releasedAmount","type":"u64"},{"name":"vestingType","type":{"defined":"VestingType"}},{"name":"vestingDuration","type":"i64"},{"name":"startTime","type":"i64"},{"name":"acceptTime","type":"i64"},{"name":"doneObligationTime","type":"i64"},{"name":"status","type":{"defined":"DealStatus"}},{"name":"disputeReason","type":{"defined":"DisputeReason"}},{"name":"dealBump","type":"u8"},{"name":"eligibilityStatus","type":{"defined":"EligibilityStatus"}},{"name":"pendingAmendment","type":{"option":{"defined":"DealAmendment"}}},{"name":"bonusAmount","type":"u64"},{"name":"releasedBonusAmount","type":"u64"},{"name":"payees","type":{"array":[{"defined":"PayeeShare"},4]}},{"name":"payeeCount","type":"u8"},{"name":"referrer","type":"publicKey"},{"name":"referrerBps","type":"u16"},{"name":"payoutOwner","type":"publicKey"},{"name":"receiptMint","type":"publicKey"},{"name":"partiallyEligibleTime","type":"i64"},{"name":"fullyEligibleTime","type":"i64"},{"name":"revocationCount","type":"u32"},{"name":"lastRevocationReason","type":{"defined":"RevocationReason"}},{"name":"lastRevocationTime","type":"i64"},{"name":"requiredKolStake","type":"u64"},{"name":"kolStake","type":"u64"}]}},{"name":"CrankConfig","type":{"kind":"struct","fields":[{"name":"mint","type":"publicKey"},{"name":"feeBps","type":"u16"},{"name":"bump","type":"u8"}]}},{"name":"KolProfile","type":{"kind":"struct","fields":[{"name":"kol","type":"publicKey"},{"name":"payoutOwner","type":"publicKey"},{"name":"bump","type":"u8"},{"name":"dealsAccepted","type":"u64"},{"name":"dealsRejected","type":"u64"},{"name":"dealsCompleted","type":"u64"},{"name":"disputesLost","type":"u64"},{"name":"totalObligationTime","type":"i64"},{"name":"timedObligations","type":"u64"},{"name":"ratingCount","type":"u64"},{"name":"ratingTotal","type":"u64"}]}},{"name":"ProjectOwnerProfile","type":{"kind":"struct","fields":[{"name":"projectOwner","type":"publicKey"},{"name":"ratingCount","type":"u64"},{"name":"ratingTotal","type":"u64"},{"name":"bump","type":"u8"},{"name":"dealsFunded","type":"u64"},{"name":"dealsRejected","type":"u64"},{"name":"dealsCompleted","type":"u64"},{"name":"disputesOpened","type":"u64"},{"name":"disputesLost","type":"u64"}]}},{"name":"ProjectOwnerVolume","type":{"kind":"struct","fields":[{"name":"projectOwner","type":"publicKey"},{"name":"mint","type":"publicKey"},{"name":"dealsFunded","type":"u64"},{"name":"totalFunded","type":"u64"},{"name":"bump","type":"u8"}]}},{"name":"Review","type":{"kind":"struct","fields":[{"name":"deal","type":"publicKey"},{"name":"reviewer","type":"publicKey"},{"name":"reviewee","type":"publicKey"},{"name":"rating","type":"u8"},{"name":"contentHash","type":{"array":["u8",32]}},{"name":"createdAt","type":"i64"},{"name":"bump","type":"u8"}]}},{"name":"KolEarnings","type":{"kind":"struct","fields":[{"name":"kol","type":"publicKey"},{"name":"mint","type":"publicKey"},{"name":"totalEarned","type":"u64"},{"name":"bump","type":"u8"}]}},{"name":"ReferrerStats","type":{"kind":"struct","fields":[{"name":"referrer","type":"publicKey"},{"name":"mint","type":"publicKey"},{"name":"totalEarned","type":"u64"},{"name":"dealsReferred","type":"u64"},{"name":"bump","type":"u8"}]}},{"name":"MintLedger","type":{"kind":"struct","fields":[{"name":"mint","type":"publicKey"},{"name":"totalDeposited","type":"u64"},{"name":"totalReleased","type":"u64"},{"name":"totalRefunded","type":"u64"},{"name":"outstandingLiability","type":"u64"},{"name":"bump","type":"u8"}]}},{"name":"ProgramStats","type":{"kind":"struct","fields":[{"name":"dealsCreated","type":"u64"},{"name":"dealsAccepted","type":"u64"},{"name":"dealsRejected","type":"u64"},{"name":"dealsCompleted","type":"u64"},{"name":"bump","type":"u8"}]}},{"name":"MintStats","type":{"kind":"struct","fields":[{"name":"mint","type":"publicKey"},{"name":"dealsCreated","type":"u64"},{"name":"volumeEscrowed","type":"u64"},{"name":"volumeReleased","type":"u64"},{"name":"bump","type":"u8"}]}}],"types":[{"name":"DealAmendment","type":{"kind":"struct","fields":[{"name":"proposer","type":"publicKey"},{"name":"amount","type":"u64"},{"name":"vestingType","type":{"defined":"VestingType"}},{"name":"vestingDuration","type":"i64"},{"name":"deadline","type":"i64"}]}},{"name":"PayeeShare","type":{"kind":"struct","fields":[{"name":"wallet","type":"publicKey"},{"name":"shareBps","type":"u16"}]}},{"name":"PayeePayout","type":{"kind":"struct","fields":[{"name":"wallet","type":"publicKey"},{"name":"amount","type":"u64"}]}},{"name":"ClaimableBreakdown","type":{"kind":"struct","fields":[{"name":"claimableNow","type":"u64"},{"name":"totalVested","type":"u64"},{"name":"releasedAmount","type":"u64"},{"name":"lockedAmount","type":"u64"},{"name":"nextUnlockTime","type":"i64"},{"name":"nextUnlockAmount","type":"u64"},{"name":"fullyVestedTime","type":"i64"},{"name":"eligibilityStatus","type":{"defined":"EligibilityStatus"}}]}},{"name":"VestingTerms","type":{"kind":"struct","fields":[{"name":"amount","type":"u64"},{"name":"vestingType","type":{"defined":"VestingType"}},{"name":"vestingDuration","type":"i64"}]}},{"name":"EligibilityScenario","type":{"kind":"struct","fields":[{"name":"eligibilityStatus","type":{"defined":"EligibilityStatus"}},{"name":"eligibleTime","type":"i64"}]}},{"name":"VestingProjection","type":{"kind":"struct","fields":[{"name":"timestamp","type":"i64"},{"name":"eligibilityStatus","type":{"defined":"EligibilityStatus"}},{"name":"vestedAmount","type":"u64"},{"name":"claimableAmount","type":"u64"}]}},{"name":"DealAction","type":{"kind":"enum","variants":[{"name":"Create"},{"name":"Accept"},{"name":"Reject"},{"name":"ProposeAmendment"},{"name":"AcceptAmendment"},{"name":"TopUp"},{"name":"SetEligibility"},{"name":"RevokeEligibility"},{"name":"Resolve","fields":[{"name":"fullyReleased","type":"bool"}]},{"name":"Clawback"},{"name":"OpenDispute"},{"name":"SettleDispute","fields":[{"name":"outcome","type":{"defined":"DisputeOutcome"}},{"name":"anyReleased","type":"bool"}]}]}},{"name":"VestingType","type":{"kind":"enum","variants":[{"name":"Time"},{"name":"Marketcap"},{"name":"None"}]}},{"name":"DealStatus","type":{"kind":"enum","variants":[{"name":"Created"},{"name":"Accepted"},{"name":"Rejected"},{"name":"PartialCompleted"},{"name":"Completed"},{"name":"Disputed"},{"name":"Resolved"},{"name":"ClawedBack"}]}},{"name":"EligibilityStatus","type":{"kind":"enum","variants":[{"name":"NotEligible"},{"name":"PartiallyEligible"},{"name":"FullyEligible"}]}},{"name":"RevocationReason","type":{"kind":"enum","variants":[{"name":"None"},{"name":"PostDeleted"},{"name":"TermsBreached"},{"name":"VerificationError"},{"name":"Other"}]}},{"name":"DisputeReason","type":{"kind":"enum","variants":[{"name":"None"},{"name":"Unresolved"},{"name":"Other"}]}},{"name":"DisputeOutcome","type":{"kind":"enum","variants":[{"name":"KolWins"},{"name":"ProjectOwnerWins"}]}}],"events":[{"name":"DealCreated","fields":[{"name":"orderId","type":{"array":["u8",16]},"index":false},{"name":"deal","type":"publicKey","index":false},{"name":"projectOwner","type":"publicKey","index":false},{"name":"kol","type":"publicKey","index":false},{"name":"amount","type":"u64","index":false}]},{"name":"DealStatusChanged","fields":[{"name":"orderId","type":{"array":["u8",16]},"index":false},{"name":"deal","type":"publicKey","index":false},{"name":"projectOwner","type":"publicKey","index":false},{"name":"kol","type":"publicKey","index":false},{"name":"status","type":{"defined":"DealStatus"},"index":false}]},{"name":"MaxClaimablePercentageUpdated","fields":[{"name":"oldPercentage","type":"u8","index":false},{"name":"newPercentage","type":"u8","index":false}]},{"name":"DealResolved","fields":[{"name":"orderId","type":{"array":["u8",16]},"index":false},{"name":"deal","type":"publicKey","index":false},{"name":"projectOwner","type":"publicKey","index":false},{"name":"kol","type":"publicKey","index":false},{"name":"releasedAmount","type":"u64","index":false},{"name":"claimAmount","type":"u64","index":false},{"name":"status","type":{"defined":"DealStatus"},"index":false},{"name":"kolAmount","type":"u64","index":false},{"name":"payeePayouts","type":{"vec":{"defined":"PayeePayout"}},"index":false},{"name":"referrerAmount","type":"u64","index":false},{"name":"recipient","type":"publicKey","index":false},{"name":"crankFee","type":"u64","index":false}]},{"name":"CrankFeeUpdated","fields":[{"name":"mint","type":"publicKey","index":false},{"name":"oldFeeBps","type":"u16","index":false},{"name":"newFeeBps","type":"u16","index":false}]},{"name":"DealReceiptMinted","fields":[{"name":"orderId","type":{"array":["u8",16]},"index":false},{"name":"deal","type":"publicKey","index":false},{"name":"kol","type":"publicKey","index":false},{"name":"receiptMint","type":"publicKey","index":false}]},{"name":"EligibilityStatusUpdated","fields":[{"name":"orderId","type":{"array":["u8",16]},"index":false},{"name":"deal","type":"publicKey","index":false},{"name":"kol","type":"publicKey","index":false},{"name":"newStatus","type":{"defined":"EligibilityStatus"},"index":false}]},{"name":"EligibilityRevoked","fields":[{"name":"orderId","type":{"array":["u8",16]},"index":false},{"name":"deal","type":"publicKey","index":false},{"name":"kol","type":"publicKey","index":false},{"name":"oldStatus","type":{"defined":"EligibilityStatus"},"index":false},{"name":"newStatus","type":{"defined":"EligibilityStatus"},"index":false},{"name":"reason","type":{"defined":"RevocationReason"},"index":false}]},{"name":"AmendmentProposed","fields":[{"name":"orderId","type":{"array":["u8",16]},"index":false},{"name":"deal","type":"publicKey","index":false},{"name":"proposer","type":"publicKey","index":false},{"name":"amount","type":"u64","index":false},{"name":"vestingType","type":{"defined":"VestingType"},"index":false},{"name":"vestingDuration","type":"i64","index":false},{"name":"deadline","type":"i64","index":false}]},{"name":"AmendmentAccepted","fields":[{"name":"orderId","type":{"array":["u8",16]},"index":false},{"name":"deal","type":"publicKey","index":false},{"name":"projectOwner","type":"publicKey","index":false},{"name":"kol","type":"publicKey","index":false},{"name":"oldAmount","type":"u64","index":false},{"name":"newAmount","type":"u64","index":false},{"name":"vestingType","type":{"defined":"VestingType"},"index":false},{"name":"vestingDuration","type":"i64","index":false}]},{"name":"DealToppedUp","fields":[{"name":"orderId","type":{"array":["u8",16]},"index":false},{"name":"deal","type":"publicKey","index":false},{"name":"projectOwner","type":"publicKey","index":false},{"name":"kol","type":"publicKey","index":false},{"name":"addedAmount","type":"u64","index":false},{"name":"isBonus","type":"bool","index":false},{"name":"newAmount","type":"u64","index":false}]},{"name":"DealClawedBack","fields":[{"name":"orderId","type":{"array":["u8",16]},"index":false},{"name":"deal","type":"publicKey","index":false},{"name":"projectOwner","type":"publicKey","index":false},{"name":"kol","type":"publicKey","index":false},{"name":"kolAmount","type":"u64","index":false},{"name":"refundedAmount","type":"u64","index":false}]},{"name":"PayoutAddressUpdated","fields":[{"name":"kol","type":"publicKey","index":false},{"name":"deal","type":{"option":"publicKey"},"index":false},{"name":"payoutOwner","type":"publicKey","index":false}]},{"name":"DisputeOpened","fields":[{"name":"orderId","type":{"array":["u8",16]},"index":false},{"name":"deal","type":"publicKey","index":false},{"name":"openedBy","type":"publicKey","index":false},{"name":"reason","type":{"defined":"DisputeReason"},"index":false}]},{"name":"DisputeSettled","fields":[{"name":"orderId","type":{"array":["u8",16]},"index":false},{"name":"deal","type":"publicKey","index":false},{"name":"outcome","type":{"defined":"DisputeOutcome"},"index":false},{"name":"refundedAmount","type":"u64","index":false}]},{"name":"ProjectOwnerProfileUpdated","fields":[{"name":"projectOwner","type":"publicKey","index":false},{"name":"dealsFunded","type":"u64","index":false},{"name":"dealsRejected","type":"u64","index":false},{"name":"dealsCompleted","type":"u64","index":false},{"name":"disputesOpened","type":"u64","index":false},{"name":"disputesLost","type":"u64","index":false}]},{"name":"ProjectOwnerVolumeUpdated","fields":[{"name":"projectOwner","type":"publicKey","index":false},{"name":"mint","type":"publicKey","index":false},{"name":"dealsFunded","type":"u64","index":false},{"name":"totalFunded","type":"u64","index":false},{"name":"averageDealSize","type":"u64","index":false}]},{"name":"ReviewSubmitted","fields":[{"name":"orderId","type":{"array":["u8",16]},"index":false},{"name":"deal","type":"publicKey","index":false},{"name":"reviewer","type":"publicKey","index":false},{"name":"reviewee","type":"publicKey","index":false},{"name":"rating","type":"u8","index":false},{"name":"contentHash","type":{"array":["u8",32]},"index":false}]},{"name":"KolStakePosted","fields":[{"name":"orderId","type":{"array":["u8",16]},"index":false},{"name":"deal","type":"publicKey","index":false},{"name":"kol","type":"publicKey","index":false},{"name":"amount","type":"u64","index":false}]},{"name":"KolStakeReleased","fields":[{"name":"orderId","type":{"array":["u8",16]},"index":false},{"name":"deal","type":"publicKey","index":false},{"name":"kol","type":"publicKey","index":false},{"name":"recipient","type":"publicKey","index":false},{"name":"amount","type":"u64","index":false}]},{"name":"KolStakeSlashed","fields":[{"name":"orderId","type":{"array":["u8",16]},"index":false},{"name":"deal","type":"publicKey","index":false},{"name":"kol","type":"publicKey","index":false},{"name":"projectOwner","type":"publicKey","index":false},{"name":"amount","type":"u64","index":false}]}],"errors":[{"code":6000,"name":"InvalidDealStatus","msg":"Invalid deal status"},{"code":6001,"name":"UnauthorizedSigner","msg":"Unauthorized signer"},{"code":6002,"name":"ExceedsVestedAmount","msg":"Exceeds vested amount"},{"code":6003,"name":"InvalidVestingType","msg":"Invalid vesting type"},{"code":6004,"name":"InvalidPercentage","msg":"Invalid percentage value"},{"code":6005,"name":"InvalidAmendment","msg":"Invalid amendment terms"},{"code":6006,"name":"NoPendingAmendment","msg":"No pending amendment"},{"code":6007,"name":"AmendmentExpired","msg":"Amendment deadline has passed"},{"code":6008,"name":"ProjectOwnerSignatureRequired","msg":"Project owner signature required to top up the deal"},{"code":6009,"name":"InvalidAmount","msg":"Invalid amount"},{"code":6010,"name":"InvalidPayees","msg":"Invalid payees"},{"code":6011,"name":"MissingReferrerAccounts","msg":"Referrer accounts are required for this deal"},{"code":6012,"name":"InvalidPayoutAccount","msg":"Token account is not owned by the KOL's payout address"},{"code":6013,"name":"MissingReceiptAccount","msg":"Deal receipt token account is required"},{"code":6014,"name":"MissingCrankerTokenAccount","msg":"Cranker token account is required"},{"code":6015,"name":"InvalidBatchDeal","msg":"Deal cannot be resolved in a batch"},{"code":6016,"name":"InvalidBatchLength","msg":"Number of deals does not match the number of statuses"},{"code":6017,"name":"InvalidEligibilityTransition","msg":"Invalid eligibility status transition"},{"code":6018,"name":"MissingRevocationReason","msg":"A reason is required to revoke eligibility"},{"code":6019,"name":"DealNotPending","msg":"Deal is no longer pending"},{"code":6020,"name":"DealNotActive","msg":"Deal has not been accepted"},{"code":6021,"name":"DealNotDisputed","msg":"Deal is not disputed"},{"code":6022,"name":"DealDisputed","msg":"Deal is disputed"},{"code":6023,"name":"DealClosed","msg":"Deal is closed"},{"code":6024,"name":"DealAlreadyExists","msg":"Deal already exists"},{"code":6025,"name":"DealNotCreated","msg":"Deal has not been created"},{"code":6026,"name":"MissingDisputeReason","msg":"A reason is required to open a dispute"},{"code":6027,"name":"InvalidSimulationInput","msg":"Pass either a deal or vesting terms to simulate"},{"code":6028,"name":"TooManySimulationPoints","msg":"Too many simulation points"},{"code":6029,"name":"VaultInsolvent","msg":"Vault balance is below the outstanding liability"},{"code":6030,"name":"InvalidRating","msg":"Rating must be between 1 and 5"},{"code":6031,"name":"DealNotFinished","msg":"Deal is not finished yet"},{"code":6032,"name":"MissingProjectOwnerProfile","msg":"Missing or mismatched project owner profile account"},{"code":6033,"name":"MissingStakeAccount","msg":"The deal requires a stake token account from the KOL"},{"code":6034,"name":"NoKolStake","msg":"Deal has no KOL stake to release"},{"code":6035,"name":"SelfReview","msg":"Deal has the same KOL and project owner, nobody to review"},{"code":6036,"name":"CrankFeeTooHigh","msg":"Crank fee is above the maximum"},{"code":6037,"name":"InvalidReferrer","msg":"The referrer can't be a party of the deal"},{"code":6038,"name":"LedgerUnderflow","msg":"Mint ledger has less outstanding liability than the amount paid out"},{"code":6039,"name":"AccountNotMigratable","msg":"Only deals and profiles can be migrated"},{"code":6040,"name":"AccountAlreadyMigrated","msg":"Account already has the current size"}]}
 
 module.exports = {
   MUTUAL_ESCROW_IDL,
@@ -1,8 +1,22 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+use anchor_lang::{system_program, Discriminator};
+use anchor_spl::associated_token::{get_associated_token_address, AssociatedToken};
+use anchor_spl::token::spl_token::instruction::AuthorityType;
+use anchor_spl::token::{self, Mint, MintTo, SetAuthority, Token, TokenAccount, Transfer};
+use mutual_vesting::{bps_share, Eligibility, VestingKind, VestingState};
+
+pub mod state_machine;
+
+use state_machine::{is_terminal, transition, DealAction};
 
 declare_id!("mutvCELMcCmXrHetrFYpDTydeBcowm9gneVVgUQ179f");
 
+pub const MAX_PAYEES: usize = 4;
+pub use mutual_vesting::BPS_DENOMINATOR;
+pub const MAX_SIMULATION_POINTS: usize = 32; // Keeps `simulate_vesting` within the return data limit
+pub const MAX_REFERRER_BPS: u16 = 1_000; // Referral fees never take more than 10% of a claim
+pub const MAX_CRANK_FEE_BPS: u16 = 100; // Crank fees never take more than 1% of a claim
+
 #[program]
 pub mod mutual_escrow {
     use super::*;
@@ -51,6 +65,26 @@ pub mod mutual_escrow {
         pub released_amount: u64,
         pub claim_amount: u64,
         pub status: DealStatus,
+        pub kol_amount: u64,
+        pub payee_payouts: Vec<PayeePayout>,
+        pub referrer_amount: u64,
+        pub recipient: Pubkey, // Owner of the token account that received `kol_amount`
+        pub crank_fee: u64,    // Paid to the cranker out of `claim_amount`
+    }
+
+    #[event]
+    pub struct CrankFeeUpdated {
+        pub mint: Pubkey,
+        pub old_fee_bps: u16,
+        pub new_fee_bps: u16,
+    }
+
+    #[event]
+    pub struct DealReceiptMinted {
+        pub order_id: [u8; 16],
+        pub deal: Pubkey,
+        pub kol: Pubkey,
+        pub receipt_mint: Pubkey,
     }
 
     #[event]
@@ -61,18 +95,195 @@ pub mod mutual_escrow {
         pub new_status: EligibilityStatus,
     }
 
+    #[event]
+    pub struct EligibilityRevoked {
+        pub order_id: [u8; 16],
+        pub deal: Pubkey,
+        pub kol: Pubkey,
+        pub old_status: EligibilityStatus,
+        pub new_status: EligibilityStatus,
+        pub reason: RevocationReason,
+    }
+
+    #[event]
+    pub struct AmendmentProposed {
+        pub order_id: [u8; 16],
+        pub deal: Pubkey,
+        pub proposer: Pubkey,
+        pub amount: u64,
+        pub vesting_type: VestingType,
+        pub vesting_duration: i64,
+        pub deadline: i64,
+    }
+
+    #[event]
+    pub struct AmendmentAccepted {
+        pub order_id: [u8; 16],
+        pub deal: Pubkey,
+        pub project_owner: Pubkey,
+        pub kol: Pubkey,
+        pub old_amount: u64,
+        pub new_amount: u64,
+        pub vesting_type: VestingType,
+        pub vesting_duration: i64,
+    }
+
+    #[event]
+    pub struct DealToppedUp {
+        pub order_id: [u8; 16],
+        pub deal: Pubkey,
+        pub project_owner: Pubkey,
+        pub kol: Pubkey,
+        pub added_amount: u64,
+        pub is_bonus: bool,
+        pub new_amount: u64,
+    }
+
+    #[event]
+    pub struct DealClawedBack {
+        pub order_id: [u8; 16],
+        pub deal: Pubkey,
+        pub project_owner: Pubkey,
+        pub kol: Pubkey,
+        pub kol_amount: u64,
+        pub refunded_amount: u64,
+    }
+
+    #[event]
+    pub struct PayoutAddressUpdated {
+        pub kol: Pubkey,
+        pub deal: Option<Pubkey>, // None when the KOL-wide default was updated
+        pub payout_owner: Pubkey,
+    }
+
+    #[event]
+    pub struct DisputeOpened {
+        pub order_id: [u8; 16],
+        pub deal: Pubkey,
+        pub opened_by: Pubkey,
+        pub reason: DisputeReason,
+    }
+
+    #[event]
+    pub struct DisputeSettled {
+        pub order_id: [u8; 16],
+        pub deal: Pubkey,
+        pub outcome: DisputeOutcome,
+        pub refunded_amount: u64,
+    }
+
+    #[event]
+    pub struct ProjectOwnerProfileUpdated {
+        pub project_owner: Pubkey,
+        pub deals_funded: u64,
+        pub deals_rejected: u64,
+        pub deals_completed: u64,
+        pub disputes_opened: u64,
+        pub disputes_lost: u64,
+    }
+
+    #[event]
+    pub struct ProjectOwnerVolumeUpdated {
+        pub project_owner: Pubkey,
+        pub mint: Pubkey,
+        pub deals_funded: u64,
+        pub total_funded: u64,
+        pub average_deal_size: u64,
+    }
+
+    #[event]
+    pub struct ReviewSubmitted {
+        pub order_id: [u8; 16],
+        pub deal: Pubkey,
+        pub reviewer: Pubkey,
+        pub reviewee: Pubkey,
+        pub rating: u8,
+        pub content_hash: [u8; 32],
+    }
+
+    #[event]
+    pub struct KolStakePosted {
+        pub order_id: [u8; 16],
+        pub deal: Pubkey,
+        pub kol: Pubkey,
+        pub amount: u64,
+    }
+
+    #[event]
+    pub struct KolStakeReleased {
+        pub order_id: [u8; 16],
+        pub deal: Pubkey,
+        pub kol: Pubkey,
+        pub recipient: Pubkey,
+        pub amount: u64,
+    }
+
+    #[event]
+    pub struct KolStakeSlashed {
+        pub order_id: [u8; 16],
+        pub deal: Pubkey,
+        pub kol: Pubkey,
+        pub project_owner: Pubkey,
+        pub amount: u64,
+    }
+
+    #[allow(clippy::too_many_arguments)]
     pub fn create_deal(
         ctx: Context<CreateDeal>,
         amount: u64,
         vesting_type: VestingType,
         vesting_duration: i64,
         order_id: [u8; 16],
+        referrer: Option<Pubkey>,
+        referrer_bps: u16,
+        required_kol_stake: Option<u64>,
     ) -> Result<()> {
         let deal = &mut ctx.accounts.deal;
         let deal_bump: u8 = ctx.bumps.deal; // Accessing bump directly for the 'deal' account
 
         // TODO: make the order_id always unique
 
+        // Referrer takes `referrer_bps` of every claim, tracked in its per-mint stats account
+        match referrer {
+            Some(referrer) => {
+                require!(
+                    referrer_bps <= MAX_REFERRER_BPS,
+                    ErrorCode::InvalidPercentage
+                );
+                // The fee comes out of the KOL's claims, neither party may take it
+                require!(
+                    referrer != ctx.accounts.project_owner.key()
+                        && referrer != ctx.accounts.kol.key(),
+                    ErrorCode::InvalidReferrer
+                );
+                let referrer_stats = ctx
+                    .accounts
+                    .referrer_stats
+                    .as_mut()
+                    .ok_or(ErrorCode::MissingReferrerAccounts)?;
+
+                if referrer_stats.referrer == Pubkey::default() {
+                    referrer_stats.referrer = referrer;
+                    referrer_stats.mint = ctx.accounts.mint.key();
+                    referrer_stats.bump = ctx.bumps.referrer_stats;
+                }
+                referrer_stats.deals_referred =
+                    referrer_stats.deals_referred.checked_add(1).unwrap();
+
+                deal.referrer = referrer;
+                deal.referrer_bps = referrer_bps;
+            }
+            None => {
+                require!(referrer_bps == 0, ErrorCode::InvalidPercentage);
+                require!(
+                    ctx.accounts.referrer_stats.is_none(),
+                    ErrorCode::MissingReferrerAccounts
+                );
+                deal.referrer = Pubkey::default();
+                deal.referrer_bps = 0;
+            }
+        }
+
         deal.order_id = order_id.clone();
         deal.project_owner = ctx.accounts.project_owner.key();
         deal.kol = ctx.accounts.kol.key();
@@ -83,13 +294,28 @@ pub mod mutual_escrow {
         deal.vesting_duration = vesting_duration;
         deal.start_time = Clock::get()?.unix_timestamp;
         deal.accept_time = 0;
-        deal.status = DealStatus::Created;
+        deal.status = transition(None, DealAction::Create)?;
         deal.dispute_reason = DisputeReason::None;
         deal.deal_bump = deal_bump;
+        deal.pending_amendment = None;
+        deal.bonus_amount = 0;
+        deal.released_bonus_amount = 0;
+        deal.payout_owner = Pubkey::default();
+        deal.receipt_mint = Pubkey::default();
+        deal.partially_eligible_time = 0;
+        deal.fully_eligible_time = 0;
+        deal.revocation_count = 0;
+        deal.last_revocation_reason = RevocationReason::None;
+        deal.last_revocation_time = 0;
+        deal.required_kol_stake = required_kol_stake.unwrap_or(0);
+        deal.kol_stake = 0;
 
         // TODO: If use SOL, it wraps it into WSOL
         // let is_wsol = ctx.accounts.mint.key() == spl_token::native_mint::id();
 
+        // A vault that predates its ledger holds what its older deals still owe
+        let vault_balance = ctx.accounts.vault_token_account.amount;
+
         // Transfer tokens from project owner to vault
         let cpi_accounts = Transfer {
             from: ctx.accounts.project_owner_token_account.to_account_info(),
@@ -101,6 +327,50 @@ pub mod mutual_escrow {
 
         token::transfer(CpiContext::new(cpi_program, cpi_accounts), amount)?;
 
+        let mint_ledger = &mut ctx.accounts.mint_ledger;
+        if mint_ledger.mint == Pubkey::default() {
+            mint_ledger.mint = ctx.accounts.mint.key();
+            mint_ledger.bump = ctx.bumps.mint_ledger;
+            mint_ledger.deposit(vault_balance);
+        }
+        mint_ledger.deposit(amount);
+
+        let program_stats = &mut ctx.accounts.program_stats;
+        program_stats.bump = ctx.bumps.program_stats;
+        program_stats.deals_created = program_stats.deals_created.checked_add(1).unwrap();
+
+        let mint_stats = &mut ctx.accounts.mint_stats;
+        if mint_stats.mint == Pubkey::default() {
+            mint_stats.mint = ctx.accounts.mint.key();
+            mint_stats.bump = ctx.bumps.mint_stats;
+        }
+        mint_stats.deals_created = mint_stats.deals_created.checked_add(1).unwrap();
+        mint_stats.volume_escrowed = mint_stats.volume_escrowed.checked_add(amount).unwrap();
+
+        let project_owner_profile = &mut ctx.accounts.project_owner_profile;
+        init_project_owner_profile(
+            project_owner_profile,
+            ctx.accounts.project_owner.key(),
+            ctx.bumps.project_owner_profile,
+        );
+        project_owner_profile.deals_funded =
+            project_owner_profile.deals_funded.checked_add(1).unwrap();
+        emit_project_owner_profile(project_owner_profile);
+
+        let project_owner_volume = &mut ctx.accounts.project_owner_volume;
+        if project_owner_volume.project_owner == Pubkey::default() {
+            project_owner_volume.project_owner = ctx.accounts.project_owner.key();
+            project_owner_volume.mint = ctx.accounts.mint.key();
+            project_owner_volume.bump = ctx.bumps.project_owner_volume;
+        }
+        project_owner_volume.deals_funded =
+            project_owner_volume.deals_funded.checked_add(1).unwrap();
+        project_owner_volume.total_funded = project_owner_volume
+            .total_funded
+            .checked_add(amount)
+            .unwrap();
+        emit_project_owner_volume(project_owner_volume);
+
         emit!(DealCreated {
             order_id: order_id.clone(),
             deal: ctx.accounts.deal.key(),
@@ -112,19 +382,108 @@ pub mod mutual_escrow {
         Ok(())
     }
 
-    pub fn accept_deal(ctx: Context<AcceptDeal>) -> Result<()> {
+    pub fn accept_deal(ctx: Context<AcceptDeal>, payees: Vec<PayeeShare>) -> Result<()> {
         let deal = &mut ctx.accounts.deal;
 
-        // Ensure the deal is in the 'Created' status
-        require!(
-            deal.status == DealStatus::Created,
-            ErrorCode::InvalidDealStatus
-        );
+        // Ensure the deal is still pending
+        let new_status = transition(Some(&deal.status), DealAction::Accept)?;
+
+        // Revenue split is the KOL's own arrangement, the admin can't register it for them
+        if !payees.is_empty() {
+            require!(
+                ctx.accounts.signer.key() == deal.kol,
+                ErrorCode::UnauthorizedSigner
+            );
+        }
+        require!(payees.len() <= MAX_PAYEES, ErrorCode::InvalidPayees);
+
+        let mut total_bps: u64 = 0;
+        for payee in payees.iter() {
+            require!(payee.share_bps > 0, ErrorCode::InvalidPayees);
+            total_bps += payee.share_bps as u64;
+        }
+        require!(total_bps <= BPS_DENOMINATOR, ErrorCode::InvalidPayees);
+
+        deal.payees = [PayeeShare::default(); MAX_PAYEES];
+        deal.payees[..payees.len()].copy_from_slice(&payees);
+        deal.payee_count = payees.len() as u8;
+
+        // The KOL bonds the deal with their own tokens, held in the vault until completion
+        let stake = deal.required_kol_stake;
+        if stake > 0 {
+            require!(
+                ctx.accounts.signer.key() == deal.kol,
+                ErrorCode::UnauthorizedSigner
+            );
+            let kol_stake_token_account = ctx
+                .accounts
+                .kol_stake_token_account
+                .as_ref()
+                .ok_or(ErrorCode::MissingStakeAccount)?;
+
+            let cpi_accounts = Transfer {
+                from: kol_stake_token_account.to_account_info(),
+                to: ctx.accounts.vault_token_account.to_account_info(),
+                authority: ctx.accounts.signer.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+
+            token::transfer(CpiContext::new(cpi_program, cpi_accounts), stake)?;
+            ctx.accounts.mint_ledger.deposit(stake);
+            deal.kol_stake = stake;
+
+            emit!(KolStakePosted {
+                order_id: deal.order_id,
+                deal: deal.key(),
+                kol: deal.kol,
+                amount: stake,
+            });
+        }
 
         // Update the deal status and accept_time
-        deal.status = DealStatus::Accepted;
+        deal.status = new_status;
         deal.accept_time = Clock::get()?.unix_timestamp;
 
+        let program_stats = &mut ctx.accounts.program_stats;
+        program_stats.deals_accepted = program_stats.deals_accepted.checked_add(1).unwrap();
+
+        let kol_profile = &mut ctx.accounts.kol_profile;
+        init_kol_profile(kol_profile, deal.kol, ctx.bumps.kol_profile);
+        kol_profile.deals_accepted = kol_profile.deals_accepted.checked_add(1).unwrap();
+
+        // Accepting the original terms discards any counter-offer still on the table
+        deal.pending_amendment = None;
+
+        // Mint the receipt NFT representing the KOL's claim on the deal
+        let seeds = &[b"vault_authority".as_ref(), &[ctx.bumps.vault_authority]];
+        let signer = &[&seeds[..]];
+        let cpi_accounts = MintTo {
+            mint: ctx.accounts.receipt_mint.to_account_info(),
+            to: ctx.accounts.kol_receipt_account.to_account_info(),
+            authority: ctx.accounts.vault_authority.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+
+        token::mint_to(
+            CpiContext::new_with_signer(cpi_program, cpi_accounts, signer),
+            1,
+        )?;
+
+        // Drop the mint authority so the receipt supply is fixed at one
+        let cpi_accounts = SetAuthority {
+            account_or_mint: ctx.accounts.receipt_mint.to_account_info(),
+            current_authority: ctx.accounts.vault_authority.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+
+        token::set_authority(
+            CpiContext::new_with_signer(cpi_program, cpi_accounts, signer),
+            AuthorityType::MintTokens,
+            None,
+        )?;
+
+        deal.receipt_mint = ctx.accounts.receipt_mint.key();
+
         emit!(DealStatusChanged {
             order_id: deal.order_id,
             deal: deal.key(),
@@ -133,17 +492,21 @@ pub mod mutual_escrow {
             status: deal.status.clone(),
         });
 
+        emit!(DealReceiptMinted {
+            order_id: deal.order_id,
+            deal: deal.key(),
+            kol: deal.kol,
+            receipt_mint: deal.receipt_mint,
+        });
+
         Ok(())
     }
 
     pub fn reject_deal(ctx: Context<RejectDeal>) -> Result<()> {
         let deal = &mut ctx.accounts.deal;
 
-        // Ensure the deal is in the 'Created' status
-        require!(
-            deal.status == DealStatus::Created,
-            ErrorCode::InvalidDealStatus
-        );
+        // Ensure the deal is still pending
+        let new_status = transition(Some(&deal.status), DealAction::Reject)?;
 
         // Access the bump seed directly
         let vault_authority_bump = ctx.bumps.vault_authority;
@@ -164,9 +527,28 @@ pub mod mutual_escrow {
             CpiContext::new_with_signer(cpi_program, cpi_accounts, signer),
             deal.amount,
         )?;
+        ctx.accounts.mint_ledger.refund(deal.amount)?;
 
         // Update deal status
-        deal.status = DealStatus::Rejected;
+        deal.status = new_status;
+        deal.pending_amendment = None;
+
+        let program_stats = &mut ctx.accounts.program_stats;
+        program_stats.deals_rejected = program_stats.deals_rejected.checked_add(1).unwrap();
+
+        let kol_profile = &mut ctx.accounts.kol_profile;
+        init_kol_profile(kol_profile, deal.kol, ctx.bumps.kol_profile);
+        kol_profile.deals_rejected = kol_profile.deals_rejected.checked_add(1).unwrap();
+
+        let project_owner_profile = &mut ctx.accounts.project_owner_profile;
+        init_project_owner_profile(
+            project_owner_profile,
+            deal.project_owner,
+            ctx.bumps.project_owner_profile,
+        );
+        project_owner_profile.deals_rejected =
+            project_owner_profile.deals_rejected.checked_add(1).unwrap();
+        emit_project_owner_profile(project_owner_profile);
 
         emit!(DealStatusChanged {
             order_id: deal.order_id,
@@ -179,301 +561,2353 @@ pub mod mutual_escrow {
         Ok(())
     }
 
-    pub fn resolve_deal(ctx: Context<ResolveDeal>) -> Result<()> {
+    // Either party can counter the current terms while the deal is still pending
+    pub fn propose_amendment(
+        ctx: Context<ProposeAmendment>,
+        amount: u64,
+        vesting_type: VestingType,
+        vesting_duration: i64,
+        deadline: i64,
+    ) -> Result<()> {
         let deal = &mut ctx.accounts.deal;
-        let current_time = Clock::get()?.unix_timestamp;
 
-        // Calculate how much the KOL can claim based on time or marketcap
-        let claimable_amount = calculate_vested_amount(
-            deal,
-            current_time,
-            ctx.accounts.escrow.max_claimable_after_obligation, // Handles the partial claim logic
-        )?;
+        // Terms can only be negotiated before the KOL accepts
+        transition(Some(&deal.status), DealAction::ProposeAmendment)?;
+        require!(
+            amount > 0 && vesting_duration >= 0,
+            ErrorCode::InvalidAmendment
+        );
+        require!(
+            deadline > Clock::get()?.unix_timestamp,
+            ErrorCode::AmendmentExpired
+        );
 
-        // Debugging output using `msg!()`
-        msg!("Calculated claimable amount: {}", claimable_amount);
-        msg!("Current time: {}", current_time);
-        msg!("Released amount before claim: {}", deal.released_amount);
+        // A new proposal replaces whatever was pending before
+        deal.pending_amendment = Some(DealAmendment {
+            proposer: ctx.accounts.signer.key(),
+            amount,
+            vesting_type: vesting_type.clone(),
+            vesting_duration,
+            deadline,
+        });
 
-        // Ensure they are claiming at least some amount
-        require!(claimable_amount > 0, ErrorCode::ExceedsVestedAmount);
+        emit!(AmendmentProposed {
+            order_id: deal.order_id,
+            deal: deal.key(),
+            proposer: ctx.accounts.signer.key(),
+            amount,
+            vesting_type,
+            vesting_duration,
+            deadline,
+        });
 
-        // Transfer tokens to the KOL
-        let seeds = &[b"vault_authority".as_ref(), &[ctx.bumps.vault_authority]];
-        let signer = &[&seeds[..]];
-        let cpi_accounts = Transfer {
-            from: ctx.accounts.vault_token_account.to_account_info(),
-            to: ctx.accounts.kol_token_account.to_account_info(),
-            authority: ctx.accounts.vault_authority.to_account_info(),
-        };
-        let cpi_program = ctx.accounts.token_program.to_account_info();
+        Ok(())
+    }
 
-        token::transfer(
-            CpiContext::new_with_signer(cpi_program, cpi_accounts, signer),
-            claimable_amount,
-        )?;
+    // The counterparty of the proposer accepts the amended terms, settling the vault difference
+    pub fn accept_amendment(ctx: Context<AcceptAmendment>) -> Result<()> {
+        let deal = &mut ctx.accounts.deal;
 
-        // Update the released amount after the claim
-        deal.released_amount = deal.released_amount.checked_add(claimable_amount).unwrap();
+        transition(Some(&deal.status), DealAction::AcceptAmendment)?;
 
-        // Log the updated released amount for debugging
-        msg!("Released amount after claim: {}", deal.released_amount);
+        let amendment = deal
+            .pending_amendment
+            .clone()
+            .ok_or(ErrorCode::NoPendingAmendment)?;
 
-        // Determine the new status based on the released amount
-        let new_status = if deal.released_amount >= deal.amount {
-            DealStatus::Completed
+        // Only the other party can accept a proposal
+        let counterparty = if amendment.proposer == deal.project_owner {
+            deal.kol
         } else {
-            DealStatus::PartialCompleted
+            deal.project_owner
         };
+        require!(
+            ctx.accounts.signer.key() == counterparty,
+            ErrorCode::UnauthorizedSigner
+        );
+        require!(
+            Clock::get()?.unix_timestamp <= amendment.deadline,
+            ErrorCode::AmendmentExpired
+        );
 
-        // Check if the status has changed before updating and emitting the event
-        if deal.status != new_status {
-            deal.status = new_status;
+        let cpi_program = ctx.accounts.token_program.to_account_info();
 
-            // Emit the event only if the status has changed
-            emit!(DealStatusChanged {
-                order_id: deal.order_id,
-                deal: deal.key(),
-                project_owner: deal.project_owner,
-                kol: deal.kol,
-                status: deal.status.clone(),
-            });
-        } else {
-            msg!("Deal status unchanged. No event emitted.");
+        if amendment.amount > deal.amount {
+            // Top up the vault; the project owner has to co-sign when the KOL is the one accepting
+            require!(
+                ctx.accounts.project_owner.is_signer,
+                ErrorCode::ProjectOwnerSignatureRequired
+            );
+
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.project_owner_token_account.to_account_info(),
+                to: ctx.accounts.vault_token_account.to_account_info(),
+                authority: ctx.accounts.project_owner.to_account_info(),
+            };
+
+            token::transfer(
+                CpiContext::new(cpi_program, cpi_accounts),
+                amendment.amount - deal.amount,
+            )?;
+            ctx.accounts
+                .mint_ledger
+                .deposit(amendment.amount - deal.amount);
+        } else if amendment.amount < deal.amount {
+            // Refund the difference to the project owner
+            let seeds = &[b"vault_authority".as_ref(), &[ctx.bumps.vault_authority]];
+            let signer = &[&seeds[..]];
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.vault_token_account.to_account_info(),
+                to: ctx.accounts.project_owner_token_account.to_account_info(),
+                authority: ctx.accounts.vault_authority.to_account_info(),
+            };
+
+            token::transfer(
+                CpiContext::new_with_signer(cpi_program, cpi_accounts, signer),
+                deal.amount - amendment.amount,
+            )?;
+            ctx.accounts
+                .mint_ledger
+                .refund(deal.amount - amendment.amount)?;
         }
 
-        emit!(DealResolved {
+        let old_amount = deal.amount;
+        record_amount_change(
+            &mut ctx.accounts.mint_stats,
+            &mut ctx.accounts.project_owner_volume,
+            old_amount,
+            amendment.amount,
+        );
+
+        deal.amount = amendment.amount;
+        deal.vesting_type = amendment.vesting_type.clone();
+        deal.vesting_duration = amendment.vesting_duration;
+        deal.pending_amendment = None;
+
+        emit!(AmendmentAccepted {
             order_id: deal.order_id,
             deal: deal.key(),
             project_owner: deal.project_owner,
             kol: deal.kol,
-            released_amount: deal.released_amount,
-            claim_amount: claimable_amount,
-            status: deal.status.clone(),
+            old_amount,
+            new_amount: deal.amount,
+            vesting_type: deal.vesting_type.clone(),
+            vesting_duration: deal.vesting_duration,
         });
 
         Ok(())
     }
 
-    pub fn set_eligibility_status(
-        ctx: Context<SetEligibilityStatus>,
-        new_status: EligibilityStatus,
-    ) -> Result<()> {
+    // Project owner adds tokens to an accepted deal, either as a bonus or on top of the vesting base
+    pub fn add_to_deal(ctx: Context<AddToDeal>, amount: u64, is_bonus: bool) -> Result<()> {
         let deal = &mut ctx.accounts.deal;
 
-        // Ensure the signer is the admin of the escrow
-        require!(
-            ctx.accounts.signer.key() == ctx.accounts.escrow.admin,
-            ErrorCode::UnauthorizedSigner
-        );
+        transition(Some(&deal.status), DealAction::TopUp)?;
+        require!(amount > 0, ErrorCode::InvalidAmount);
 
-        // Set `done_obligation_time` if transitioning to `PartiallyEligible` or `FullyEligible` for the first time
-        if (new_status == EligibilityStatus::PartiallyEligible
-            || new_status == EligibilityStatus::FullyEligible)
-            && deal.done_obligation_time == 0
-        {
-            deal.done_obligation_time = Clock::get()?.unix_timestamp;
-            msg!("done_obligation_time set to: {}", deal.done_obligation_time);
-        }
+        // Transfer the extra tokens from project owner to vault
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.project_owner_token_account.to_account_info(),
+            to: ctx.accounts.vault_token_account.to_account_info(),
+            authority: ctx.accounts.project_owner.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
 
-        // Update the eligibility status
-        deal.eligibility_status = new_status.clone();
+        token::transfer(CpiContext::new(cpi_program, cpi_accounts), amount)?;
+        ctx.accounts.mint_ledger.deposit(amount);
 
-        // Emit event for the status update
-        emit!(EligibilityStatusUpdated {
+        let new_amount = deal.amount.checked_add(amount).unwrap();
+        record_amount_change(
+            &mut ctx.accounts.mint_stats,
+            &mut ctx.accounts.project_owner_volume,
+            deal.amount,
+            new_amount,
+        );
+        deal.amount = new_amount;
+        if is_bonus {
+            deal.bonus_amount = deal.bonus_amount.checked_add(amount).unwrap();
+        }
+
+        emit!(DealToppedUp {
             order_id: deal.order_id,
             deal: deal.key(),
+            project_owner: deal.project_owner,
             kol: deal.kol,
-            new_status: new_status.clone(),
+            added_amount: amount,
+            is_bonus,
+            new_amount: deal.amount,
         });
 
         Ok(())
     }
 
-    // Admin can update the max claimable percentage for early claim
-    pub fn update_max_claimable_percentage(
-        ctx: Context<UpdateMaxClaimablePercentage>,
-        new_percentage: u8,
+    // Payee token accounts are passed as remaining accounts, in the order they were registered
+    pub fn resolve_deal<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ResolveDeal<'info>>,
     ) -> Result<()> {
-        let escrow = &mut ctx.accounts.escrow;
+        let deal = &mut ctx.accounts.deal;
+        let current_time = Clock::get()?.unix_timestamp;
 
-        // Ensure the new percentage is valid (0-100)
-        require!(new_percentage <= 100, ErrorCode::InvalidPercentage);
+        // Calculate how much the KOL can claim based on time or marketcap
+        let claimable_amount = calculate_vested_amount(
+            deal,
+            current_time,
+            ctx.accounts.escrow.max_claimable_after_obligation, // Handles the partial claim logic
+        );
 
-        // Update the max claimable percentage
-        escrow.max_claimable_after_obligation = new_percentage;
+        // Debugging output using `msg!()`
+        msg!("Calculated claimable amount: {}", claimable_amount);
+        msg!("Current time: {}", current_time);
+        msg!("Released amount before claim: {}", deal.released_amount);
 
-        emit!(MaxClaimablePercentageUpdated {
-            old_percentage: escrow.max_claimable_after_obligation,
-            new_percentage,
-        });
+        // Ensure they are claiming at least some amount
+        require!(claimable_amount > 0, ErrorCode::ExceedsVestedAmount);
+
+        // Transfer the rest to the receipt holder, honouring the KOL's payout address
+        init_kol_profile(
+            &mut ctx.accounts.kol_profile,
+            deal.kol,
+            ctx.bumps.kol_profile,
+        );
+        let recipient = claim_recipient(
+            deal,
+            ctx.accounts.receipt_token_account.as_ref(),
+            &ctx.accounts.kol_profile,
+        )?;
+        require!(
+            ctx.accounts.kol_token_account.owner == recipient,
+            ErrorCode::InvalidPayoutAccount
+        );
+
+        let distribution = distribute_claim(
+            deal,
+            claimable_amount,
+            ClaimAccounts {
+                token_program: &ctx.accounts.token_program,
+                vault_token_account: &ctx.accounts.vault_token_account,
+                vault_authority: &ctx.accounts.vault_authority,
+                vault_authority_bump: ctx.bumps.vault_authority,
+                recipient_token_account: ctx.accounts.kol_token_account.to_account_info(),
+                referrer_token_account: ctx.accounts.referrer_token_account.as_ref(),
+                referrer_stats: ctx.accounts.referrer_stats.as_mut(),
+                payee_token_accounts: ctx.remaining_accounts,
+            },
+        )?;
+        ctx.accounts.mint_ledger.release(claimable_amount)?;
+
+        let kol_amount = distribution.kol_amount;
+        record_claim(deal, claimable_amount, 0, recipient, distribution)?;
+        record_claim_stats(
+            &mut ctx.accounts.program_stats,
+            &mut ctx.accounts.mint_stats,
+            deal,
+            claimable_amount,
+        );
+        record_kol_claim(
+            &mut ctx.accounts.kol_profile,
+            &mut ctx.accounts.kol_earnings,
+            ctx.bumps.kol_earnings,
+            deal,
+            kol_amount,
+        );
+        init_project_owner_profile(
+            &mut ctx.accounts.project_owner_profile,
+            deal.project_owner,
+            ctx.bumps.project_owner_profile,
+        );
+        record_project_owner_claim(&mut ctx.accounts.project_owner_profile, deal);
 
         Ok(())
     }
 
-    // To check how much KOL can claim
-    pub fn check_claimable_amount(ctx: Context<CheckClaimableAmount>) -> Result<u64> {
-        let deal = &ctx.accounts.deal;
+    // Permissionless claim that only ever pays into associated token accounts,
+    // so a keeper can push vested tokens to KOLs on a schedule
+    pub fn crank_claim<'info>(ctx: Context<'_, '_, 'info, 'info, CrankClaim<'info>>) -> Result<()> {
+        let deal = &mut ctx.accounts.deal;
+
+        let claimable_amount = calculate_vested_amount(
+            deal,
+            Clock::get()?.unix_timestamp,
+            ctx.accounts.escrow.max_claimable_after_obligation,
+        );
+        require!(claimable_amount > 0, ErrorCode::ExceedsVestedAmount);
+
+        init_kol_profile(
+            &mut ctx.accounts.kol_profile,
+            deal.kol,
+            ctx.bumps.kol_profile,
+        );
+        let recipient = claim_recipient(
+            deal,
+            ctx.accounts.receipt_token_account.as_deref(),
+            &ctx.accounts.kol_profile,
+        )?;
+        require!(
+            ctx.accounts.recipient.key() == recipient,
+            ErrorCode::InvalidPayoutAccount
+        );
+
+        // Referrer and payees have to be paid into their associated token accounts too
+        if let Some(referrer_token_account) = ctx.accounts.referrer_token_account.as_ref() {
+            require!(
+                referrer_token_account.key()
+                    == get_associated_token_address(&deal.referrer, &deal.mint),
+                ErrorCode::InvalidPayoutAccount
+            );
+        }
+        for (payee, payee_token_info) in deal.payees[..deal.payee_count as usize]
+            .iter()
+            .zip(ctx.remaining_accounts.iter())
+        {
+            require!(
+                payee_token_info.key() == get_associated_token_address(&payee.wallet, &deal.mint),
+                ErrorCode::InvalidPayees
+            );
+        }
+
+        // Reimburse the cranker with a share of the claim, so cranking often can't eat the deal
+        let crank_fee = ctx
+            .accounts
+            .crank_config
+            .as_ref()
+            .map_or(0, |crank_config| {
+                crank_fee(claimable_amount, crank_config.fee_bps)
+            });
+        if crank_fee > 0 {
+            let cranker_token_account = ctx
+                .accounts
+                .cranker_token_account
+                .as_ref()
+                .ok_or(ErrorCode::MissingCrankerTokenAccount)?;
+
+            transfer_from_vault(
+                &ctx.accounts.token_program,
+                &ctx.accounts.vault_token_account,
+                &cranker_token_account.to_account_info(),
+                &ctx.accounts.vault_authority,
+                ctx.bumps.vault_authority,
+                crank_fee,
+            )?;
+        }
+
+        let distribution = distribute_claim(
+            deal,
+            claimable_amount - crank_fee,
+            ClaimAccounts {
+                token_program: &ctx.accounts.token_program,
+                vault_token_account: &ctx.accounts.vault_token_account,
+                vault_authority: &ctx.accounts.vault_authority,
+                vault_authority_bump: ctx.bumps.vault_authority,
+                recipient_token_account: ctx.accounts.recipient_token_account.to_account_info(),
+                referrer_token_account: ctx.accounts.referrer_token_account.as_deref(),
+                referrer_stats: ctx.accounts.referrer_stats.as_deref_mut(),
+                payee_token_accounts: ctx.remaining_accounts,
+            },
+        )?;
+        ctx.accounts.mint_ledger.release(claimable_amount)?;
+
+        let kol_amount = distribution.kol_amount;
+        record_claim(deal, claimable_amount, crank_fee, recipient, distribution)?;
+        record_claim_stats(
+            &mut ctx.accounts.program_stats,
+            &mut ctx.accounts.mint_stats,
+            deal,
+            claimable_amount,
+        );
+        record_kol_claim(
+            &mut ctx.accounts.kol_profile,
+            &mut ctx.accounts.kol_earnings,
+            ctx.bumps.kol_earnings,
+            deal,
+            kol_amount,
+        );
+        init_project_owner_profile(
+            &mut ctx.accounts.project_owner_profile,
+            deal.project_owner,
+            ctx.bumps.project_owner_profile,
+        );
+        record_project_owner_claim(&mut ctx.accounts.project_owner_profile, deal);
+
+        Ok(())
+    }
+
+    // KOL claims from many deals of the same mint with a single transfer. Deals are passed as
+    // remaining accounts, each followed by the KOL's receipt token account when it has a receipt,
+    // then by the project owner's profile
+    pub fn resolve_many<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ResolveMany<'info>>,
+    ) -> Result<()> {
         let current_time = Clock::get()?.unix_timestamp;
+        let kol = ctx.accounts.kol.key();
+        let mut total_claim: u64 = 0;
+        let mut remaining_accounts = ctx.remaining_accounts.iter();
+        init_kol_profile(&mut ctx.accounts.kol_profile, kol, ctx.bumps.kol_profile);
+
+        while let Some(deal_info) = remaining_accounts.next() {
+            let mut deal = Account::<Deal>::try_from(deal_info)?;
+            require!(deal.kol == kol, ErrorCode::UnauthorizedSigner);
+            require!(
+                deal.mint == ctx.accounts.mint.key(),
+                ErrorCode::InvalidBatchDeal
+            );
+
+            // Splits need their own token accounts, those deals go through `resolve_deal`
+            require!(
+                deal.payee_count == 0 && deal.referrer_bps == 0,
+                ErrorCode::InvalidBatchDeal
+            );
+
+            // The KOL must still hold the receipt, otherwise the claim belongs to someone else
+            if deal.receipt_mint != Pubkey::default() {
+                let receipt_info = remaining_accounts
+                    .next()
+                    .ok_or(ErrorCode::MissingReceiptAccount)?;
+                let receipt = Account::<TokenAccount>::try_from(receipt_info)?;
+                require!(
+                    receipt.mint == deal.receipt_mint
+                        && receipt.amount == 1
+                        && receipt.owner == kol,
+                    ErrorCode::UnauthorizedSigner
+                );
+            }
+
+            // Followed by the profile of the deal's project owner, created with the deal
+            let project_owner_profile_info = remaining_accounts
+                .next()
+                .ok_or(ErrorCode::MissingProjectOwnerProfile)?;
+            let mut project_owner_profile =
+                Account::<ProjectOwnerProfile>::try_from(project_owner_profile_info)?;
+            require!(
+                project_owner_profile.project_owner == deal.project_owner,
+                ErrorCode::MissingProjectOwnerProfile
+            );
+
+            let recipient = kol_payout_owner(&deal, &ctx.accounts.kol_profile);
+            require!(
+                ctx.accounts.kol_token_account.owner == recipient,
+                ErrorCode::InvalidPayoutAccount
+            );
+
+            let claimable_amount = calculate_vested_amount(
+                &deal,
+                current_time,
+                ctx.accounts.escrow.max_claimable_after_obligation,
+            );
+            if claimable_amount == 0 {
+                msg!("Nothing to claim for deal {}", deal.key());
+                continue;
+            }
+            total_claim = total_claim.checked_add(claimable_amount).unwrap();
+
+            record_claim(
+                &mut deal,
+                claimable_amount,
+                0,
+                recipient,
+                ClaimDistribution {
+                    kol_amount: claimable_amount,
+                    payee_payouts: Vec::new(),
+                    referrer_amount: 0,
+                },
+            )?;
+            record_claim_stats(
+                &mut ctx.accounts.program_stats,
+                &mut ctx.accounts.mint_stats,
+                &deal,
+                claimable_amount,
+            );
+            record_kol_claim(
+                &mut ctx.accounts.kol_profile,
+                &mut ctx.accounts.kol_earnings,
+                ctx.bumps.kol_earnings,
+                &deal,
+                claimable_amount, // Batched deals have no splits, the KOL gets all of it
+            );
+            record_project_owner_claim(&mut project_owner_profile, &deal);
+
+            // Persist right away so a deal passed twice can't be claimed twice
+            deal.exit(&crate::ID)?;
+            project_owner_profile.exit(&crate::ID)?;
+        }
+
+        // Ensure they are claiming at least some amount
+        require!(total_claim > 0, ErrorCode::ExceedsVestedAmount);
+
+        // One transfer covering every deal in the batch
+        transfer_from_vault(
+            &ctx.accounts.token_program,
+            &ctx.accounts.vault_token_account,
+            &ctx.accounts.kol_token_account.to_account_info(),
+            &ctx.accounts.vault_authority,
+            ctx.bumps.vault_authority,
+            total_claim,
+        )?;
+        ctx.accounts.mint_ledger.release(total_claim)?;
+
+        Ok(())
+    }
+
+    // Admin sets the fee, in basis points of each claim and capped at MAX_CRANK_FEE_BPS, paid to whoever cranks it
+    pub fn set_crank_fee(ctx: Context<SetCrankFee>, fee_bps: u16) -> Result<()> {
+        require!(fee_bps <= MAX_CRANK_FEE_BPS, ErrorCode::CrankFeeTooHigh);
+
+        let crank_config = &mut ctx.accounts.crank_config;
+        let old_fee_bps = crank_config.fee_bps;
+
+        crank_config.mint = ctx.accounts.mint.key();
+        crank_config.fee_bps = fee_bps;
+        crank_config.bump = ctx.bumps.crank_config;
+
+        emit!(CrankFeeUpdated {
+            mint: crank_config.mint,
+            old_fee_bps,
+            new_fee_bps: fee_bps,
+        });
+
+        Ok(())
+    }
+
+    // Admin terminates a breached deal, refunding the unvested tokens to the project owner.
+    // Payee token accounts are passed as remaining accounts, in the order they were registered
+    pub fn clawback<'info>(
+        ctx: Context<'_, '_, 'info, 'info, Clawback<'info>>,
+        pay_vested: bool,
+    ) -> Result<()> {
+        let deal = &mut ctx.accounts.deal;
+
+        // Disputed deals can be clawed back too, that's how the admin ends a dispute over a breach
+        let new_status = transition(Some(&deal.status), DealAction::Clawback)?;
+        let was_disputed = deal.status == DealStatus::Disputed;
+        init_kol_profile(
+            &mut ctx.accounts.kol_profile,
+            deal.kol,
+            ctx.bumps.kol_profile,
+        );
+
+        // What the KOL had already earned but not yet claimed
+        let kol_amount = if pay_vested {
+            calculate_vested_amount(
+                deal,
+                Clock::get()?.unix_timestamp,
+                ctx.accounts.escrow.max_claimable_after_obligation,
+            )
+        } else {
+            0
+        };
+
+        let refunded_amount = deal
+            .amount
+            .checked_sub(deal.released_amount)
+            .unwrap()
+            .checked_sub(kol_amount)
+            .unwrap();
 
-        // Log the current time and vesting status
         msg!(
-            "Checking claimable amount at current time: {}",
-            current_time
+            "Clawback: paying KOL {}, refunding {}",
+            kol_amount,
+            refunded_amount
         );
-        msg!("Deal order_id: {:?}", deal.order_id);
 
-        // Calculate how much the KOL can claim
-        let claimable_amount = calculate_vested_amount(
+        let seeds = &[b"vault_authority".as_ref(), &[ctx.bumps.vault_authority]];
+        let signer = &[&seeds[..]];
+
+        // The vested part is split like any other claim, referrer and payees included
+        let mut kol_share = 0;
+        if kol_amount > 0 {
+            require!(
+                ctx.accounts.kol_token_account.owner
+                    == claim_recipient(
+                        deal,
+                        ctx.accounts.receipt_token_account.as_ref(),
+                        &ctx.accounts.kol_profile,
+                    )?,
+                ErrorCode::InvalidPayoutAccount
+            );
+
+            let distribution = distribute_claim(
+                deal,
+                kol_amount,
+                ClaimAccounts {
+                    token_program: &ctx.accounts.token_program,
+                    vault_token_account: &ctx.accounts.vault_token_account,
+                    vault_authority: &ctx.accounts.vault_authority,
+                    vault_authority_bump: ctx.bumps.vault_authority,
+                    recipient_token_account: ctx.accounts.kol_token_account.to_account_info(),
+                    referrer_token_account: ctx.accounts.referrer_token_account.as_ref(),
+                    referrer_stats: ctx.accounts.referrer_stats.as_mut(),
+                    payee_token_accounts: ctx.remaining_accounts,
+                },
+            )?;
+            kol_share = distribution.kol_amount;
+        }
+
+        if refunded_amount > 0 {
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.vault_token_account.to_account_info(),
+                to: ctx.accounts.project_owner_token_account.to_account_info(),
+                authority: ctx.accounts.vault_authority.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+
+            token::transfer(
+                CpiContext::new_with_signer(cpi_program, cpi_accounts, signer),
+                refunded_amount,
+            )?;
+        }
+
+        let mint_ledger = &mut ctx.accounts.mint_ledger;
+        mint_ledger.release(kol_amount)?;
+        mint_ledger.refund(refunded_amount)?;
+
+        // A breached deal forfeits the KOL's stake to the project owner
+        let slashed_stake = deal.kol_stake;
+        transfer_from_vault(
+            &ctx.accounts.token_program,
+            &ctx.accounts.vault_token_account,
+            &ctx.accounts.project_owner_token_account.to_account_info(),
+            &ctx.accounts.vault_authority,
+            ctx.bumps.vault_authority,
+            slashed_stake,
+        )?;
+        ctx.accounts.mint_ledger.refund(slashed_stake)?;
+        deal.kol_stake = 0;
+
+        record_release(deal, kol_amount);
+        deal.status = new_status;
+
+        // Clawing back a disputed deal settles the dispute in favour of the project owner
+        let kol_profile = &mut ctx.accounts.kol_profile;
+        if was_disputed {
+            kol_profile.disputes_lost = kol_profile.disputes_lost.checked_add(1).unwrap();
+        }
+        record_kol_claim(
+            kol_profile,
+            &mut ctx.accounts.kol_earnings,
+            ctx.bumps.kol_earnings,
             deal,
-            current_time,
-            ctx.accounts.escrow.max_claimable_after_obligation,
+            kol_share,
+        );
+
+        emit!(DealStatusChanged {
+            order_id: deal.order_id,
+            deal: deal.key(),
+            project_owner: deal.project_owner,
+            kol: deal.kol,
+            status: deal.status.clone(),
+        });
+
+        emit!(DealClawedBack {
+            order_id: deal.order_id,
+            deal: deal.key(),
+            project_owner: deal.project_owner,
+            kol: deal.kol,
+            kol_amount,
+            refunded_amount,
+        });
+
+        if slashed_stake > 0 {
+            emit!(KolStakeSlashed {
+                order_id: deal.order_id,
+                deal: deal.key(),
+                kol: deal.kol,
+                project_owner: deal.project_owner,
+                amount: slashed_stake,
+            });
+        }
+
+        Ok(())
+    }
+
+    // Either party freezes the deal until the admin settles it
+    pub fn open_dispute(ctx: Context<OpenDispute>, reason: DisputeReason) -> Result<()> {
+        let deal = &mut ctx.accounts.deal;
+
+        let new_status = transition(Some(&deal.status), DealAction::OpenDispute)?;
+        require!(
+            reason != DisputeReason::None,
+            ErrorCode::MissingDisputeReason
+        );
+
+        deal.status = new_status;
+        deal.dispute_reason = reason.clone();
+
+        let project_owner_profile = &mut ctx.accounts.project_owner_profile;
+        init_project_owner_profile(
+            project_owner_profile,
+            deal.project_owner,
+            ctx.bumps.project_owner_profile,
+        );
+        if ctx.accounts.signer.key() == deal.project_owner {
+            project_owner_profile.disputes_opened = project_owner_profile
+                .disputes_opened
+                .checked_add(1)
+                .unwrap();
+            emit_project_owner_profile(project_owner_profile);
+        }
+
+        emit!(DealStatusChanged {
+            order_id: deal.order_id,
+            deal: deal.key(),
+            project_owner: deal.project_owner,
+            kol: deal.kol,
+            status: deal.status.clone(),
+        });
+
+        emit!(DisputeOpened {
+            order_id: deal.order_id,
+            deal: deal.key(),
+            opened_by: ctx.accounts.signer.key(),
+            reason,
+        });
+
+        Ok(())
+    }
+
+    // Admin settles a dispute, refunding everything unreleased when the project owner wins
+    pub fn settle_dispute(ctx: Context<SettleDispute>, outcome: DisputeOutcome) -> Result<()> {
+        let deal = &mut ctx.accounts.deal;
+
+        let new_status = transition(
+            Some(&deal.status),
+            DealAction::SettleDispute {
+                outcome,
+                any_released: deal.released_amount > 0,
+            },
+        )?;
+
+        let refunded_amount = match outcome {
+            DisputeOutcome::KolWins => 0,
+            DisputeOutcome::ProjectOwnerWins => {
+                deal.amount.checked_sub(deal.released_amount).unwrap()
+            }
+        };
+
+        let kol_profile = &mut ctx.accounts.kol_profile;
+        init_kol_profile(kol_profile, deal.kol, ctx.bumps.kol_profile);
+        if outcome == DisputeOutcome::ProjectOwnerWins {
+            kol_profile.disputes_lost = kol_profile.disputes_lost.checked_add(1).unwrap();
+        }
+
+        let project_owner_profile = &mut ctx.accounts.project_owner_profile;
+        init_project_owner_profile(
+            project_owner_profile,
+            deal.project_owner,
+            ctx.bumps.project_owner_profile,
+        );
+        if outcome == DisputeOutcome::KolWins {
+            project_owner_profile.disputes_lost =
+                project_owner_profile.disputes_lost.checked_add(1).unwrap();
+            emit_project_owner_profile(project_owner_profile);
+        }
+
+        transfer_from_vault(
+            &ctx.accounts.token_program,
+            &ctx.accounts.vault_token_account,
+            &ctx.accounts.project_owner_token_account.to_account_info(),
+            &ctx.accounts.vault_authority,
+            ctx.bumps.vault_authority,
+            refunded_amount,
+        )?;
+        ctx.accounts.mint_ledger.refund(refunded_amount)?;
+
+        // Losing the dispute forfeits the KOL's stake to the project owner
+        let slashed_stake = match outcome {
+            DisputeOutcome::KolWins => 0,
+            DisputeOutcome::ProjectOwnerWins => deal.kol_stake,
+        };
+        transfer_from_vault(
+            &ctx.accounts.token_program,
+            &ctx.accounts.vault_token_account,
+            &ctx.accounts.project_owner_token_account.to_account_info(),
+            &ctx.accounts.vault_authority,
+            ctx.bumps.vault_authority,
+            slashed_stake,
+        )?;
+        ctx.accounts.mint_ledger.refund(slashed_stake)?;
+        deal.kol_stake = deal.kol_stake.checked_sub(slashed_stake).unwrap();
+
+        deal.status = new_status;
+        deal.dispute_reason = DisputeReason::None;
+
+        emit!(DealStatusChanged {
+            order_id: deal.order_id,
+            deal: deal.key(),
+            project_owner: deal.project_owner,
+            kol: deal.kol,
+            status: deal.status.clone(),
+        });
+
+        emit!(DisputeSettled {
+            order_id: deal.order_id,
+            deal: deal.key(),
+            outcome,
+            refunded_amount,
+        });
+
+        if slashed_stake > 0 {
+            emit!(KolStakeSlashed {
+                order_id: deal.order_id,
+                deal: deal.key(),
+                kol: deal.kol,
+                project_owner: deal.project_owner,
+                amount: slashed_stake,
+            });
+        }
+
+        Ok(())
+    }
+
+    // Permissionless return of the KOL's stake once the deal is completed, paid into the
+    // associated token account of the KOL's payout address
+    pub fn release_kol_stake(ctx: Context<ReleaseKolStake>) -> Result<()> {
+        let deal = &mut ctx.accounts.deal;
+
+        require!(
+            deal.status == DealStatus::Completed,
+            ErrorCode::DealNotFinished
+        );
+        let stake = deal.kol_stake;
+        require!(stake > 0, ErrorCode::NoKolStake);
+
+        transfer_from_vault(
+            &ctx.accounts.token_program,
+            &ctx.accounts.vault_token_account,
+            &ctx.accounts.recipient_token_account.to_account_info(),
+            &ctx.accounts.vault_authority,
+            ctx.bumps.vault_authority,
+            stake,
         )?;
+        ctx.accounts.mint_ledger.release(stake)?;
+        deal.kol_stake = 0;
+
+        emit!(KolStakeReleased {
+            order_id: deal.order_id,
+            deal: deal.key(),
+            kol: deal.kol,
+            recipient: ctx.accounts.recipient.key(),
+            amount: stake,
+        });
 
-        Ok(claimable_amount)
+        Ok(())
     }
-}
 
-// Enums
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
-pub enum VestingType {
-    Time,      // Vesting based on time
-    Marketcap, // Vesting based on market cap reaching a target
-    None,      // No vesting, the KOL can claim all tokens immediately
-}
+    // KOL chooses where their payouts go, either for all deals or for a single deal
+    pub fn set_payout_address(ctx: Context<SetPayoutAddress>, payout_owner: Pubkey) -> Result<()> {
+        let kol_profile = &mut ctx.accounts.kol_profile;
+        init_kol_profile(kol_profile, ctx.accounts.kol.key(), ctx.bumps.kol_profile);
+
+        // Passing the default pubkey clears the setting
+        let deal_key = match ctx.accounts.deal.as_mut() {
+            Some(deal) => {
+                deal.payout_owner = payout_owner;
+                Some(deal.key())
+            }
+            None => {
+                kol_profile.payout_owner = payout_owner;
+                None
+            }
+        };
+
+        emit!(PayoutAddressUpdated {
+            kol: ctx.accounts.kol.key(),
+            deal: deal_key,
+            payout_owner,
+        });
+
+        Ok(())
+    }
+
+    // Either party rates the other once the deal is over, one review per party and deal
+    pub fn submit_review(
+        ctx: Context<SubmitReview>,
+        rating: u8,
+        content_hash: [u8; 32],
+    ) -> Result<()> {
+        let deal = &ctx.accounts.deal;
+        let reviewer = ctx.accounts.reviewer.key();
+
+        require!(is_terminal(&deal.status), ErrorCode::DealNotFinished);
+        require!((1..=5).contains(&rating), ErrorCode::InvalidRating);
+        require!(deal.kol != deal.project_owner, ErrorCode::SelfReview);
+
+        let kol_profile = &mut ctx.accounts.kol_profile;
+        init_kol_profile(kol_profile, deal.kol, ctx.bumps.kol_profile);
+        let project_owner_profile = &mut ctx.accounts.project_owner_profile;
+        init_project_owner_profile(
+            project_owner_profile,
+            deal.project_owner,
+            ctx.bumps.project_owner_profile,
+        );
+
+        let reviewee = record_review(kol_profile, project_owner_profile, deal, reviewer, rating);
+
+        let review = &mut ctx.accounts.review;
+        review.deal = deal.key();
+        review.reviewer = reviewer;
+        review.reviewee = reviewee;
+        review.rating = rating;
+        review.content_hash = content_hash;
+        review.created_at = Clock::get()?.unix_timestamp;
+        review.bump = ctx.bumps.review;
+
+        emit!(ReviewSubmitted {
+            order_id: deal.order_id,
+            deal: deal.key(),
+            reviewer,
+            reviewee,
+            rating,
+            content_hash,
+        });
+
+        Ok(())
+    }
+
+    pub fn set_eligibility_status(
+        ctx: Context<SetEligibilityStatus>,
+        new_status: EligibilityStatus,
+    ) -> Result<()> {
+        let deal = &mut ctx.accounts.deal;
+
+        // Ensure the signer is the admin of the escrow
+        require!(
+            ctx.accounts.signer.key() == ctx.accounts.escrow.admin,
+            ErrorCode::UnauthorizedSigner
+        );
+
+        apply_eligibility_status(deal, new_status)
+    }
+
+    // Admin moves a deal back to a lower eligibility status, the only way to regress eligibility
+    pub fn revoke_eligibility(
+        ctx: Context<SetEligibilityStatus>,
+        new_status: EligibilityStatus,
+        reason: RevocationReason,
+    ) -> Result<()> {
+        let deal = &mut ctx.accounts.deal;
+        let old_status = deal.eligibility_status.clone();
+
+        transition(Some(&deal.status), DealAction::RevokeEligibility)?;
+        require!(
+            new_status < old_status,
+            ErrorCode::InvalidEligibilityTransition
+        );
+        require!(
+            reason != RevocationReason::None,
+            ErrorCode::MissingRevocationReason
+        );
+
+        // Clear the timestamps of the phases the deal is no longer in, they're set again if re-granted
+        if new_status < EligibilityStatus::FullyEligible {
+            deal.fully_eligible_time = 0;
+        }
+        if new_status < EligibilityStatus::PartiallyEligible {
+            deal.partially_eligible_time = 0;
+        }
+
+        deal.eligibility_status = new_status.clone();
+        deal.revocation_count = deal.revocation_count.checked_add(1).unwrap();
+        deal.last_revocation_reason = reason.clone();
+        deal.last_revocation_time = Clock::get()?.unix_timestamp;
+
+        emit!(EligibilityStatusUpdated {
+            order_id: deal.order_id,
+            deal: deal.key(),
+            kol: deal.kol,
+            new_status: new_status.clone(),
+        });
+
+        emit!(EligibilityRevoked {
+            order_id: deal.order_id,
+            deal: deal.key(),
+            kol: deal.kol,
+            old_status,
+            new_status,
+            reason,
+        });
+
+        Ok(())
+    }
+
+    // Admin updates eligibility of many deals at once, passed as remaining accounts
+    // in the same order as `new_statuses`
+    pub fn batch_set_eligibility_status<'info>(
+        ctx: Context<'_, '_, 'info, 'info, BatchSetEligibilityStatus<'info>>,
+        new_statuses: Vec<EligibilityStatus>,
+    ) -> Result<()> {
+        require!(
+            ctx.remaining_accounts.len() == new_statuses.len(),
+            ErrorCode::InvalidBatchLength
+        );
+
+        for (deal_info, new_status) in ctx.remaining_accounts.iter().zip(new_statuses) {
+            let mut deal = Account::<Deal>::try_from(deal_info)?;
+            apply_eligibility_status(&mut deal, new_status)?;
+            deal.exit(&crate::ID)?;
+        }
+
+        Ok(())
+    }
+
+    // Admin can update the max claimable percentage for early claim
+    pub fn update_max_claimable_percentage(
+        ctx: Context<UpdateMaxClaimablePercentage>,
+        new_percentage: u8,
+    ) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+
+        // Ensure the new percentage is valid (0-100)
+        require!(new_percentage <= 100, ErrorCode::InvalidPercentage);
+
+        // Update the max claimable percentage, keeping the old one for the event
+        let old_percentage = escrow.max_claimable_after_obligation;
+        escrow.max_claimable_after_obligation = new_percentage;
+
+        emit!(MaxClaimablePercentageUpdated {
+            old_percentage,
+            new_percentage,
+        });
+
+        Ok(())
+    }
+
+    // Read-only projection of what the KOL would have at each timestamp under each eligibility
+    // scenario, meant for simulateTransaction. Takes either an existing deal or the terms of a new one
+    pub fn simulate_vesting(
+        ctx: Context<SimulateVesting>,
+        terms: Option<VestingTerms>,
+        scenarios: Vec<EligibilityScenario>,
+        timestamps: Vec<i64>,
+    ) -> Result<Vec<VestingProjection>> {
+        let base_deal = match (ctx.accounts.deal.as_ref(), terms) {
+            (Some(deal), None) => (**deal).clone(),
+            (None, Some(terms)) => simulated_deal(&terms),
+            _ => return err!(ErrorCode::InvalidSimulationInput),
+        };
+        require!(
+            scenarios.len() * timestamps.len() <= MAX_SIMULATION_POINTS,
+            ErrorCode::TooManySimulationPoints
+        );
+
+        let mut projections = Vec::with_capacity(scenarios.len() * timestamps.len());
+        for scenario in scenarios.iter() {
+            let mut deal = base_deal.clone();
+            apply_eligibility_scenario(&mut deal, scenario);
+
+            for timestamp in timestamps.iter() {
+                let claimable_amount = calculate_vested_amount(
+                    &deal,
+                    *timestamp,
+                    ctx.accounts.escrow.max_claimable_after_obligation,
+                );
+
+                projections.push(VestingProjection {
+                    timestamp: *timestamp,
+                    eligibility_status: scenario.eligibility_status.clone(),
+                    vested_amount: deal.released_amount.checked_add(claimable_amount).unwrap(),
+                    claimable_amount,
+                });
+            }
+        }
+
+        Ok(projections)
+    }
+
+    // To check how much KOL can claim, along with the rest of the vesting schedule
+    pub fn check_claimable_amount(
+        ctx: Context<CheckClaimableAmount>,
+    ) -> Result<ClaimableBreakdown> {
+        let deal = &ctx.accounts.deal;
+        let current_time = Clock::get()?.unix_timestamp;
+
+        // Log the current time and vesting status
+        msg!(
+            "Checking claimable amount at current time: {}",
+            current_time
+        );
+        msg!("Deal order_id: {:?}", deal.order_id);
+
+        // Calculate how much the KOL can claim
+        Ok(calculate_claimable_breakdown(
+            deal,
+            current_time,
+            ctx.accounts.escrow.max_claimable_after_obligation,
+        ))
+    }
+
+    // Creates the ledger of a mint whose vault was funded before ledgers existed, so
+    // its deals can be settled without waiting for a new deal in that mint. The vault
+    // balance is taken as outstanding liability
+    pub fn init_mint_ledger(ctx: Context<InitMintLedger>) -> Result<()> {
+        let mint_ledger = &mut ctx.accounts.mint_ledger;
+        mint_ledger.mint = ctx.accounts.mint.key();
+        mint_ledger.bump = ctx.bumps.mint_ledger;
+        mint_ledger.deposit(ctx.accounts.vault_token_account.amount);
+
+        Ok(())
+    }
+
+    // Grows a deal or profile created by an older version of the program to its current
+    // size. Their fields are only ever appended, and zeroed bytes decode as unset
+    pub fn migrate_account(ctx: Context<MigrateAccount>) -> Result<()> {
+        let account = &ctx.accounts.account;
+        let current_len = account.data_len();
+        let target_len = {
+            let data = account.try_borrow_data()?;
+            let discriminator = data.get(..8).ok_or(ErrorCode::AccountNotMigratable)?;
+            if discriminator == Deal::DISCRIMINATOR {
+                8 + std::mem::size_of::<Deal>()
+            } else if discriminator == KolProfile::DISCRIMINATOR {
+                8 + std::mem::size_of::<KolProfile>()
+            } else if discriminator == ProjectOwnerProfile::DISCRIMINATOR {
+                8 + std::mem::size_of::<ProjectOwnerProfile>()
+            } else {
+                return err!(ErrorCode::AccountNotMigratable);
+            }
+        };
+        require!(current_len < target_len, ErrorCode::AccountAlreadyMigrated);
+
+        let rent = Rent::get()?
+            .minimum_balance(target_len)
+            .saturating_sub(account.lamports());
+        if rent > 0 {
+            let cpi_accounts = system_program::Transfer {
+                from: ctx.accounts.payer.to_account_info(),
+                to: account.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.system_program.to_account_info();
+            system_program::transfer(CpiContext::new(cpi_program, cpi_accounts), rent)?;
+        }
+        account.realloc(target_len, true)?;
+
+        msg!(
+            "Account migrated from {} to {} bytes",
+            current_len,
+            target_len
+        );
+
+        Ok(())
+    }
+
+    // Fails when the shared vault of a mint holds less than what its deals still owe,
+    // meant for monitors running it through simulateTransaction
+    pub fn verify_solvency(ctx: Context<VerifySolvency>) -> Result<()> {
+        let mint_ledger = &ctx.accounts.mint_ledger;
+        let vault_balance = ctx.accounts.vault_token_account.amount;
+
+        msg!(
+            "Vault balance: {}, outstanding liability: {}",
+            vault_balance,
+            mint_ledger.outstanding_liability
+        );
+        require!(
+            vault_balance >= mint_ledger.outstanding_liability,
+            ErrorCode::VaultInsolvent
+        );
+
+        Ok(())
+    }
+}
+
+// Enums
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub enum VestingType {
+    Time,      // Vesting based on time
+    Marketcap, // Vesting based on market cap reaching a target
+    None,      // No vesting, the KOL can claim all tokens immediately
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, Debug)]
+pub enum DealStatus {
+    Created,
+    Accepted,
+    Rejected,
+    PartialCompleted,
+    Completed,
+    Disputed,
+    Resolved,
+    ClawedBack, // Terminated by the admin after a breach, unvested tokens refunded
+}
+
+// Variants are ordered from least to most eligible
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum EligibilityStatus {
+    NotEligible,       // No tokens can be claimed yet
+    PartiallyEligible, // Can claim max_claimable_after_obligation (e.g., 20%)
+    FullyEligible,     // Can claim all remaining tokens (market cap or time conditions met)
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub enum RevocationReason {
+    None,
+    PostDeleted,
+    TermsBreached,
+    VerificationError,
+    Other,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub enum DisputeReason {
+    None,
+    Unresolved,
+    Other,
+    // Add more variants as needed
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DisputeOutcome {
+    KolWins,          // Deal goes back to where it was before the dispute
+    ProjectOwnerWins, // Unreleased tokens are refunded to the project owner
+}
+
+// Error codes
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Invalid deal status")]
+    InvalidDealStatus,
+    #[msg("Unauthorized signer")]
+    UnauthorizedSigner,
+    #[msg("Exceeds vested amount")]
+    ExceedsVestedAmount,
+    #[msg("Invalid vesting type")]
+    InvalidVestingType,
+    #[msg("Invalid percentage value")]
+    InvalidPercentage,
+    #[msg("Invalid amendment terms")]
+    InvalidAmendment,
+    #[msg("No pending amendment")]
+    NoPendingAmendment,
+    #[msg("Amendment deadline has passed")]
+    AmendmentExpired,
+    #[msg("Project owner signature required to top up the deal")]
+    ProjectOwnerSignatureRequired,
+    #[msg("Invalid amount")]
+    InvalidAmount,
+    #[msg("Invalid payees")]
+    InvalidPayees,
+    #[msg("Referrer accounts are required for this deal")]
+    MissingReferrerAccounts,
+    #[msg("Token account is not owned by the KOL's payout address")]
+    InvalidPayoutAccount,
+    #[msg("Deal receipt token account is required")]
+    MissingReceiptAccount,
+    #[msg("Cranker token account is required")]
+    MissingCrankerTokenAccount,
+    #[msg("Deal cannot be resolved in a batch")]
+    InvalidBatchDeal,
+    #[msg("Number of deals does not match the number of statuses")]
+    InvalidBatchLength,
+    #[msg("Invalid eligibility status transition")]
+    InvalidEligibilityTransition,
+    #[msg("A reason is required to revoke eligibility")]
+    MissingRevocationReason,
+    #[msg("Deal is no longer pending")]
+    DealNotPending,
+    #[msg("Deal has not been accepted")]
+    DealNotActive,
+    #[msg("Deal is not disputed")]
+    DealNotDisputed,
+    #[msg("Deal is disputed")]
+    DealDisputed,
+    #[msg("Deal is closed")]
+    DealClosed,
+    #[msg("Deal already exists")]
+    DealAlreadyExists,
+    #[msg("Deal has not been created")]
+    DealNotCreated,
+    #[msg("A reason is required to open a dispute")]
+    MissingDisputeReason,
+    #[msg("Pass either a deal or vesting terms to simulate")]
+    InvalidSimulationInput,
+    #[msg("Too many simulation points")]
+    TooManySimulationPoints,
+    #[msg("Vault balance is below the outstanding liability")]
+    VaultInsolvent,
+    #[msg("Rating must be between 1 and 5")]
+    InvalidRating,
+    #[msg("Deal is not finished yet")]
+    DealNotFinished,
+    #[msg("Missing or mismatched project owner profile account")]
+    MissingProjectOwnerProfile,
+    #[msg("The deal requires a stake token account from the KOL")]
+    MissingStakeAccount,
+    #[msg("Deal has no KOL stake to release")]
+    NoKolStake,
+    #[msg("Deal has the same KOL and project owner, nobody to review")]
+    SelfReview,
+    #[msg("Crank fee is above the maximum")]
+    CrankFeeTooHigh,
+    #[msg("The referrer can't be a party of the deal")]
+    InvalidReferrer,
+    #[msg("Mint ledger has less outstanding liability than the amount paid out")]
+    LedgerUnderflow,
+    #[msg("Only deals and profiles can be migrated")]
+    AccountNotMigratable,
+    #[msg("Account already has the current size")]
+    AccountAlreadyMigrated,
+}
+
+// Structs
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct DealAmendment {
+    pub proposer: Pubkey,
+    pub amount: u64,
+    pub vesting_type: VestingType,
+    pub vesting_duration: i64,
+    pub deadline: i64, // Proposal must be accepted before this timestamp
+}
+
+// Share of every claim routed to a KOL's agency or manager
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PayeeShare {
+    pub wallet: Pubkey,
+    pub share_bps: u16,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct PayeePayout {
+    pub wallet: Pubkey,
+    pub amount: u64,
+}
+
+// Vesting schedule of a deal as seen at a given time, returned by `check_claimable_amount`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct ClaimableBreakdown {
+    pub claimable_now: u64,
+    pub total_vested: u64, // Released plus claimable now
+    pub released_amount: u64,
+    pub locked_amount: u64,
+    pub next_unlock_time: i64, // 0 when nothing unlocks without an eligibility update
+    pub next_unlock_amount: u64,
+    pub fully_vested_time: i64, // 0 while the deal isn't fully eligible
+    pub eligibility_status: EligibilityStatus,
+}
+
+// Terms of a deal that doesn't exist yet, for `simulate_vesting`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct VestingTerms {
+    pub amount: u64,
+    pub vesting_type: VestingType,
+    pub vesting_duration: i64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct EligibilityScenario {
+    pub eligibility_status: EligibilityStatus,
+    pub eligible_time: i64, // When the status is reached, 0 keeps the deal's own timestamps
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct VestingProjection {
+    pub timestamp: i64,
+    pub eligibility_status: EligibilityStatus,
+    pub vested_amount: u64, // Released plus claimable at `timestamp`
+    pub claimable_amount: u64,
+}
+
+#[account]
+pub struct Escrow {
+    pub admin: Pubkey,
+    pub escrow_bump: u8,
+    pub max_claimable_after_obligation: u8,
+}
+
+#[account]
+pub struct Deal {
+    pub order_id: [u8; 16],
+    pub project_owner: Pubkey,
+    pub kol: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub released_amount: u64,
+    pub vesting_type: VestingType,
+    pub vesting_duration: i64,
+    pub start_time: i64,
+    pub accept_time: i64,
+    pub done_obligation_time: i64,
+    pub status: DealStatus,
+    pub dispute_reason: DisputeReason,
+    pub deal_bump: u8,
+    pub eligibility_status: EligibilityStatus,
+    pub pending_amendment: Option<DealAmendment>,
+    pub bonus_amount: u64, // Part of `amount` that is claimable without vesting
+    pub released_bonus_amount: u64, // Part of `released_amount` paid out of the bonus
+    pub payees: [PayeeShare; MAX_PAYEES], // Only the first `payee_count` entries are set
+    pub payee_count: u8,
+    pub referrer: Pubkey, // Default pubkey when the deal has no referrer
+    pub referrer_bps: u16,
+    pub payout_owner: Pubkey, // Overrides the KOL profile payout address when set
+    pub receipt_mint: Pubkey, // NFT whose holder owns the economic right to the claims
+    pub partially_eligible_time: i64,
+    pub fully_eligible_time: i64, // Start of time vesting
+    pub revocation_count: u32,
+    pub last_revocation_reason: RevocationReason,
+    pub last_revocation_time: i64,
+    pub required_kol_stake: u64, // Bond the KOL posts on acceptance, zero when none is required
+    pub kol_stake: u64,          // Bond currently held in the vault
+}
+
+// Per-mint settings for permissionless claim cranking
+#[account]
+pub struct CrankConfig {
+    pub mint: Pubkey,
+    pub fee_bps: u16, // Share of each cranked claim paid to the cranker, at most `MAX_CRANK_FEE_BPS`
+    pub bump: u8,
+}
+
+// Created by the first instruction touching one of the KOL's deals. Doubles as the KOL's
+// on-chain reputation, every counter is updated by the program itself
+#[account]
+#[derive(Default)]
+pub struct KolProfile {
+    pub kol: Pubkey,
+    pub payout_owner: Pubkey, // Default pubkey means payouts go to the KOL key itself
+    pub bump: u8,
+    pub deals_accepted: u64,
+    pub deals_rejected: u64,
+    pub deals_completed: u64,
+    pub disputes_lost: u64, // Disputes settled in favour of the project owner
+    pub total_obligation_time: i64, // Seconds from accept_time to done_obligation_time, summed
+    pub timed_obligations: u64, // Completed deals counted in `total_obligation_time`
+    pub rating_count: u64,
+    pub rating_total: u64, // Sum of the ratings project owners gave the KOL
+}
+
+impl KolProfile {
+    // Average seconds the KOL took to fulfil the obligation of a completed deal
+    pub fn average_obligation_time(&self) -> i64 {
+        if self.timed_obligations == 0 {
+            return 0;
+        }
+        self.total_obligation_time / self.timed_obligations as i64
+    }
+
+    // Average rating out of 5, scaled by 100 to keep two decimals
+    pub fn average_rating(&self) -> u64 {
+        average_rating(self.rating_total, self.rating_count)
+    }
+}
+
+// Created with the project owner's first deal. Doubles as their payment-reliability history,
+// every counter is updated by the program itself
+#[account]
+#[derive(Default)]
+pub struct ProjectOwnerProfile {
+    pub project_owner: Pubkey,
+    pub rating_count: u64,
+    pub rating_total: u64, // Sum of the ratings KOLs gave the project owner
+    pub bump: u8,
+    pub deals_funded: u64,
+    pub deals_rejected: u64, // Funded deals that were turned down and refunded
+    pub deals_completed: u64,
+    pub disputes_opened: u64, // Disputes opened by the project owner
+    pub disputes_lost: u64,   // Disputes settled in favour of the KOL
+}
+
+impl ProjectOwnerProfile {
+    // Average rating out of 5, scaled by 100 to keep two decimals
+    pub fn average_rating(&self) -> u64 {
+        average_rating(self.rating_total, self.rating_count)
+    }
+}
+
+// What a project owner funded in a given mint
+#[account]
+pub struct ProjectOwnerVolume {
+    pub project_owner: Pubkey,
+    pub mint: Pubkey,
+    pub deals_funded: u64,
+    pub total_funded: u64,
+    pub bump: u8,
+}
+
+impl ProjectOwnerVolume {
+    pub fn average_deal_size(&self) -> u64 {
+        if self.deals_funded == 0 {
+            return 0;
+        }
+        self.total_funded / self.deals_funded
+    }
+}
+
+// One party's rating of the other once a deal is over, the review text itself lives off chain
+#[account]
+pub struct Review {
+    pub deal: Pubkey,
+    pub reviewer: Pubkey,
+    pub reviewee: Pubkey,
+    pub rating: u8,
+    pub content_hash: [u8; 32], // Hash of the off-chain review content
+    pub created_at: i64,
+    pub bump: u8,
+}
+
+// Lifetime KOL share of the claims paid out on a KOL's deals in a given mint, after the
+// referrer, payees and cranker took theirs
+#[account]
+pub struct KolEarnings {
+    pub kol: Pubkey,
+    pub mint: Pubkey,
+    pub total_earned: u64,
+    pub bump: u8,
+}
+
+// Lifetime referral earnings of a referrer in a given mint
+#[account]
+pub struct ReferrerStats {
+    pub referrer: Pubkey,
+    pub mint: Pubkey,
+    pub total_earned: u64,
+    pub deals_referred: u64,
+    pub bump: u8,
+}
+
+// Running totals of the tokens of a mint moving through its shared vault
+#[account]
+pub struct MintLedger {
+    pub mint: Pubkey,
+    pub total_deposited: u64,       // Deal amounts, top-ups and KOL stakes
+    pub total_released: u64,        // Paid to the KOL side: claims, crank fees and returned stakes
+    pub total_refunded: u64,        // Returned to project owners, slashed stakes included
+    pub outstanding_liability: u64, // Still owed to KOLs or project owners
+    pub bump: u8,
+}
+
+impl MintLedger {
+    pub fn deposit(&mut self, amount: u64) {
+        self.total_deposited = self.total_deposited.checked_add(amount).unwrap();
+        self.outstanding_liability = self.outstanding_liability.checked_add(amount).unwrap();
+    }
+
+    pub fn release(&mut self, amount: u64) -> Result<()> {
+        self.total_released = self.total_released.checked_add(amount).unwrap();
+        self.outstanding_liability = self
+            .outstanding_liability
+            .checked_sub(amount)
+            .ok_or(ErrorCode::LedgerUnderflow)?;
+        Ok(())
+    }
+
+    pub fn refund(&mut self, amount: u64) -> Result<()> {
+        self.total_refunded = self.total_refunded.checked_add(amount).unwrap();
+        self.outstanding_liability = self
+            .outstanding_liability
+            .checked_sub(amount)
+            .ok_or(ErrorCode::LedgerUnderflow)?;
+        Ok(())
+    }
+}
+
+// Program-wide deal counters, readable by dashboards and other programs
+#[account]
+pub struct ProgramStats {
+    pub deals_created: u64,
+    pub deals_accepted: u64,
+    pub deals_rejected: u64,
+    pub deals_completed: u64,
+    pub bump: u8,
+}
+
+// Per-mint deal count and token volume
+#[account]
+pub struct MintStats {
+    pub mint: Pubkey,
+    pub deals_created: u64,
+    pub volume_escrowed: u64, // Amounts deals were funded with, top-ups and amendments included
+    pub volume_released: u64, // Claimed by KOLs, splits and fees included
+    pub bump: u8,
+}
+
+// ACCOUNTS
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + std::mem::size_of::<Escrow>(),
+        seeds = [b"escrow"],
+        bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(
+    amount: u64,
+    vesting_type: VestingType,
+    vesting_duration: i64,
+    order_id: [u8; 16],
+    referrer: Option<Pubkey>,
+)]
+pub struct CreateDeal<'info> {
+    #[account(mut)]
+    pub escrow: Account<'info, Escrow>,
+
+    #[account(
+        init,
+        payer = project_owner,
+        space = 8 + std::mem::size_of::<Deal>(),
+        seeds = [
+            b"deal",
+            order_id.as_ref(), 
+            project_owner.key().as_ref(),
+            kol.key().as_ref(),
+            mint.key().as_ref(),
+        ],
+        bump,
+    )]
+    pub deal: Account<'info, Deal>,
+
+    #[account(mut)]
+    pub project_owner: Signer<'info>,
+    /// CHECK: This is safe; we only read the public key
+    pub kol: AccountInfo<'info>,
+    pub mint: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = project_owner_token_account.owner == project_owner.key(),
+        constraint = project_owner_token_account.mint == mint.key()
+    )]
+    pub project_owner_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+       init_if_needed,
+    payer = project_owner,
+    seeds = [b"vault_token_account", mint.key().as_ref()],
+    bump,
+    token::mint = mint,
+    token::authority = vault_authority,
+    )]
+    pub vault_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = project_owner,
+        space = 8 + std::mem::size_of::<MintLedger>(),
+        seeds = [b"mint_ledger", mint.key().as_ref()],
+        bump,
+    )]
+    pub mint_ledger: Account<'info, MintLedger>,
+
+    #[account(
+        init_if_needed,
+        payer = project_owner,
+        space = 8 + std::mem::size_of::<ProgramStats>(),
+        seeds = [b"program_stats"],
+        bump,
+    )]
+    pub program_stats: Box<Account<'info, ProgramStats>>,
+
+    #[account(
+        init_if_needed,
+        payer = project_owner,
+        space = 8 + std::mem::size_of::<MintStats>(),
+        seeds = [b"mint_stats", mint.key().as_ref()],
+        bump,
+    )]
+    pub mint_stats: Box<Account<'info, MintStats>>,
+
+    #[account(
+        init_if_needed,
+        payer = project_owner,
+        space = 8 + std::mem::size_of::<ProjectOwnerProfile>(),
+        seeds = [b"project_owner_profile", project_owner.key().as_ref()],
+        bump,
+    )]
+    pub project_owner_profile: Box<Account<'info, ProjectOwnerProfile>>,
+
+    #[account(
+        init_if_needed,
+        payer = project_owner,
+        space = 8 + std::mem::size_of::<ProjectOwnerVolume>(),
+        seeds = [b"project_owner_volume", project_owner.key().as_ref(), mint.key().as_ref()],
+        bump,
+    )]
+    pub project_owner_volume: Box<Account<'info, ProjectOwnerVolume>>,
+
+    /// CHECK: This is the PDA acting as the vault authority
+    #[account(
+        seeds = [b"vault_authority"],
+        bump
+    )]
+    pub vault_authority: AccountInfo<'info>,
+
+    // Only required when the deal has a referrer
+    #[account(
+        init_if_needed,
+        payer = project_owner,
+        space = 8 + std::mem::size_of::<ReferrerStats>(),
+        seeds = [
+            b"referrer_stats",
+            referrer.unwrap_or_default().as_ref(),
+            mint.key().as_ref(),
+        ],
+        bump,
+    )]
+    pub referrer_stats: Option<Account<'info, ReferrerStats>>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptDeal<'info> {
+    #[account(mut)]
+    pub deal: Account<'info, Deal>,
+
+    // Allow either the KOL or the admin to sign, the signer pays for the receipt accounts
+    #[account(
+        mut,
+        constraint = signer.key() == deal.kol || signer.key() == escrow.admin,
+    )]
+    pub signer: Signer<'info>,
+
+    // Fetch the escrow account to get the admin's public key
+    pub escrow: Account<'info, Escrow>,
+
+    #[account(
+        mut,
+        seeds = [b"program_stats"],
+        bump = program_stats.bump,
+    )]
+    pub program_stats: Account<'info, ProgramStats>,
+
+    /// CHECK: This is safe; we only read the public key
+    #[account(address = deal.kol)]
+    pub kol: AccountInfo<'info>,
+
+    // Created on first use, holds the KOL's payout address and reputation
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = 8 + std::mem::size_of::<KolProfile>(),
+        seeds = [b"kol_profile", deal.kol.as_ref()],
+        bump,
+    )]
+    pub kol_profile: Account<'info, KolProfile>,
+
+    #[account(
+        init,
+        payer = signer,
+        seeds = [b"receipt", deal.key().as_ref()],
+        bump,
+        mint::decimals = 0,
+        mint::authority = vault_authority,
+    )]
+    pub receipt_mint: Account<'info, Mint>,
+
+    #[account(
+        init_if_needed,
+        payer = signer,
+        associated_token::mint = receipt_mint,
+        associated_token::authority = kol,
+    )]
+    pub kol_receipt_account: Account<'info, TokenAccount>,
+
+    // Only required when the deal asks the KOL for a stake
+    #[account(
+        mut,
+        constraint = kol_stake_token_account.owner == deal.kol,
+        constraint = kol_stake_token_account.mint == deal.mint
+    )]
+    pub kol_stake_token_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [b"vault_token_account", deal.mint.as_ref()],
+        bump,
+    )]
+    pub vault_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"mint_ledger", deal.mint.as_ref()],
+        bump = mint_ledger.bump,
+    )]
+    pub mint_ledger: Box<Account<'info, MintLedger>>,
+
+    /// CHECK: This is the PDA acting as the vault authority
+    #[account(
+        seeds = [b"vault_authority"],
+        bump
+    )]
+    pub vault_authority: AccountInfo<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct RejectDeal<'info> {
+    #[account(mut)]
+    pub deal: Account<'info, Deal>,
+
+    // Allow either the KOL or the admin to sign
+    #[account(
+        mut,
+        signer,
+        constraint = signer.key() == deal.kol || signer.key() == escrow.admin,
+    )]
+    /// CHECK: This is either the KOL or the admin
+    pub signer: AccountInfo<'info>,
+
+    // Fetch the escrow account to get the admin's public key
+    pub escrow: Account<'info, Escrow>,
+
+    // Created on first use, holds the KOL's payout address and reputation
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = 8 + std::mem::size_of::<KolProfile>(),
+        seeds = [b"kol_profile", deal.kol.as_ref()],
+        bump,
+    )]
+    pub kol_profile: Account<'info, KolProfile>,
+
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = 8 + std::mem::size_of::<ProjectOwnerProfile>(),
+        seeds = [b"project_owner_profile", deal.project_owner.as_ref()],
+        bump,
+    )]
+    pub project_owner_profile: Account<'info, ProjectOwnerProfile>,
+
+    #[account(
+        mut,
+        seeds = [b"program_stats"],
+        bump = program_stats.bump,
+    )]
+    pub program_stats: Account<'info, ProgramStats>,
+
+    #[account(mut)]
+    pub project_owner: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"vault_token_account", deal.mint.as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = vault_authority,
+    )]
+    pub vault_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"mint_ledger", deal.mint.as_ref()],
+        bump = mint_ledger.bump,
+    )]
+    pub mint_ledger: Account<'info, MintLedger>,
+
+    /// CHECK: This is the PDA acting as the vault authority
+    #[account(
+        seeds = [b"vault_authority"],
+        bump
+    )]
+    pub vault_authority: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = project_owner,
+    )]
+    pub project_owner_token_account: Account<'info, TokenAccount>,
+
+    pub mint: Account<'info, Mint>,
+
+    pub token_program: Program<'info, Token>,
+
+    pub system_program: Program<'info, System>,
+
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct ProposeAmendment<'info> {
+    #[account(mut)]
+    pub deal: Account<'info, Deal>,
+
+    // Only the two parties of the deal can negotiate
+    #[account(
+        constraint = signer.key() == deal.project_owner || signer.key() == deal.kol @ ErrorCode::UnauthorizedSigner,
+    )]
+    pub signer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptAmendment<'info> {
+    #[account(mut)]
+    pub deal: Account<'info, Deal>,
+
+    // Must be the counterparty of the proposer, checked in the instruction
+    pub signer: Signer<'info>,
+
+    /// CHECK: Only has to sign when the amendment tops up the vault
+    #[account(address = deal.project_owner @ ErrorCode::UnauthorizedSigner)]
+    pub project_owner: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        constraint = project_owner_token_account.owner == deal.project_owner,
+        constraint = project_owner_token_account.mint == deal.mint
+    )]
+    pub project_owner_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"vault_token_account", deal.mint.as_ref()],
+        bump,
+    )]
+    pub vault_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"mint_ledger", deal.mint.as_ref()],
+        bump = mint_ledger.bump,
+    )]
+    pub mint_ledger: Account<'info, MintLedger>,
+
+    #[account(
+        mut,
+        seeds = [b"mint_stats", deal.mint.as_ref()],
+        bump = mint_stats.bump,
+    )]
+    pub mint_stats: Account<'info, MintStats>,
+
+    #[account(
+        mut,
+        seeds = [b"project_owner_volume", deal.project_owner.as_ref(), deal.mint.as_ref()],
+        bump = project_owner_volume.bump,
+    )]
+    pub project_owner_volume: Account<'info, ProjectOwnerVolume>,
+
+    /// CHECK: This is the PDA acting as the vault authority
+    #[account(
+        seeds = [b"vault_authority"],
+        bump
+    )]
+    pub vault_authority: AccountInfo<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct AddToDeal<'info> {
+    #[account(mut)]
+    pub deal: Account<'info, Deal>,
+
+    #[account(
+        mut,
+        address = deal.project_owner @ ErrorCode::UnauthorizedSigner,
+    )]
+    pub project_owner: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = project_owner_token_account.owner == project_owner.key(),
+        constraint = project_owner_token_account.mint == deal.mint
+    )]
+    pub project_owner_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"vault_token_account", deal.mint.as_ref()],
+        bump,
+    )]
+    pub vault_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"mint_ledger", deal.mint.as_ref()],
+        bump = mint_ledger.bump,
+    )]
+    pub mint_ledger: Account<'info, MintLedger>,
+
+    #[account(
+        mut,
+        seeds = [b"mint_stats", deal.mint.as_ref()],
+        bump = mint_stats.bump,
+    )]
+    pub mint_stats: Account<'info, MintStats>,
+
+    #[account(
+        mut,
+        seeds = [b"project_owner_volume", deal.project_owner.as_ref(), deal.mint.as_ref()],
+        bump = project_owner_volume.bump,
+    )]
+    pub project_owner_volume: Account<'info, ProjectOwnerVolume>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ResolveDeal<'info> {
+    #[account(mut)]
+    pub deal: Account<'info, Deal>,
+
+    // Allow the KOL, the receipt holder or the admin to sign
+    #[account(
+        mut,
+        signer,
+        constraint = signer.key() == deal.kol
+            || signer.key() == escrow.admin
+            || receipt_token_account.as_ref().is_some_and(|receipt| receipt.owner == signer.key()),
+    )]
+    /// CHECK: This is either the KOL, the receipt holder or the admin
+    pub signer: AccountInfo<'info>,
+
+    // Fetch the escrow account to get the admin's public key
+    pub escrow: Account<'info, Escrow>,
+
+    #[account(mut)]
+    pub vault_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"mint_ledger", deal.mint.as_ref()],
+        bump = mint_ledger.bump,
+    )]
+    pub mint_ledger: Account<'info, MintLedger>,
+
+    #[account(
+        mut,
+        seeds = [b"program_stats"],
+        bump = program_stats.bump,
+    )]
+    pub program_stats: Account<'info, ProgramStats>,
+
+    #[account(
+        mut,
+        seeds = [b"mint_stats", deal.mint.as_ref()],
+        bump = mint_stats.bump,
+    )]
+    pub mint_stats: Account<'info, MintStats>,
+
+    // Must be owned by the receipt holder or the KOL's payout address, checked in the instruction
+    #[account(
+        mut,
+        constraint = kol_token_account.mint == deal.mint
+    )]
+    pub kol_token_account: Account<'info, TokenAccount>,
+
+    // Created on first use, holds the KOL's payout address and reputation
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = 8 + std::mem::size_of::<KolProfile>(),
+        seeds = [b"kol_profile", deal.kol.as_ref()],
+        bump,
+    )]
+    pub kol_profile: Account<'info, KolProfile>,
+
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = 8 + std::mem::size_of::<ProjectOwnerProfile>(),
+        seeds = [b"project_owner_profile", deal.project_owner.as_ref()],
+        bump,
+    )]
+    pub project_owner_profile: Account<'info, ProjectOwnerProfile>,
+
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = 8 + std::mem::size_of::<KolEarnings>(),
+        seeds = [b"kol_earnings", deal.kol.as_ref(), deal.mint.as_ref()],
+        bump,
+    )]
+    pub kol_earnings: Account<'info, KolEarnings>,
+
+    // Token account currently holding the deal receipt, required once the receipt is minted
+    #[account(
+        constraint = receipt_token_account.mint == deal.receipt_mint,
+        constraint = receipt_token_account.amount == 1,
+    )]
+    pub receipt_token_account: Option<Account<'info, TokenAccount>>,
+
+    #[account(
+        seeds = [b"vault_authority"],
+        bump
+    )]
+    pub vault_authority: AccountInfo<'info>,
+
+    // Only required when the deal has a referrer
+    #[account(
+        mut,
+        constraint = referrer_token_account.owner == deal.referrer,
+        constraint = referrer_token_account.mint == deal.mint
+    )]
+    pub referrer_token_account: Option<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"referrer_stats", deal.referrer.as_ref(), deal.mint.as_ref()],
+        bump = referrer_stats.bump,
+    )]
+    pub referrer_stats: Option<Account<'info, ReferrerStats>>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CrankClaim<'info> {
+    #[account(mut)]
+    pub deal: Box<Account<'info, Deal>>,
+
+    // Anyone can crank, paying for the recipient's associated token account if needed
+    #[account(mut)]
+    pub cranker: Signer<'info>,
+
+    pub escrow: Box<Account<'info, Escrow>>,
+
+    #[account(address = deal.mint)]
+    pub mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [b"vault_token_account", deal.mint.as_ref()],
+        bump,
+    )]
+    pub vault_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"mint_ledger", deal.mint.as_ref()],
+        bump = mint_ledger.bump,
+    )]
+    pub mint_ledger: Box<Account<'info, MintLedger>>,
+
+    #[account(
+        mut,
+        seeds = [b"program_stats"],
+        bump = program_stats.bump,
+    )]
+    pub program_stats: Box<Account<'info, ProgramStats>>,
+
+    #[account(
+        mut,
+        seeds = [b"mint_stats", deal.mint.as_ref()],
+        bump = mint_stats.bump,
+    )]
+    pub mint_stats: Box<Account<'info, MintStats>>,
+
+    /// CHECK: This is the PDA acting as the vault authority
+    #[account(
+        seeds = [b"vault_authority"],
+        bump
+    )]
+    pub vault_authority: AccountInfo<'info>,
+
+    /// CHECK: Must be the receipt holder or the KOL's payout address, checked in the instruction
+    pub recipient: AccountInfo<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = cranker,
+        associated_token::mint = mint,
+        associated_token::authority = recipient,
+    )]
+    pub recipient_token_account: Box<Account<'info, TokenAccount>>,
+
+    // Created on first use, holds the KOL's payout address and reputation
+    #[account(
+        init_if_needed,
+        payer = cranker,
+        space = 8 + std::mem::size_of::<KolProfile>(),
+        seeds = [b"kol_profile", deal.kol.as_ref()],
+        bump,
+    )]
+    pub kol_profile: Box<Account<'info, KolProfile>>,
+
+    #[account(
+        init_if_needed,
+        payer = cranker,
+        space = 8 + std::mem::size_of::<ProjectOwnerProfile>(),
+        seeds = [b"project_owner_profile", deal.project_owner.as_ref()],
+        bump,
+    )]
+    pub project_owner_profile: Box<Account<'info, ProjectOwnerProfile>>,
+
+    #[account(
+        init_if_needed,
+        payer = cranker,
+        space = 8 + std::mem::size_of::<KolEarnings>(),
+        seeds = [b"kol_earnings", deal.kol.as_ref(), deal.mint.as_ref()],
+        bump,
+    )]
+    pub kol_earnings: Box<Account<'info, KolEarnings>>,
+
+    #[account(
+        constraint = receipt_token_account.mint == deal.receipt_mint,
+        constraint = receipt_token_account.amount == 1,
+    )]
+    pub receipt_token_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    // Only required when the deal has a referrer
+    #[account(
+        mut,
+        constraint = referrer_token_account.owner == deal.referrer,
+        constraint = referrer_token_account.mint == deal.mint
+    )]
+    pub referrer_token_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [b"referrer_stats", deal.referrer.as_ref(), deal.mint.as_ref()],
+        bump = referrer_stats.bump,
+    )]
+    pub referrer_stats: Option<Box<Account<'info, ReferrerStats>>>,
+
+    #[account(
+        seeds = [b"crank_config", deal.mint.as_ref()],
+        bump = crank_config.bump,
+    )]
+    pub crank_config: Option<Box<Account<'info, CrankConfig>>>,
+
+    // Only required when a crank fee is set for the mint
+    #[account(
+        mut,
+        constraint = cranker_token_account.owner == cranker.key(),
+        constraint = cranker_token_account.mint == deal.mint
+    )]
+    pub cranker_token_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ResolveMany<'info> {
+    #[account(mut)]
+    pub kol: Signer<'info>,
+
+    pub escrow: Account<'info, Escrow>,
+
+    pub mint: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [b"vault_token_account", mint.key().as_ref()],
+        bump,
+    )]
+    pub vault_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"mint_ledger", mint.key().as_ref()],
+        bump = mint_ledger.bump,
+    )]
+    pub mint_ledger: Account<'info, MintLedger>,
+
+    #[account(
+        mut,
+        seeds = [b"program_stats"],
+        bump = program_stats.bump,
+    )]
+    pub program_stats: Account<'info, ProgramStats>,
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
-pub enum DealStatus {
-    Created,
-    Accepted,
-    Rejected,
-    PartialCompleted,
-    Completed,
-    Disputed,
-    Resolved,
-}
+    #[account(
+        mut,
+        seeds = [b"mint_stats", mint.key().as_ref()],
+        bump = mint_stats.bump,
+    )]
+    pub mint_stats: Account<'info, MintStats>,
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
-pub enum EligibilityStatus {
-    NotEligible,       // No tokens can be claimed yet
-    PartiallyEligible, // Can claim max_claimable_after_obligation (e.g., 20%)
-    FullyEligible,     // Can claim all remaining tokens (market cap or time conditions met)
-}
+    // Must be owned by the KOL's payout address of every deal, checked in the instruction
+    #[account(
+        mut,
+        constraint = kol_token_account.mint == mint.key()
+    )]
+    pub kol_token_account: Account<'info, TokenAccount>,
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
-pub enum DisputeReason {
-    None,
-    Unresolved,
-    Other,
-    // Add more variants as needed
-}
+    // Created on first use, holds the KOL's payout address and reputation
+    #[account(
+        init_if_needed,
+        payer = kol,
+        space = 8 + std::mem::size_of::<KolProfile>(),
+        seeds = [b"kol_profile", kol.key().as_ref()],
+        bump,
+    )]
+    pub kol_profile: Account<'info, KolProfile>,
 
-// Error codes
-#[error_code]
-pub enum ErrorCode {
-    #[msg("Invalid deal status")]
-    InvalidDealStatus,
-    #[msg("Unauthorized signer")]
-    UnauthorizedSigner,
-    #[msg("Exceeds vested amount")]
-    ExceedsVestedAmount,
-    #[msg("Invalid vesting type")]
-    InvalidVestingType,
-    #[msg("Invalid percentage value")]
-    InvalidPercentage,
-}
+    #[account(
+        init_if_needed,
+        payer = kol,
+        space = 8 + std::mem::size_of::<KolEarnings>(),
+        seeds = [b"kol_earnings", kol.key().as_ref(), mint.key().as_ref()],
+        bump,
+    )]
+    pub kol_earnings: Account<'info, KolEarnings>,
 
-// Structs
-#[account]
-pub struct Escrow {
-    pub admin: Pubkey,
-    pub escrow_bump: u8,
-    pub max_claimable_after_obligation: u8,
-}
+    /// CHECK: This is the PDA acting as the vault authority
+    #[account(
+        seeds = [b"vault_authority"],
+        bump
+    )]
+    pub vault_authority: AccountInfo<'info>,
 
-#[account]
-pub struct Deal {
-    pub order_id: [u8; 16],
-    pub project_owner: Pubkey,
-    pub kol: Pubkey,
-    pub mint: Pubkey,
-    pub amount: u64,
-    pub released_amount: u64,
-    pub vesting_type: VestingType,
-    pub vesting_duration: i64,
-    pub start_time: i64,
-    pub accept_time: i64,
-    pub done_obligation_time: i64,
-    pub status: DealStatus,
-    pub dispute_reason: DisputeReason,
-    pub deal_bump: u8,
-    pub eligibility_status: EligibilityStatus,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
 }
 
-// ACCOUNTS
 #[derive(Accounts)]
-pub struct Initialize<'info> {
+pub struct SetCrankFee<'info> {
+    pub escrow: Account<'info, Escrow>,
+
     #[account(
-        init,
+        mut,
+        constraint = admin.key() == escrow.admin @ ErrorCode::UnauthorizedSigner
+    )]
+    pub admin: Signer<'info>,
+
+    pub mint: Account<'info, Mint>,
+
+    #[account(
+        init_if_needed,
         payer = admin,
-        space = 8 + std::mem::size_of::<Escrow>(),
-        seeds = [b"escrow"],
+        space = 8 + std::mem::size_of::<CrankConfig>(),
+        seeds = [b"crank_config", mint.key().as_ref()],
         bump,
     )]
-    pub escrow: Account<'info, Escrow>,
-    #[account(mut)]
-    pub admin: Signer<'info>,
+    pub crank_config: Account<'info, CrankConfig>,
+
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-#[instruction(
-    amount: u64,
-    vesting_type: VestingType,
-    vesting_duration: i64,
-    order_id: [u8; 16],
-)]
-pub struct CreateDeal<'info> {
+pub struct Clawback<'info> {
     #[account(mut)]
-    pub escrow: Account<'info, Escrow>,
+    pub deal: Account<'info, Deal>,
 
     #[account(
-        init,
-        payer = project_owner,
-        space = 8 + std::mem::size_of::<Deal>(),
-        seeds = [
-            b"deal",
-            order_id.as_ref(), 
-            project_owner.key().as_ref(),
-            kol.key().as_ref(),
-            mint.key().as_ref(),
-        ],
-        bump,
+        mut,
+        constraint = admin.key() == escrow.admin @ ErrorCode::UnauthorizedSigner
     )]
-    pub deal: Account<'info, Deal>,
+    pub admin: Signer<'info>,
 
-    #[account(mut)]
-    pub project_owner: Signer<'info>,
-    /// CHECK: This is safe; we only read the public key
-    pub kol: AccountInfo<'info>,
-    pub mint: Account<'info, Mint>,
+    pub escrow: Account<'info, Escrow>,
 
     #[account(
         mut,
-        constraint = project_owner_token_account.owner == project_owner.key(),
-        constraint = project_owner_token_account.mint == mint.key()
+        seeds = [b"vault_token_account", deal.mint.as_ref()],
+        bump,
     )]
-    pub project_owner_token_account: Account<'info, TokenAccount>,
+    pub vault_token_account: Account<'info, TokenAccount>,
 
     #[account(
-       init_if_needed,
-    payer = project_owner,
-    seeds = [b"vault_token_account", mint.key().as_ref()],
-    bump,
-    token::mint = mint,
-    token::authority = vault_authority,
+        mut,
+        seeds = [b"mint_ledger", deal.mint.as_ref()],
+        bump = mint_ledger.bump,
     )]
-    pub vault_token_account: Account<'info, TokenAccount>,
+    pub mint_ledger: Account<'info, MintLedger>,
 
     /// CHECK: This is the PDA acting as the vault authority
     #[account(
@@ -481,56 +2915,196 @@ pub struct CreateDeal<'info> {
         bump
     )]
     pub vault_authority: AccountInfo<'info>,
+
+    // Must be owned by the receipt holder or the KOL's payout address, checked in the instruction
+    #[account(
+        mut,
+        constraint = kol_token_account.mint == deal.mint
+    )]
+    pub kol_token_account: Account<'info, TokenAccount>,
+
+    // Created on first use, holds the KOL's payout address and reputation
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + std::mem::size_of::<KolProfile>(),
+        seeds = [b"kol_profile", deal.kol.as_ref()],
+        bump,
+    )]
+    pub kol_profile: Account<'info, KolProfile>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + std::mem::size_of::<KolEarnings>(),
+        seeds = [b"kol_earnings", deal.kol.as_ref(), deal.mint.as_ref()],
+        bump,
+    )]
+    pub kol_earnings: Account<'info, KolEarnings>,
+
+    #[account(
+        constraint = receipt_token_account.mint == deal.receipt_mint,
+        constraint = receipt_token_account.amount == 1,
+    )]
+    pub receipt_token_account: Option<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = project_owner_token_account.owner == deal.project_owner,
+        constraint = project_owner_token_account.mint == deal.mint
+    )]
+    pub project_owner_token_account: Account<'info, TokenAccount>,
+
+    // Only required when the deal has a referrer and vested tokens are paid out
+    #[account(
+        mut,
+        constraint = referrer_token_account.owner == deal.referrer,
+        constraint = referrer_token_account.mint == deal.mint
+    )]
+    pub referrer_token_account: Option<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"referrer_stats", deal.referrer.as_ref(), deal.mint.as_ref()],
+        bump = referrer_stats.bump,
+    )]
+    pub referrer_stats: Option<Account<'info, ReferrerStats>>,
+
     pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
-    pub rent: Sysvar<'info, Rent>,
 }
 
 #[derive(Accounts)]
-pub struct AcceptDeal<'info> {
+pub struct OpenDispute<'info> {
     #[account(mut)]
     pub deal: Account<'info, Deal>,
 
-    // Allow either the KOL or the admin to sign
     #[account(
-        signer,
-        constraint = signer.key() == deal.kol || signer.key() == escrow.admin,
+        mut,
+        constraint = signer.key() == deal.project_owner || signer.key() == deal.kol @ ErrorCode::UnauthorizedSigner,
     )]
-    /// CHECK: This is either the KOL or the admin
-    pub signer: AccountInfo<'info>,
+    pub signer: Signer<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = 8 + std::mem::size_of::<ProjectOwnerProfile>(),
+        seeds = [b"project_owner_profile", deal.project_owner.as_ref()],
+        bump,
+    )]
+    pub project_owner_profile: Account<'info, ProjectOwnerProfile>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SettleDispute<'info> {
+    #[account(mut)]
+    pub deal: Account<'info, Deal>,
+
+    #[account(
+        mut,
+        constraint = admin.key() == escrow.admin @ ErrorCode::UnauthorizedSigner
+    )]
+    pub admin: Signer<'info>,
 
-    // Fetch the escrow account to get the admin's public key
     pub escrow: Account<'info, Escrow>,
+
+    // Created on first use, holds the KOL's payout address and reputation
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + std::mem::size_of::<KolProfile>(),
+        seeds = [b"kol_profile", deal.kol.as_ref()],
+        bump,
+    )]
+    pub kol_profile: Account<'info, KolProfile>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + std::mem::size_of::<ProjectOwnerProfile>(),
+        seeds = [b"project_owner_profile", deal.project_owner.as_ref()],
+        bump,
+    )]
+    pub project_owner_profile: Account<'info, ProjectOwnerProfile>,
+
+    #[account(
+        mut,
+        seeds = [b"vault_token_account", deal.mint.as_ref()],
+        bump,
+    )]
+    pub vault_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"mint_ledger", deal.mint.as_ref()],
+        bump = mint_ledger.bump,
+    )]
+    pub mint_ledger: Account<'info, MintLedger>,
+
+    /// CHECK: This is the PDA acting as the vault authority
+    #[account(
+        seeds = [b"vault_authority"],
+        bump
+    )]
+    pub vault_authority: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        constraint = project_owner_token_account.owner == deal.project_owner,
+        constraint = project_owner_token_account.mint == deal.mint
+    )]
+    pub project_owner_token_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct RejectDeal<'info> {
+pub struct ReleaseKolStake<'info> {
     #[account(mut)]
     pub deal: Account<'info, Deal>,
 
-    // Allow either the KOL or the admin to sign
+    // Anyone can release, paying for the recipient's associated token account if needed
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    #[account(address = deal.mint)]
+    pub mint: Account<'info, Mint>,
+
     #[account(
-        signer,
-        constraint = signer.key() == deal.kol || signer.key() == escrow.admin,
+        seeds = [b"kol_profile", deal.kol.as_ref()],
+        bump = kol_profile.bump,
     )]
-    /// CHECK: This is either the KOL or the admin
-    pub signer: AccountInfo<'info>,
+    pub kol_profile: Account<'info, KolProfile>,
 
-    // Fetch the escrow account to get the admin's public key
-    pub escrow: Account<'info, Escrow>,
+    /// CHECK: Checked against the KOL's payout address
+    #[account(address = kol_payout_owner(&deal, &kol_profile) @ ErrorCode::InvalidPayoutAccount)]
+    pub recipient: AccountInfo<'info>,
 
-    #[account(mut)]
-    pub project_owner: AccountInfo<'info>,
+    #[account(
+        init_if_needed,
+        payer = signer,
+        associated_token::mint = mint,
+        associated_token::authority = recipient,
+    )]
+    pub recipient_token_account: Account<'info, TokenAccount>,
 
     #[account(
         mut,
         seeds = [b"vault_token_account", deal.mint.as_ref()],
         bump,
-        token::mint = mint,
-        token::authority = vault_authority,
     )]
     pub vault_token_account: Account<'info, TokenAccount>,
 
+    #[account(
+        mut,
+        seeds = [b"mint_ledger", deal.mint.as_ref()],
+        bump = mint_ledger.bump,
+    )]
+    pub mint_ledger: Account<'info, MintLedger>,
+
     /// CHECK: This is the PDA acting as the vault authority
     #[account(
         seeds = [b"vault_authority"],
@@ -538,51 +3112,74 @@ pub struct RejectDeal<'info> {
     )]
     pub vault_authority: AccountInfo<'info>,
 
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetPayoutAddress<'info> {
     #[account(
-        mut,
-        associated_token::mint = mint,
-        associated_token::authority = project_owner,
+        init_if_needed,
+        payer = kol,
+        space = 8 + std::mem::size_of::<KolProfile>(),
+        seeds = [b"kol_profile", kol.key().as_ref()],
+        bump,
     )]
-    pub project_owner_token_account: Account<'info, TokenAccount>,
+    pub kol_profile: Account<'info, KolProfile>,
 
-    pub mint: Account<'info, Mint>,
+    // Only set when overriding the payout address of a single deal
+    #[account(
+        mut,
+        constraint = deal.kol == kol.key() @ ErrorCode::UnauthorizedSigner,
+    )]
+    pub deal: Option<Account<'info, Deal>>,
 
-    pub token_program: Program<'info, Token>,
+    #[account(mut)]
+    pub kol: Signer<'info>,
 
     pub system_program: Program<'info, System>,
-
-    pub rent: Sysvar<'info, Rent>,
 }
 
 #[derive(Accounts)]
-pub struct ResolveDeal<'info> {
-    #[account(mut)]
+pub struct SubmitReview<'info> {
     pub deal: Account<'info, Deal>,
 
-    // Allow either the KOL or the admin to sign
     #[account(
-        signer,
-        constraint = signer.key() == deal.kol || signer.key() == escrow.admin,
+        mut,
+        constraint = reviewer.key() == deal.project_owner || reviewer.key() == deal.kol @ ErrorCode::UnauthorizedSigner,
     )]
-    /// CHECK: This is either the KOL or the admin
-    pub signer: AccountInfo<'info>,
+    pub reviewer: Signer<'info>,
 
-    // Fetch the escrow account to get the admin's public key
-    pub escrow: Account<'info, Escrow>,
-
-    #[account(mut)]
-    pub vault_token_account: Account<'info, TokenAccount>,
+    // `init` rather than `init_if_needed`, so each party reviews a deal only once
+    #[account(
+        init,
+        payer = reviewer,
+        space = 8 + std::mem::size_of::<Review>(),
+        seeds = [b"review", deal.key().as_ref(), reviewer.key().as_ref()],
+        bump,
+    )]
+    pub review: Account<'info, Review>,
 
-    #[account(mut)]
-    pub kol_token_account: Account<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer = reviewer,
+        space = 8 + std::mem::size_of::<KolProfile>(),
+        seeds = [b"kol_profile", deal.kol.as_ref()],
+        bump,
+    )]
+    pub kol_profile: Account<'info, KolProfile>,
 
     #[account(
-        seeds = [b"vault_authority"],
-        bump
+        init_if_needed,
+        payer = reviewer,
+        space = 8 + std::mem::size_of::<ProjectOwnerProfile>(),
+        seeds = [b"project_owner_profile", deal.project_owner.as_ref()],
+        bump,
     )]
-    pub vault_authority: AccountInfo<'info>,
+    pub project_owner_profile: Account<'info, ProjectOwnerProfile>,
 
-    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
@@ -599,6 +3196,16 @@ pub struct SetEligibilityStatus<'info> {
     pub escrow: Account<'info, Escrow>,
 }
 
+#[derive(Accounts)]
+pub struct BatchSetEligibilityStatus<'info> {
+    #[account(
+        constraint = signer.key() == escrow.admin @ ErrorCode::UnauthorizedSigner
+    )]
+    pub signer: Signer<'info>,
+
+    pub escrow: Account<'info, Escrow>,
+}
+
 #[derive(Accounts)]
 pub struct UpdateMaxClaimablePercentage<'info> {
     #[account(mut)]
@@ -610,162 +3217,725 @@ pub struct UpdateMaxClaimablePercentage<'info> {
     pub admin: Signer<'info>,
 }
 
+#[derive(Accounts)]
+pub struct SimulateVesting<'info> {
+    // Leave out to simulate the terms passed as arguments instead
+    pub deal: Option<Account<'info, Deal>>,
+    pub escrow: Account<'info, Escrow>,
+}
+
 #[derive(Accounts)]
 pub struct CheckClaimableAmount<'info> {
     pub deal: Account<'info, Deal>,
     pub escrow: Account<'info, Escrow>,
 }
 
+#[derive(Accounts)]
+pub struct InitMintLedger<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub mint: Account<'info, Mint>,
+
+    #[account(
+        seeds = [b"vault_token_account", mint.key().as_ref()],
+        bump,
+    )]
+    pub vault_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + std::mem::size_of::<MintLedger>(),
+        seeds = [b"mint_ledger", mint.key().as_ref()],
+        bump,
+    )]
+    pub mint_ledger: Account<'info, MintLedger>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateAccount<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: A deal or profile of this program, identified by its discriminator
+    #[account(mut, owner = crate::ID)]
+    pub account: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct VerifySolvency<'info> {
+    pub mint: Account<'info, Mint>,
+
+    #[account(
+        seeds = [b"mint_ledger", mint.key().as_ref()],
+        bump = mint_ledger.bump,
+    )]
+    pub mint_ledger: Account<'info, MintLedger>,
+
+    #[account(
+        seeds = [b"vault_token_account", mint.key().as_ref()],
+        bump,
+    )]
+    pub vault_token_account: Account<'info, TokenAccount>,
+}
+
+// Helper function to resolve who should receive the KOL's tokens: deal override, then profile, then the KOL
+fn kol_payout_owner(deal: &Deal, kol_profile: &KolProfile) -> Pubkey {
+    if deal.payout_owner != Pubkey::default() {
+        deal.payout_owner
+    } else if kol_profile.payout_owner != Pubkey::default() {
+        kol_profile.payout_owner
+    } else {
+        deal.kol
+    }
+}
+
+// Helper function to average ratings out of 5, scaled by 100
+fn average_rating(rating_total: u64, rating_count: u64) -> u64 {
+    if rating_count == 0 {
+        return 0;
+    }
+    rating_total * 100 / rating_count
+}
+
+// Helper function to fill in a KOL profile created by the current instruction
+fn init_kol_profile(kol_profile: &mut KolProfile, kol: Pubkey, bump: u8) {
+    if kol_profile.kol == Pubkey::default() {
+        kol_profile.kol = kol;
+        kol_profile.bump = bump;
+    }
+}
+
+// Helper function to fill in a project owner profile created by the current instruction
+fn init_project_owner_profile(profile: &mut ProjectOwnerProfile, project_owner: Pubkey, bump: u8) {
+    if profile.project_owner == Pubkey::default() {
+        profile.project_owner = project_owner;
+        profile.bump = bump;
+    }
+}
+
+// Helper function to publish a project owner's track record after one of its counters changed
+fn emit_project_owner_profile(profile: &ProjectOwnerProfile) {
+    emit!(mutual_escrow::ProjectOwnerProfileUpdated {
+        project_owner: profile.project_owner,
+        deals_funded: profile.deals_funded,
+        deals_rejected: profile.deals_rejected,
+        deals_completed: profile.deals_completed,
+        disputes_opened: profile.disputes_opened,
+        disputes_lost: profile.disputes_lost,
+    });
+}
+
+// Helper function to publish what a project owner funded in a mint after it changed
+fn emit_project_owner_volume(volume: &ProjectOwnerVolume) {
+    emit!(mutual_escrow::ProjectOwnerVolumeUpdated {
+        project_owner: volume.project_owner,
+        mint: volume.mint,
+        deals_funded: volume.deals_funded,
+        total_funded: volume.total_funded,
+        average_deal_size: volume.average_deal_size(),
+    });
+}
+
+// Helper function to resolve who receives the KOL's part of a claim: whoever holds the receipt,
+// falling back to the KOL's payout address while the KOL still holds it
+fn claim_recipient(
+    deal: &Deal,
+    receipt_token_account: Option<&Account<TokenAccount>>,
+    kol_profile: &KolProfile,
+) -> Result<Pubkey> {
+    // Deals accepted before receipts existed have no receipt mint
+    if deal.receipt_mint != Pubkey::default() {
+        let receipt = receipt_token_account.ok_or(ErrorCode::MissingReceiptAccount)?;
+        if receipt.owner != deal.kol {
+            return Ok(receipt.owner);
+        }
+    }
+
+    Ok(kol_payout_owner(deal, kol_profile))
+}
+
+// Helper function to move a deal to a new eligibility status
+fn apply_eligibility_status(deal: &mut Account<Deal>, new_status: EligibilityStatus) -> Result<()> {
+    // Only accepted deals can become eligible
+    transition(Some(&deal.status), DealAction::SetEligibility)?;
+
+    // Eligibility only ever moves forward here, regressions go through `revoke_eligibility`
+    require!(
+        new_status > deal.eligibility_status,
+        ErrorCode::InvalidEligibilityTransition
+    );
+
+    let current_time = Clock::get()?.unix_timestamp;
+
+    // Set `done_obligation_time` if transitioning to `PartiallyEligible` or `FullyEligible` for the first time
+    if deal.done_obligation_time == 0 {
+        deal.done_obligation_time = current_time;
+        msg!("done_obligation_time set to: {}", deal.done_obligation_time);
+    }
+
+    // Record when each phase was reached
+    match new_status {
+        EligibilityStatus::PartiallyEligible => deal.partially_eligible_time = current_time,
+        EligibilityStatus::FullyEligible => deal.fully_eligible_time = current_time,
+        EligibilityStatus::NotEligible => {}
+    }
+
+    // Update the eligibility status
+    deal.eligibility_status = new_status.clone();
+
+    // Emit event for the status update
+    emit!(mutual_escrow::EligibilityStatusUpdated {
+        order_id: deal.order_id,
+        deal: deal.key(),
+        kol: deal.kol,
+        new_status,
+    });
+
+    Ok(())
+}
+
+// Accounts needed to pay a claim out of the vault
+struct ClaimAccounts<'a, 'info> {
+    token_program: &'a Program<'info, Token>,
+    vault_token_account: &'a Account<'info, TokenAccount>,
+    vault_authority: &'a AccountInfo<'info>,
+    vault_authority_bump: u8,
+    recipient_token_account: AccountInfo<'info>,
+    referrer_token_account: Option<&'a Account<'info, TokenAccount>>,
+    referrer_stats: Option<&'a mut Account<'info, ReferrerStats>>,
+    payee_token_accounts: &'info [AccountInfo<'info>], // In the order the payees were registered
+}
+
+// How a claim was split between the referrer, the payees and the recipient
+struct ClaimDistribution {
+    kol_amount: u64,
+    payee_payouts: Vec<PayeePayout>,
+    referrer_amount: u64,
+}
+
+// Helper function to split `amount` between the referrer, the payees and the recipient
+fn distribute_claim(
+    deal: &Deal,
+    amount: u64,
+    accounts: ClaimAccounts<'_, '_>,
+) -> Result<ClaimDistribution> {
+    // The referrer's cut comes off the top of every claim
+    let referrer_amount = bps_share(amount, deal.referrer_bps);
+    if deal.referrer_bps > 0 {
+        let referrer_token_account = accounts
+            .referrer_token_account
+            .ok_or(ErrorCode::MissingReferrerAccounts)?;
+        let referrer_stats = accounts
+            .referrer_stats
+            .ok_or(ErrorCode::MissingReferrerAccounts)?;
+
+        transfer_from_vault(
+            accounts.token_program,
+            accounts.vault_token_account,
+            &referrer_token_account.to_account_info(),
+            accounts.vault_authority,
+            accounts.vault_authority_bump,
+            referrer_amount,
+        )?;
+
+        referrer_stats.total_earned = referrer_stats
+            .total_earned
+            .checked_add(referrer_amount)
+            .unwrap();
+        msg!("Referrer paid: {}", referrer_amount);
+    }
+    let kol_share = amount.checked_sub(referrer_amount).unwrap();
+
+    let payee_count = deal.payee_count as usize;
+    require!(
+        accounts.payee_token_accounts.len() == payee_count,
+        ErrorCode::InvalidPayees
+    );
+
+    // Pay each registered payee its share of the KOL's part of the claim
+    let mut payee_payouts = Vec::with_capacity(payee_count);
+    let mut kol_amount = kol_share;
+    for (payee, payee_token_info) in deal.payees[..payee_count]
+        .iter()
+        .zip(accounts.payee_token_accounts.iter())
+    {
+        let payee_token_account = Account::<TokenAccount>::try_from(payee_token_info)?;
+        require!(
+            payee_token_account.owner == payee.wallet && payee_token_account.mint == deal.mint,
+            ErrorCode::InvalidPayees
+        );
+
+        let payee_amount = bps_share(kol_share, payee.share_bps);
+        kol_amount = kol_amount.checked_sub(payee_amount).unwrap();
+
+        transfer_from_vault(
+            accounts.token_program,
+            accounts.vault_token_account,
+            payee_token_info,
+            accounts.vault_authority,
+            accounts.vault_authority_bump,
+            payee_amount,
+        )?;
+
+        payee_payouts.push(PayeePayout {
+            wallet: payee.wallet,
+            amount: payee_amount,
+        });
+    }
+
+    // Transfer the rest to the recipient
+    transfer_from_vault(
+        accounts.token_program,
+        accounts.vault_token_account,
+        &accounts.recipient_token_account,
+        accounts.vault_authority,
+        accounts.vault_authority_bump,
+        kol_amount,
+    )?;
+
+    Ok(ClaimDistribution {
+        kol_amount,
+        payee_payouts,
+        referrer_amount,
+    })
+}
+
+// Helper function to add a paid claim to the program and mint statistics
+fn record_claim_stats(
+    program_stats: &mut ProgramStats,
+    mint_stats: &mut MintStats,
+    deal: &Deal,
+    claimable_amount: u64,
+) {
+    mint_stats.volume_released = mint_stats
+        .volume_released
+        .checked_add(claimable_amount)
+        .unwrap();
+
+    // Claims are rejected once a deal is completed, so this is the claim that completed it
+    if deal.status == DealStatus::Completed {
+        program_stats.deals_completed = program_stats.deals_completed.checked_add(1).unwrap();
+    }
+}
+
+// Helper function to keep the escrowed and funded volumes in line with a top-up or an amendment
+fn record_amount_change(
+    mint_stats: &mut MintStats,
+    project_owner_volume: &mut ProjectOwnerVolume,
+    old_amount: u64,
+    new_amount: u64,
+) {
+    let apply = |total: u64| {
+        if new_amount >= old_amount {
+            total.checked_add(new_amount - old_amount).unwrap()
+        } else {
+            total.saturating_sub(old_amount - new_amount)
+        }
+    };
+    mint_stats.volume_escrowed = apply(mint_stats.volume_escrowed);
+    project_owner_volume.total_funded = apply(project_owner_volume.total_funded);
+    emit_project_owner_volume(project_owner_volume);
+}
+
+// Helper function to compute the cranker's share of a claim
+fn crank_fee(claimable_amount: u64, fee_bps: u16) -> u64 {
+    bps_share(claimable_amount, fee_bps.min(MAX_CRANK_FEE_BPS))
+}
+
+// Helper function to add a rating to the profile of the reviewer's counterparty, returning
+// the reviewee
+fn record_review(
+    kol_profile: &mut KolProfile,
+    project_owner_profile: &mut ProjectOwnerProfile,
+    deal: &Deal,
+    reviewer: Pubkey,
+    rating: u8,
+) -> Pubkey {
+    if reviewer == deal.kol {
+        project_owner_profile.rating_count =
+            project_owner_profile.rating_count.checked_add(1).unwrap();
+        project_owner_profile.rating_total = project_owner_profile
+            .rating_total
+            .checked_add(rating as u64)
+            .unwrap();
+        deal.project_owner
+    } else {
+        kol_profile.rating_count = kol_profile.rating_count.checked_add(1).unwrap();
+        kol_profile.rating_total = kol_profile.rating_total.checked_add(rating as u64).unwrap();
+        deal.kol
+    }
+}
+
+// Helper function to count a deal the claim completed in its project owner's history
+fn record_project_owner_claim(profile: &mut ProjectOwnerProfile, deal: &Deal) {
+    if deal.status == DealStatus::Completed {
+        profile.deals_completed = profile.deals_completed.checked_add(1).unwrap();
+        emit_project_owner_profile(profile);
+    }
+}
+
+// Helper function to add a paid claim to the KOL's reputation and earnings, `kol_amount` being
+// the KOL's own share of it
+fn record_kol_claim(
+    kol_profile: &mut KolProfile,
+    kol_earnings: &mut KolEarnings,
+    kol_earnings_bump: u8,
+    deal: &Deal,
+    kol_amount: u64,
+) {
+    if kol_earnings.kol == Pubkey::default() {
+        kol_earnings.kol = deal.kol;
+        kol_earnings.mint = deal.mint;
+        kol_earnings.bump = kol_earnings_bump;
+    }
+    kol_earnings.total_earned = kol_earnings.total_earned.checked_add(kol_amount).unwrap();
+
+    if deal.status != DealStatus::Completed {
+        return;
+    }
+    kol_profile.deals_completed = kol_profile.deals_completed.checked_add(1).unwrap();
+
+    // Deals accepted or made eligible before these timestamps existed aren't timed
+    if deal.accept_time != 0 && deal.done_obligation_time >= deal.accept_time {
+        kol_profile.total_obligation_time = kol_profile
+            .total_obligation_time
+            .checked_add(deal.done_obligation_time - deal.accept_time)
+            .unwrap();
+        kol_profile.timed_obligations = kol_profile.timed_obligations.checked_add(1).unwrap();
+    }
+}
+
+// Helper function to add a payout to the released amounts, paying out any bonus first
+fn record_release(deal: &mut Deal, amount: u64) {
+    let bonus_released = std::cmp::min(
+        amount,
+        deal.bonus_amount.saturating_sub(deal.released_bonus_amount),
+    );
+    deal.released_bonus_amount = deal
+        .released_bonus_amount
+        .checked_add(bonus_released)
+        .unwrap();
+    deal.released_amount = deal.released_amount.checked_add(amount).unwrap();
+}
+
+// Helper function to book a paid claim on the deal and emit the resolve events
+fn record_claim(
+    deal: &mut Account<Deal>,
+    claimable_amount: u64,
+    crank_fee: u64,
+    recipient: Pubkey,
+    distribution: ClaimDistribution,
+) -> Result<()> {
+    // Update the released amount after the claim
+    record_release(deal, claimable_amount);
+
+    // Log the updated released amount for debugging
+    msg!("Released amount after claim: {}", deal.released_amount);
+
+    // Determine the new status based on the released amount
+    let new_status = transition(
+        Some(&deal.status),
+        DealAction::Resolve {
+            fully_released: deal.released_amount >= deal.amount,
+        },
+    )?;
+
+    // Check if the status has changed before updating and emitting the event
+    if deal.status != new_status {
+        deal.status = new_status;
+
+        // Emit the event only if the status has changed
+        emit!(mutual_escrow::DealStatusChanged {
+            order_id: deal.order_id,
+            deal: deal.key(),
+            project_owner: deal.project_owner,
+            kol: deal.kol,
+            status: deal.status.clone(),
+        });
+    } else {
+        msg!("Deal status unchanged. No event emitted.");
+    }
+
+    emit!(mutual_escrow::DealResolved {
+        order_id: deal.order_id,
+        deal: deal.key(),
+        project_owner: deal.project_owner,
+        kol: deal.kol,
+        released_amount: deal.released_amount,
+        claim_amount: claimable_amount,
+        status: deal.status.clone(),
+        kol_amount: distribution.kol_amount,
+        payee_payouts: distribution.payee_payouts,
+        referrer_amount: distribution.referrer_amount,
+        recipient,
+        crank_fee,
+    });
+
+    Ok(())
+}
+
+// Helper function to transfer tokens out of the vault, signed by the vault authority
+fn transfer_from_vault<'info>(
+    token_program: &Program<'info, Token>,
+    vault_token_account: &Account<'info, TokenAccount>,
+    to: &AccountInfo<'info>,
+    vault_authority: &AccountInfo<'info>,
+    vault_authority_bump: u8,
+    amount: u64,
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+
+    let seeds = &[b"vault_authority".as_ref(), &[vault_authority_bump]];
+    let signer = &[&seeds[..]];
+    let cpi_accounts = Transfer {
+        from: vault_token_account.to_account_info(),
+        to: to.clone(),
+        authority: vault_authority.clone(),
+    };
+
+    token::transfer(
+        CpiContext::new_with_signer(token_program.to_account_info(), cpi_accounts, signer),
+        amount,
+    )
+}
+
 // Helper function to calculate vested amount based on time elapsed or eligible
 fn calculate_vested_amount(
     deal: &Deal,
     current_time: i64,
     max_claimable_after_obligation: u8, // Updated parameter name
-) -> Result<u64> {
-    match deal.vesting_type {
-        // No vesting: Allow the KOL to claim the entire remaining amount only if FullyEligible
-        VestingType::None => {
-            match deal.eligibility_status {
-                EligibilityStatus::FullyEligible => {
-                    msg!("Vesting type is None, and KOL is FullyEligible. Claiming all remaining tokens.");
-                    Ok(deal.amount.checked_sub(deal.released_amount).unwrap())
-                }
-                _ => {
-                    msg!("Vesting type is None, but KOL is not FullyEligible. No tokens can be claimed.");
-                    Ok(0)
-                }
-            }
-        }
+) -> u64 {
+    mutual_vesting::claimable_amount(
+        &vesting_state(deal),
+        current_time,
+        max_claimable_after_obligation,
+    )
+}
 
-        VestingType::Time => {
-            // Use `done_obligation_time` instead of `accept_time`
-            let elapsed_time = current_time.checked_sub(deal.done_obligation_time).unwrap();
-            msg!("Elapsed time since done_obligation_time: {}", elapsed_time);
-            msg!("Vesting duration: {}", deal.vesting_duration);
+// Helper function to hand the deal over to the shared vesting math
+pub fn vesting_state(deal: &Deal) -> VestingState {
+    VestingState {
+        amount: deal.amount,
+        released_amount: deal.released_amount,
+        bonus_amount: deal.bonus_amount,
+        released_bonus_amount: deal.released_bonus_amount,
+        kind: match deal.vesting_type {
+            VestingType::Time => VestingKind::Time,
+            VestingType::Marketcap => VestingKind::Marketcap,
+            VestingType::None => VestingKind::None,
+        },
+        duration: deal.vesting_duration,
+        eligibility: match deal.eligibility_status {
+            EligibilityStatus::NotEligible => Eligibility::NotEligible,
+            EligibilityStatus::PartiallyEligible => Eligibility::PartiallyEligible,
+            EligibilityStatus::FullyEligible => Eligibility::FullyEligible,
+        },
+        start_time: vesting_start_time(deal),
+    }
+}
 
-            // Ensure that the claimable amount never exceeds the total deal amount
-            let total_amount = deal.amount;
+// Helper function to build the deal `simulate_vesting` projects for new terms
+fn simulated_deal(terms: &VestingTerms) -> Deal {
+    Deal {
+        order_id: [0; 16],
+        project_owner: Pubkey::default(),
+        kol: Pubkey::default(),
+        mint: Pubkey::default(),
+        amount: terms.amount,
+        released_amount: 0,
+        vesting_type: terms.vesting_type.clone(),
+        vesting_duration: terms.vesting_duration,
+        start_time: 0,
+        accept_time: 0,
+        done_obligation_time: 0,
+        status: DealStatus::Accepted,
+        dispute_reason: DisputeReason::None,
+        deal_bump: 0,
+        eligibility_status: EligibilityStatus::NotEligible,
+        pending_amendment: None,
+        bonus_amount: 0,
+        released_bonus_amount: 0,
+        payees: [PayeeShare::default(); MAX_PAYEES],
+        payee_count: 0,
+        referrer: Pubkey::default(),
+        referrer_bps: 0,
+        payout_owner: Pubkey::default(),
+        receipt_mint: Pubkey::default(),
+        partially_eligible_time: 0,
+        fully_eligible_time: 0,
+        revocation_count: 0,
+        last_revocation_reason: RevocationReason::None,
+        last_revocation_time: 0,
+        required_kol_stake: 0,
+        kol_stake: 0,
+    }
+}
 
-            // Calculate the max claimable portion based on `max_claimable_after_obligation`
-            let max_claimable =
-                (total_amount as u128 * max_claimable_after_obligation as u128) / 100;
-            let max_claimable = max_claimable as u64;
-            msg!(
-                "Max claimable based on max_claimable_after_obligation: {}",
-                max_claimable
-            );
+// Helper function to put a simulated deal in the eligibility phase of a scenario
+fn apply_eligibility_scenario(deal: &mut Deal, scenario: &EligibilityScenario) {
+    deal.eligibility_status = scenario.eligibility_status.clone();
+    if scenario.eligible_time == 0 {
+        return;
+    }
 
-            match deal.eligibility_status {
-                // Partially eligible scenario,
-                EligibilityStatus::PartiallyEligible => {
-                    msg!("Partially eligible claim.");
-
-                    // If the KOL has not claimed the max claimable yet, allow them to claim it
-                    if deal.released_amount < max_claimable {
-                        // Allow claiming the difference between max claimable and already released
-                        let claimable =
-                            max_claimable.checked_sub(deal.released_amount).unwrap_or(0);
-                        msg!("Claimable under partial eligibility: {}", claimable);
-
-                        // Ensure the claimable amount doesn't exceed the total deal amount
-                        return Ok(std::cmp::min(claimable, total_amount));
-                    } else {
-                        // Already claimed the max claimable, no more tokens can be claimed under partial eligibility
-                        msg!("Max claimable has already been claimed. No more tokens can be claimed in partial eligibility.");
-                        return Ok(0);
-                    }
-                }
+    match scenario.eligibility_status {
+        EligibilityStatus::PartiallyEligible => {
+            deal.partially_eligible_time = scenario.eligible_time
+        }
+        EligibilityStatus::FullyEligible => deal.fully_eligible_time = scenario.eligible_time,
+        EligibilityStatus::NotEligible => {}
+    }
+    if deal.done_obligation_time == 0 {
+        deal.done_obligation_time = scenario.eligible_time;
+    }
+}
 
-                // Fully eligible scenario
-                EligibilityStatus::FullyEligible => {
-                    msg!("Fully eligible claim.");
-
-                    // If the KOL has not claimed the max claimable during partial eligibility,
-                    // allow them to claim it here first.
-                    let mut claimable = if deal.released_amount < max_claimable {
-                        max_claimable.checked_sub(deal.released_amount).unwrap_or(0)
-                    } else {
-                        0
-                    };
-
-                    // Now calculate the additional claimable based on time passed
-                    // Remaining amount after max claimable (i.e., 100% - max_claimable_after_obligation%)
-                    let remaining_amount = total_amount.checked_sub(max_claimable).unwrap_or(0);
-
-                    // Calculate how much of the remaining amount is vested proportional to time
-                    let vested_remaining = if deal.vesting_duration == 0 {
-                        // If vesting_duration is 0, consider the entire remaining amount vested
-                        remaining_amount
-                    } else {
-                        // Otherwise, calculate the vested amount proportional to time
-                        let vested = (remaining_amount as u128 * elapsed_time as u128)
-                            / deal.vesting_duration as u128;
-                        vested as u64
-                    };
-
-                    msg!("Vested portion of remaining amount: {}", vested_remaining);
-
-                    // Ensure the vested remaining does not exceed the remaining amount
-                    let vested_remaining = std::cmp::min(vested_remaining, remaining_amount);
-
-                    // Allow claiming additional vested amount minus what has already been claimed
-                    let additional_claimable = vested_remaining
-                        .checked_sub(deal.released_amount.checked_sub(max_claimable).unwrap_or(0))
-                        .unwrap_or(0);
-
-                    msg!(
-                        "Additional claimable after time vesting: {}",
-                        additional_claimable
-                    );
-
-                    claimable += additional_claimable;
-                    msg!("Total claimable under full eligibility: {}", claimable);
-
-                    // Ensure the claimable amount does not exceed the total deal amount
-                    return Ok(std::cmp::min(
-                        claimable,
-                        total_amount.checked_sub(deal.released_amount).unwrap_or(0),
-                    ));
-                }
+// Time vesting starts once the deal is fully eligible. Deals that became
+// eligible before the phase timestamps existed fall back to `done_obligation_time`
+fn vesting_start_time(deal: &Deal) -> i64 {
+    if deal.fully_eligible_time != 0 {
+        deal.fully_eligible_time
+    } else {
+        deal.done_obligation_time
+    }
+}
 
-                _ => {
-                    // Not eligible to claim yet
-                    msg!("Not eligible to claim yet.");
-                    Ok(0)
-                }
-            }
-        }
-        VestingType::Marketcap => {
-            // For Marketcap, check eligibility status
-            match deal.eligibility_status {
-                EligibilityStatus::PartiallyEligible => {
-                    // Calculate the maximum claimable amount before full eligibility
-                    let max_claimable =
-                        (deal.amount as u128 * max_claimable_after_obligation as u128) / 100;
-                    let max_claimable = max_claimable as u64;
+// Helper function to break down the vesting schedule, using the same math as
+// `calculate_vested_amount` so the UI and the program never disagree
+fn calculate_claimable_breakdown(
+    deal: &Deal,
+    current_time: i64,
+    max_claimable_after_obligation: u8,
+) -> ClaimableBreakdown {
+    let breakdown = mutual_vesting::breakdown(
+        &vesting_state(deal),
+        current_time,
+        max_claimable_after_obligation,
+    );
+
+    ClaimableBreakdown {
+        claimable_now: breakdown.claimable_now,
+        total_vested: breakdown.total_vested,
+        released_amount: breakdown.released_amount,
+        locked_amount: breakdown.locked_amount,
+        next_unlock_time: breakdown.next_unlock_time,
+        next_unlock_amount: breakdown.next_unlock_amount,
+        fully_vested_time: breakdown.fully_vested_time,
+        eligibility_status: deal.eligibility_status.clone(),
+    }
+}
 
-                    // Return the lesser of the full amount or the maximum allowed
-                    let claimable = std::cmp::min(deal.amount, max_claimable);
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-                    // Subtract the already released amount
-                    Ok(claimable.checked_sub(deal.released_amount).unwrap())
-                }
-                EligibilityStatus::FullyEligible => {
-                    // In Full eligibility, the remaining amount should be:
-                    // Total deal amount minus the already claimed amount during partial eligibility
-                    let remaining_amount =
-                        deal.amount.checked_sub(deal.released_amount).unwrap_or(0);
-
-                    msg!(
-                        "Full eligibility claim: remaining amount after partial release: {}",
-                        remaining_amount
-                    );
-
-                    // Return the remaining amount
-                    Ok(remaining_amount)
-                }
-                _ => Ok(0), // Not eligible to claim yet
-            }
+    fn accepted_deal(amount: u64) -> Deal {
+        let mut deal = simulated_deal(&VestingTerms {
+            amount,
+            vesting_type: VestingType::Time,
+            vesting_duration: 100,
+        });
+        deal.project_owner = Pubkey::new_unique();
+        deal.kol = Pubkey::new_unique();
+        deal
+    }
+
+    #[test]
+    fn repeated_cranks_stay_under_the_fee_cap() {
+        // A keeper cranking every time a few tokens vest
+        let claimed: u64 = 1_000_000;
+        let mut fees = 0;
+        for claim in (0..claimed / 150).map(|_| 150) {
+            fees += crank_fee(claim, MAX_CRANK_FEE_BPS);
         }
+        assert!(fees <= bps_share(claimed, MAX_CRANK_FEE_BPS));
+
+        // A fee set above the cap, e.g. by an older config, is still capped
+        assert_eq!(crank_fee(10_000, u16::MAX), 100);
+        assert_eq!(crank_fee(99, MAX_CRANK_FEE_BPS), 0);
+    }
+
+    #[test]
+    fn ledger_rejects_paying_out_more_than_it_owes() {
+        let mut ledger = MintLedger {
+            mint: Pubkey::new_unique(),
+            total_deposited: 0,
+            total_released: 0,
+            total_refunded: 0,
+            outstanding_liability: 0,
+            bump: 0,
+        };
+        ledger.deposit(1_000);
+        ledger.release(600).unwrap();
+        ledger.refund(400).unwrap();
+
+        assert!(ledger.release(1).is_err());
+        assert!(ledger.refund(1).is_err());
+        assert_eq!(ledger.outstanding_liability, 0);
+    }
+
+    #[test]
+    fn releases_pay_out_the_bonus_first() {
+        let mut deal = accepted_deal(1_000);
+        deal.bonus_amount = 300;
+
+        record_release(&mut deal, 200);
+        assert_eq!(
+            (deal.released_amount, deal.released_bonus_amount),
+            (200, 200)
+        );
+
+        // E.g. a clawback paying what vested, across the rest of the bonus and the base
+        record_release(&mut deal, 500);
+        assert_eq!(
+            (deal.released_amount, deal.released_bonus_amount),
+            (700, 300)
+        );
+
+        record_release(&mut deal, 300);
+        assert_eq!(
+            (deal.released_amount, deal.released_bonus_amount),
+            (1_000, 300)
+        );
+    }
+
+    #[test]
+    fn reviews_rate_the_other_party() {
+        let deal = accepted_deal(1_000);
+        let mut kol_profile = KolProfile::default();
+        let mut project_owner_profile = ProjectOwnerProfile::default();
+
+        let reviewee = record_review(
+            &mut kol_profile,
+            &mut project_owner_profile,
+            &deal,
+            deal.kol,
+            2,
+        );
+        assert_eq!(reviewee, deal.project_owner);
+        assert_eq!(kol_profile.rating_count, 0);
+        assert_eq!(project_owner_profile.rating_count, 1);
+        assert_eq!(project_owner_profile.rating_total, 2);
+
+        let reviewee = record_review(
+            &mut kol_profile,
+            &mut project_owner_profile,
+            &deal,
+            deal.project_owner,
+            5,
+        );
+        assert_eq!(reviewee, deal.kol);
+        assert_eq!(kol_profile.rating_count, 1);
+        assert_eq!(kol_profile.rating_total, 5);
+        assert_eq!(project_owner_profile.rating_count, 1);
+        assert_eq!(project_owner_profile.rating_total, 2);
     }
 }
@@ -0,0 +1,306 @@
+use anchor_lang::prelude::*;
+
+use crate::{DealStatus, DisputeOutcome, ErrorCode};
+
+// Every instruction that reads or changes the status of a deal
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DealAction {
+    Create,
+    Accept,
+    Reject,
+    ProposeAmendment,
+    AcceptAmendment,
+    TopUp,
+    SetEligibility,
+    RevokeEligibility,
+    Resolve {
+        fully_released: bool,
+    },
+    Clawback,
+    OpenDispute,
+    SettleDispute {
+        outcome: DisputeOutcome,
+        any_released: bool,
+    },
+}
+
+// Deals in these statuses hold no more funds and accept no more instructions
+pub fn is_terminal(status: &DealStatus) -> bool {
+    matches!(
+        status,
+        DealStatus::Rejected
+            | DealStatus::Completed
+            | DealStatus::Resolved
+            | DealStatus::ClawedBack
+    )
+}
+
+// Status of the deal after `action`, `None` meaning the deal doesn't exist yet
+pub fn transition(current: Option<&DealStatus>, action: DealAction) -> Result<DealStatus> {
+    next_status(current, action).map_err(Into::into)
+}
+
+fn next_status(
+    current: Option<&DealStatus>,
+    action: DealAction,
+) -> std::result::Result<DealStatus, ErrorCode> {
+    let status = match current {
+        None if action == DealAction::Create => return Ok(DealStatus::Created),
+        None => return Err(ErrorCode::DealNotCreated),
+        Some(_) if action == DealAction::Create => return Err(ErrorCode::DealAlreadyExists),
+        Some(status) => status,
+    };
+
+    if is_terminal(status) {
+        return Err(ErrorCode::DealClosed);
+    }
+
+    match (status, action) {
+        // Pending deal: the KOL decides, both parties can negotiate
+        (DealStatus::Created, DealAction::Accept) => Ok(DealStatus::Accepted),
+        (DealStatus::Created, DealAction::Reject) => Ok(DealStatus::Rejected),
+        (DealStatus::Created, DealAction::ProposeAmendment | DealAction::AcceptAmendment) => {
+            Ok(DealStatus::Created)
+        }
+        (
+            _,
+            DealAction::Accept
+            | DealAction::Reject
+            | DealAction::ProposeAmendment
+            | DealAction::AcceptAmendment,
+        ) => Err(ErrorCode::DealNotPending),
+
+        // Disputed deal: frozen until the admin settles it or claws it back
+        (
+            DealStatus::Disputed,
+            DealAction::SettleDispute {
+                outcome,
+                any_released,
+            },
+        ) => Ok(match outcome {
+            DisputeOutcome::KolWins if any_released => DealStatus::PartialCompleted,
+            DisputeOutcome::KolWins => DealStatus::Accepted,
+            DisputeOutcome::ProjectOwnerWins => DealStatus::Resolved,
+        }),
+        (DealStatus::Disputed, DealAction::Clawback) => Ok(DealStatus::ClawedBack),
+        (DealStatus::Disputed, _) => Err(ErrorCode::DealDisputed),
+        (_, DealAction::SettleDispute { .. }) => Err(ErrorCode::DealNotDisputed),
+
+        // Everything below needs an accepted deal
+        (DealStatus::Created, _) => Err(ErrorCode::DealNotActive),
+
+        // Accepted or PartialCompleted: eligibility, claims, top-ups and breaches
+        (_, DealAction::TopUp | DealAction::SetEligibility | DealAction::RevokeEligibility) => {
+            Ok(status.clone())
+        }
+        (
+            _,
+            DealAction::Resolve {
+                fully_released: true,
+            },
+        ) => Ok(DealStatus::Completed),
+        (
+            _,
+            DealAction::Resolve {
+                fully_released: false,
+            },
+        ) => Ok(DealStatus::PartialCompleted),
+        (_, DealAction::Clawback) => Ok(DealStatus::ClawedBack),
+        (_, DealAction::OpenDispute) => Ok(DealStatus::Disputed),
+        (_, DealAction::Create) => Err(ErrorCode::DealAlreadyExists),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CREATED: Option<DealStatus> = Some(DealStatus::Created);
+    const ACCEPTED: Option<DealStatus> = Some(DealStatus::Accepted);
+    const PARTIAL: Option<DealStatus> = Some(DealStatus::PartialCompleted);
+    const DISPUTED: Option<DealStatus> = Some(DealStatus::Disputed);
+
+    // Columns of the transition table, in the order of every row below
+    fn actions() -> [DealAction; 15] {
+        [
+            DealAction::Create,
+            DealAction::Accept,
+            DealAction::Reject,
+            DealAction::ProposeAmendment,
+            DealAction::AcceptAmendment,
+            DealAction::TopUp,
+            DealAction::SetEligibility,
+            DealAction::RevokeEligibility,
+            DealAction::Resolve {
+                fully_released: false,
+            },
+            DealAction::Resolve {
+                fully_released: true,
+            },
+            DealAction::Clawback,
+            DealAction::OpenDispute,
+            DealAction::SettleDispute {
+                outcome: DisputeOutcome::KolWins,
+                any_released: false,
+            },
+            DealAction::SettleDispute {
+                outcome: DisputeOutcome::KolWins,
+                any_released: true,
+            },
+            DealAction::SettleDispute {
+                outcome: DisputeOutcome::ProjectOwnerWins,
+                any_released: true,
+            },
+        ]
+    }
+
+    fn ok(status: DealStatus) -> std::result::Result<DealStatus, u32> {
+        Ok(status)
+    }
+
+    fn err(code: ErrorCode) -> std::result::Result<DealStatus, u32> {
+        Err(code as u32)
+    }
+
+    fn assert_row(
+        current: Option<DealStatus>,
+        expected: [std::result::Result<DealStatus, u32>; 15],
+    ) {
+        for (action, expected) in actions().into_iter().zip(expected) {
+            let actual = next_status(current.as_ref(), action).map_err(|code| code as u32);
+            assert_eq!(actual, expected, "{:?} + {:?}", current, action);
+        }
+    }
+
+    #[test]
+    fn missing_deal_can_only_be_created() {
+        use ErrorCode::DealNotCreated as E;
+        assert_row(
+            None,
+            [
+                ok(DealStatus::Created),
+                err(E),
+                err(E),
+                err(E),
+                err(E),
+                err(E),
+                err(E),
+                err(E),
+                err(E),
+                err(E),
+                err(E),
+                err(E),
+                err(E),
+                err(E),
+                err(E),
+            ],
+        );
+    }
+
+    #[test]
+    fn created_deal_transitions() {
+        assert_row(
+            CREATED,
+            [
+                err(ErrorCode::DealAlreadyExists),
+                ok(DealStatus::Accepted),
+                ok(DealStatus::Rejected),
+                ok(DealStatus::Created),
+                ok(DealStatus::Created),
+                err(ErrorCode::DealNotActive),
+                err(ErrorCode::DealNotActive),
+                err(ErrorCode::DealNotActive),
+                err(ErrorCode::DealNotActive),
+                err(ErrorCode::DealNotActive),
+                err(ErrorCode::DealNotActive),
+                err(ErrorCode::DealNotActive),
+                err(ErrorCode::DealNotDisputed),
+                err(ErrorCode::DealNotDisputed),
+                err(ErrorCode::DealNotDisputed),
+            ],
+        );
+    }
+
+    #[test]
+    fn active_deal_transitions() {
+        for (current, unchanged) in [
+            (ACCEPTED, DealStatus::Accepted),
+            (PARTIAL, DealStatus::PartialCompleted),
+        ] {
+            assert_row(
+                current,
+                [
+                    err(ErrorCode::DealAlreadyExists),
+                    err(ErrorCode::DealNotPending),
+                    err(ErrorCode::DealNotPending),
+                    err(ErrorCode::DealNotPending),
+                    err(ErrorCode::DealNotPending),
+                    ok(unchanged.clone()),
+                    ok(unchanged.clone()),
+                    ok(unchanged),
+                    ok(DealStatus::PartialCompleted),
+                    ok(DealStatus::Completed),
+                    ok(DealStatus::ClawedBack),
+                    ok(DealStatus::Disputed),
+                    err(ErrorCode::DealNotDisputed),
+                    err(ErrorCode::DealNotDisputed),
+                    err(ErrorCode::DealNotDisputed),
+                ],
+            );
+        }
+    }
+
+    #[test]
+    fn disputed_deal_transitions() {
+        assert_row(
+            DISPUTED,
+            [
+                err(ErrorCode::DealAlreadyExists),
+                err(ErrorCode::DealNotPending),
+                err(ErrorCode::DealNotPending),
+                err(ErrorCode::DealNotPending),
+                err(ErrorCode::DealNotPending),
+                err(ErrorCode::DealDisputed),
+                err(ErrorCode::DealDisputed),
+                err(ErrorCode::DealDisputed),
+                err(ErrorCode::DealDisputed),
+                err(ErrorCode::DealDisputed),
+                ok(DealStatus::ClawedBack),
+                err(ErrorCode::DealDisputed),
+                ok(DealStatus::Accepted),
+                ok(DealStatus::PartialCompleted),
+                ok(DealStatus::Resolved),
+            ],
+        );
+    }
+
+    #[test]
+    fn terminal_deals_are_closed() {
+        for status in [
+            DealStatus::Rejected,
+            DealStatus::Completed,
+            DealStatus::Resolved,
+            DealStatus::ClawedBack,
+        ] {
+            assert!(is_terminal(&status));
+
+            let mut expected = [(); 15].map(|_| err(ErrorCode::DealAlreadyExists));
+            for cell in expected.iter_mut().skip(1) {
+                *cell = err(ErrorCode::DealClosed);
+            }
+            assert_row(Some(status), expected);
+        }
+    }
+
+    #[test]
+    fn non_terminal_statuses_are_open() {
+        for status in [
+            DealStatus::Created,
+            DealStatus::Accepted,
+            DealStatus::PartialCompleted,
+            DealStatus::Disputed,
+        ] {
+            assert!(!is_terminal(&status));
+        }
+    }
+}
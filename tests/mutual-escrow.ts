@@ -1,13 +1,9 @@
-import BN from "bn.js";
 import assert from "assert";
 import * as web3 from "@solana/web3.js";
 import * as anchor from "@coral-xyz/anchor";
-import * as anchor from "@project-serum/anchor";
-import { Program } from "@project-serum/anchor";
-import { MutualEscrow } from "../target/types/mutual_escrow";
+import { Program } from "@coral-xyz/anchor";
 import * as splToken from "@solana/spl-token";
 import {
-  Connection,
   PublicKey,
   Keypair,
   SystemProgram,
@@ -16,20 +12,15 @@ import {
 import type { MutualEscrow } from "../target/types/mutual_escrow";
 
 describe("mutual_escrow", () => {
-  // Configure the client to use the local cluster
-  anchor.setProvider(anchor.AnchorProvider.env());
-
-  const program = anchor.workspace.MutualEscrow as anchor.Program<MutualEscrow>;
-  
   // Set up the provider and program
-  const provider = anchor.Provider.env();
+  const provider = anchor.AnchorProvider.env();
   anchor.setProvider(provider);
   const program = anchor.workspace.MutualEscrow as Program<MutualEscrow>;
   const connection = provider.connection;
-  const wallet = provider.wallet;
+  const wallet = provider.wallet as anchor.Wallet;
 
   // Keypairs
-  const adminKp = program.provider.wallet.payer;
+  const adminKp = wallet.payer;
   let projectOwnerKp: Keypair;
   let kolKp: Keypair;
 
@@ -39,12 +30,17 @@ describe("mutual_escrow", () => {
   const decimals = 6;
   let mintKeypair: Keypair;
   let projectOwnerTokenAccount: splToken.Account;
+  let kolTokenAccount: splToken.Account;
 
   // PDAs
   let escrowPda: PublicKey;
   let dealPda: PublicKey;
   let vaultTokenAccountPda: PublicKey;
   let vaultAuthorityPda: PublicKey;
+  let mintLedgerPda: PublicKey;
+  let programStatsPda: PublicKey;
+  let mintStatsPda: PublicKey;
+  let crankConfigPda: PublicKey;
 
   // Other variables
   const orderId = "abcd1234abcd1234abcd1234";
@@ -110,9 +106,8 @@ describe("mutual_escrow", () => {
     projectOwnerKp = Keypair.generate();
     kolKp = Keypair.generate();
 
-    // Give a bit of SOL to each wallet:
-    // Define the smaller SOL amount (0.05 SOL)
-    const smallAmount = 0.015 * LAMPORTS_PER_SOL;
+    // Give some SOL to each wallet, enough for the deal, profile and receipt accounts they pay for
+    const smallAmount = 0.5 * LAMPORTS_PER_SOL;
 
     // Prepare the transaction to send SOL from 'wallet' to projectOwnerKp and kolKp
     const transaction = new web3.Transaction();
@@ -129,7 +124,7 @@ describe("mutual_escrow", () => {
       SystemProgram.transfer({
         fromPubkey: wallet.publicKey, // wallet that will send the SOL
         toPubkey: projectOwnerKp.publicKey,
-        lamports: smallAmount, // 0.05 SOL
+        lamports: smallAmount, // 0.5 SOL
       })
     );
 
@@ -138,7 +133,7 @@ describe("mutual_escrow", () => {
       SystemProgram.transfer({
         fromPubkey: wallet.publicKey,
         toPubkey: kolKp.publicKey,
-        lamports: smallAmount, // 0.05 SOL
+        lamports: smallAmount, // 0.5 SOL
       })
     );
 
@@ -193,6 +188,14 @@ describe("mutual_escrow", () => {
       `Project Owner's Token Account: ${projectOwnerTokenAccount.address.toBase58()}`
     );
 
+    // Create Associated Token Account for the KOL, where claims are paid
+    kolTokenAccount = await splToken.getOrCreateAssociatedTokenAccount(
+      connection,
+      adminKp,
+      mint,
+      kolKp.publicKey
+    );
+
     // Mint tokens to the project owner's token account
     // const mintAmount = BigInt(10000 * 10 ** decimals);
     // await splToken.mintTo(
@@ -233,37 +236,24 @@ describe("mutual_escrow", () => {
     console.log("orderIdBuffer", orderIdBuffer);
 
     // Prepare PDAs
-    [escrowPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from("escrow")],
-      program.programId
-    );
-
-    [dealPda] = PublicKey.findProgramAddressSync(
-      [
-        Buffer.from("deal"),
-        orderIdBuffer,
-        projectOwnerKp.publicKey.toBuffer(),
-        kolKp.publicKey.toBuffer(),
-        mint.toBuffer(),
-      ],
-      program.programId
-    );
-
-    [vaultTokenAccountPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from("vault_token_account"), mint.toBuffer()],
-      program.programId
-    );
-
-    [vaultAuthorityPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from("vault_authority")],
-      program.programId
-    );
+    escrowPda = pda([Buffer.from("escrow")]);
+    dealPda = dealPdaFor(orderIdBuffer);
+    vaultTokenAccountPda = pda([
+      Buffer.from("vault_token_account"),
+      mint.toBuffer(),
+    ]);
+    vaultAuthorityPda = pda([Buffer.from("vault_authority")]);
+    mintLedgerPda = pda([Buffer.from("mint_ledger"), mint.toBuffer()]);
+    programStatsPda = pda([Buffer.from("program_stats")]);
+    mintStatsPda = pda([Buffer.from("mint_stats"), mint.toBuffer()]);
+    crankConfigPda = pda([Buffer.from("crank_config"), mint.toBuffer()]);
 
     console.log("Prepared PDAs", {
       escrowPda: escrowPda.toBase58(),
       dealPda: dealPda.toBase58(),
       vaultTokenAccountPda: vaultTokenAccountPda.toBase58(),
       vaultAuthorityPda: vaultAuthorityPda.toBase58(),
+      mintLedgerPda: mintLedgerPda.toBase58(),
     });
 
     // Initialize the Escrow if not already initialized
@@ -293,21 +283,12 @@ describe("mutual_escrow", () => {
         amount,
         { time: {} },
         vestingDuration,
-        Array.from(orderIdBuffer)
+        Array.from(orderIdBuffer),
+        null, // No referrer
+        0,
+        null // No stake required from the KOL
       )
-      .accounts({
-        escrow: escrowPda,
-        deal: dealPda,
-        projectOwner: projectOwnerKp.publicKey,
-        kol: kolKp.publicKey,
-        mint: mint,
-        projectOwnerTokenAccount: projectOwnerTokenAccount.address,
-        vaultTokenAccount: vaultTokenAccountPda,
-        vaultAuthority: vaultAuthorityPda,
-        tokenProgram: splToken.TOKEN_PROGRAM_ID,
-        systemProgram: SystemProgram.programId,
-        rent: anchor.web3.SYSVAR_RENT_PUBKEY,
-      })
+      .accounts(createDealAccounts(dealPda))
       .signers([projectOwnerKp])
       .rpc({ commitment: "confirmed" });
 
@@ -375,7 +356,13 @@ describe("mutual_escrow", () => {
       dealData.vestingDuration.toNumber(),
       vestingDuration.toNumber()
     );
-    // Add more assertions as needed
+
+    // The vault's ledger owes exactly what was deposited
+    const mintLedger = await program.account.mintLedger.fetch(mintLedgerPda);
+    assert.equal(
+      mintLedger.outstandingLiability.toNumber(),
+      Number(vaultTokenAccountInfo.amount)
+    );
   });
 
   // TODO: Try to reject the deal, if it's rejected, the fund will be transferred back to the project owner
@@ -387,46 +374,14 @@ describe("mutual_escrow", () => {
       const amount = new anchor.BN(1000 * 10 ** decimals); // 1000 tokens
       const vestingDuration = new anchor.BN(60); // 1 minute
 
-      orderIdBuffer = prepareOrderId("reject-order-id");
-
-      [dealPda] = PublicKey.findProgramAddressSync(
-        [
-          Buffer.from("deal"),
-          orderIdBuffer,
-          projectOwnerKp.publicKey.toBuffer(),
-          kolKp.publicKey.toBuffer(),
-          mint.toBuffer(),
-        ],
-        program.programId
+      dealPda = await createDeal(
+        "reject-order-id",
+        amount,
+        { time: {} }, // VestingType::Time
+        vestingDuration
       );
 
-      let txHash = await program.methods
-        .createDeal(
-          amount,
-          { time: {} }, // VestingType::Time
-          vestingDuration,
-          Array.from(orderIdBuffer)
-        )
-        .accounts({
-          escrow: escrowPda,
-          deal: dealPda,
-          projectOwner: projectOwnerKp.publicKey,
-          kol: kolKp.publicKey,
-          mint: mint,
-          projectOwnerTokenAccount: projectOwnerTokenAccount.address,
-          vaultTokenAccount: vaultTokenAccountPda,
-          vaultAuthority: vaultAuthorityPda,
-          tokenProgram: splToken.TOKEN_PROGRAM_ID,
-          systemProgram: SystemProgram.programId,
-          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
-        })
-        .signers([projectOwnerKp])
-        .rpc({
-          commitment: "confirmed",
-          skipPreflight: true,
-        });
-
-      console.log(`Deal created with txHash: ${txHash}`);
+      console.log(`Deal created at ${dealPda.toBase58()}`);
 
       // Check initial balances
       let projectOwnerTokenAccountInfo = await splToken.getAccount(
@@ -448,14 +403,18 @@ describe("mutual_escrow", () => {
       );
 
       // Now, reject the deal
-      txHash = await program.methods
+      const txHash = await program.methods
         .rejectDeal()
         .accounts({
           deal: dealPda,
-          escrow: escrowPda,
           signer: adminKp.publicKey, // Admin or KOL can sign
+          escrow: escrowPda,
+          kolProfile: kolProfilePda(),
+          projectOwnerProfile: projectOwnerProfilePda(),
+          programStats: programStatsPda,
           projectOwner: projectOwnerKp.publicKey,
           vaultTokenAccount: vaultTokenAccountPda,
+          mintLedger: mintLedgerPda,
           vaultAuthority: vaultAuthorityPda,
           projectOwnerTokenAccount: projectOwnerTokenAccount.address,
           mint: mint,
@@ -524,45 +483,17 @@ describe("mutual_escrow", () => {
   // Add more tests here, e.g., for releasing tokens, handling disputes, etc.
   it("Should create a market cap vesting deal, KOL claim partially and fully", async () => {
     const amount = new anchor.BN(1000 * 10 ** decimals); // 1000 tokens
-    orderIdBuffer = prepareOrderId("marketcaporderz");
-
     const vestingDuration = new anchor.BN(0); // 0 minute
 
     // Create market cap vesting deal
-    [dealPda] = PublicKey.findProgramAddressSync(
-      [
-        Buffer.from("deal"),
-        orderIdBuffer,
-        projectOwnerKp.publicKey.toBuffer(),
-        kolKp.publicKey.toBuffer(),
-        mint.toBuffer(),
-      ],
-      program.programId
-    );
-
-    let txHash = await program.methods
-      .createDeal(
-        amount,
-        { marketcap: {} }, // VestingType::Marketcap
-        vestingDuration, // No duration needed for market cap vesting
-        Array.from(orderIdBuffer)
-      )
-      .accounts({
-        escrow: escrowPda,
-        deal: dealPda,
-        projectOwner: projectOwnerKp.publicKey,
-        kol: kolKp.publicKey,
-        mint: mint,
-        projectOwnerTokenAccount: projectOwnerTokenAccount.address,
-        vaultTokenAccount: vaultTokenAccountPda,
-        vaultAuthority: vaultAuthorityPda,
-        tokenProgram: splToken.TOKEN_PROGRAM_ID,
-        systemProgram: SystemProgram.programId,
-      })
-      .signers([projectOwnerKp])
-      .rpc({ commitment: "confirmed", skipPreflight: true });
+    dealPda = await createDeal(
+      "marketcaporderz",
+      amount,
+      { marketcap: {} }, // VestingType::Marketcap
+      vestingDuration // No duration needed for market cap vesting
+    );
 
-    console.log(`Market cap vesting deal created: ${txHash}`);
+    console.log(`Market cap vesting deal created at ${dealPda.toBase58()}`);
 
     // Status Checking
     const dealData = (await program.account.deal.fetch(dealPda)) as any;
@@ -571,17 +502,7 @@ describe("mutual_escrow", () => {
     console.log("Deal become 'Created'");
 
     // KOL accept the deal
-    txHash = await program.methods
-      .acceptDeal()
-      .accounts({
-        deal: dealPda,
-        escrow: escrowPda,
-        signer: kolKp.publicKey, // KOL signs the transaction
-      })
-      .signers([kolKp])
-      .rpc({ commitment: "confirmed", skipPreflight: true });
-
-    console.log(`Deal accepted by KOL: ${txHash}`);
+    await acceptDeal(dealPda);
 
     // Fetch the updated deal data after the KOL accepts it
     const updatedDealData = (await program.account.deal.fetch(dealPda)) as any;
@@ -589,88 +510,51 @@ describe("mutual_escrow", () => {
     assert.equal(isAccepted, true, "Deal should be marked as 'Accepted'");
     console.log("Deal status updated to 'Accepted'");
 
-    // KOL becomes partially eligible
-    txHash = await program.methods
-      .setEligibilityStatus({ partiallyEligible: {} })
-      .accounts({
-        deal: dealPda,
-        escrow: escrowPda,
-        signer: adminKp.publicKey,
-      })
-      .signers([adminKp])
-      .rpc({ commitment: "confirmed" });
+    // The KOL holds the receipt NFT of the deal
+    assert.equal(await tokenBalance(receiptAccount(dealPda)), 1);
 
-    // Confirm transaction
-    const latestBlockHash = await connection.getLatestBlockhash();
-    await connection.confirmTransaction(
-      {
-        signature: txHash,
-        ...latestBlockHash,
-      },
-      "confirmed"
-    );
+    // KOL becomes partially eligible
+    await setEligibility(dealPda, { partiallyEligible: {} });
 
     console.log("KOL is now partially eligible.");
 
-    // KOL claims 20%
-    let kolTokenAccount = await splToken.getOrCreateAssociatedTokenAccount(
-      connection,
-      adminKp,
-      mint,
-      kolKp.publicKey
-    );
-
-    txHash = await program.methods
-      .resolveDeal()
-      .accounts({
-        deal: dealPda,
-        vaultTokenAccount: vaultTokenAccountPda,
-        vaultAuthority: vaultAuthorityPda,
-        kolTokenAccount: kolTokenAccount.address,
-        tokenProgram: splToken.TOKEN_PROGRAM_ID,
-        escrow: escrowPda,
-        signer: kolKp.publicKey,
-      })
-      .signers([kolKp])
-      .rpc({ commitment: "confirmed" });
+    // KOL claims 25%
+    let txHash = await resolveDeal(dealPda);
 
     console.log(
       `KOL claimed ${max_claimable_after_obligation}% of the tokens. txHash: ${txHash}`
     );
+    assert.equal(
+      await tokenBalance(kolTokenAccount.address),
+      250 * 10 ** decimals
+    );
 
     // Admin sets fully eligible after market cap reached
-    await program.methods
-      .setEligibilityStatus({ fullyEligible: {} })
-      .accounts({
-        deal: dealPda,
-        escrow: escrowPda,
-        signer: adminKp.publicKey,
-      })
-      .signers([adminKp])
-      .rpc({ commitment: "confirmed" });
+    await setEligibility(dealPda, { fullyEligible: {} });
 
     console.log("KOL is now fully eligible.");
 
-    // KOL claims remaining 80%
-    txHash = await program.methods
-      .resolveDeal()
-      .accounts({
-        deal: dealPda,
-        vaultTokenAccount: vaultTokenAccountPda,
-        vaultAuthority: vaultAuthorityPda,
-        kolTokenAccount: kolTokenAccount.address,
-        tokenProgram: splToken.TOKEN_PROGRAM_ID,
-        escrow: escrowPda,
-        signer: kolKp.publicKey,
-      })
-      .signers([kolKp])
-      .rpc({ commitment: "confirmed" });
+    // KOL claims remaining 75%
+    txHash = await resolveDeal(dealPda);
 
     console.log(
       `KOL claimed ${
         100 - max_claimable_after_obligation
       }% of the tokens. txHash: ${txHash}`
     );
+    assert.equal(
+      await tokenBalance(kolTokenAccount.address),
+      1000 * 10 ** decimals
+    );
+
+    const completedDeal = (await program.account.deal.fetch(dealPda)) as any;
+    assert.ok(completedDeal.status.completed, "Deal should be 'Completed'");
+
+    // The KOL's earnings in this mint count what reached them
+    const kolEarnings = await program.account.kolEarnings.fetch(
+      kolEarningsPda()
+    );
+    assert.equal(kolEarnings.totalEarned.toNumber(), 1000 * 10 ** decimals);
   });
 
   it("Should create a time vesting deal, KOL claims over time", async () => {
@@ -679,109 +563,36 @@ describe("mutual_escrow", () => {
     const firstClaimDelay = 1000; // 1 second delay
     const secondClaimDelay = 10000; // 9 second delay
     const max_claimable_after_obligation = 25; // 25% for partial eligibility
-    orderIdBuffer = prepareOrderId("time-order-id-hehe");
 
     // Create time vesting deal
-    [dealPda] = PublicKey.findProgramAddressSync(
-      [
-        Buffer.from("deal"),
-        orderIdBuffer,
-        projectOwnerKp.publicKey.toBuffer(),
-        kolKp.publicKey.toBuffer(),
-        mint.toBuffer(),
-      ],
-      program.programId
-    );
-
-    let txHash = await program.methods
-      .createDeal(
-        amount,
-        { time: {} }, // VestingType::Time
-        vestingDuration,
-        Array.from(orderIdBuffer)
-      )
-      .accounts({
-        escrow: escrowPda,
-        deal: dealPda,
-        projectOwner: projectOwnerKp.publicKey,
-        kol: kolKp.publicKey,
-        mint: mint,
-        projectOwnerTokenAccount: projectOwnerTokenAccount.address,
-        vaultTokenAccount: vaultTokenAccountPda,
-        vaultAuthority: vaultAuthorityPda,
-        tokenProgram: splToken.TOKEN_PROGRAM_ID,
-        systemProgram: SystemProgram.programId,
-        rent: anchor.web3.SYSVAR_RENT_PUBKEY,
-      })
-      .signers([projectOwnerKp])
-      .rpc({ commitment: "confirmed" });
+    dealPda = await createDeal(
+      "time-order-id-hehe",
+      amount,
+      { time: {} }, // VestingType::Time
+      vestingDuration
+    );
 
-    console.log(`Time vesting deal created with txHash: ${txHash}`);
+    console.log(`Time vesting deal created at ${dealPda.toBase58()}`);
 
     // KOL accepts the deal
-    txHash = await program.methods
-      .acceptDeal()
-      .accounts({
-        deal: dealPda,
-        escrow: escrowPda,
-        signer: kolKp.publicKey, // KOL signs the transaction
-      })
-      .signers([kolKp])
-      .rpc({ commitment: "confirmed", skipPreflight: true });
-
-    console.log(`Deal accepted by KOL with txHash: ${txHash}`);
+    await acceptDeal(dealPda);
 
     // KOL becomes partially eligible (based on max_claimable_after_obligation)
-    await program.methods
-      .setEligibilityStatus({ partiallyEligible: {} })
-      .accounts({
-        deal: dealPda,
-        escrow: escrowPda,
-        signer: adminKp.publicKey,
-      })
-      .signers([adminKp])
-      .rpc({ commitment: "confirmed" });
+    await setEligibility(dealPda, { partiallyEligible: {} });
 
     console.log(
       `KOL is now partially eligible (${max_claimable_after_obligation}%).`
     );
 
     // KOL claims the partial eligibility amount
-    let kolTokenAccount = await splToken.getOrCreateAssociatedTokenAccount(
-      connection,
-      adminKp,
-      mint,
-      kolKp.publicKey
-    );
-
-    txHash = await program.methods
-      .resolveDeal()
-      .accounts({
-        deal: dealPda,
-        escrow: escrowPda,
-        signer: kolKp.publicKey,
-        vaultTokenAccount: vaultTokenAccountPda,
-        vaultAuthority: vaultAuthorityPda,
-        kolTokenAccount: kolTokenAccount.address,
-        tokenProgram: splToken.TOKEN_PROGRAM_ID,
-      })
-      .signers([kolKp])
-      .rpc({ commitment: "confirmed" });
+    let txHash = await resolveDeal(dealPda);
 
     console.log(
       `KOL claimed ${max_claimable_after_obligation}% of the tokens. txHash: ${txHash}`
     );
 
     // KOL becomes fully eligible
-    await program.methods
-      .setEligibilityStatus({ fullyEligible: {} })
-      .accounts({
-        deal: dealPda,
-        escrow: escrowPda,
-        signer: adminKp.publicKey,
-      })
-      .signers([adminKp])
-      .rpc({ commitment: "confirmed" });
+    await setEligibility(dealPda, { fullyEligible: {} });
 
     console.log("KOL is now fully eligible.");
 
@@ -791,19 +602,7 @@ describe("mutual_escrow", () => {
 
     // KOL claims again after some time
     try {
-      txHash = await program.methods
-        .resolveDeal()
-        .accounts({
-          deal: dealPda,
-          escrow: escrowPda,
-          signer: kolKp.publicKey,
-          vaultTokenAccount: vaultTokenAccountPda,
-          vaultAuthority: vaultAuthorityPda,
-          kolTokenAccount: kolTokenAccount.address,
-          tokenProgram: splToken.TOKEN_PROGRAM_ID,
-        })
-        .signers([kolKp])
-        .rpc({ commitment: "confirmed", skipPreflight: true });
+      txHash = await resolveDeal(dealPda);
     } catch (e) {
       console.log("Error while KOL claiming after first delay", e);
       throw e;
@@ -820,27 +619,1026 @@ describe("mutual_escrow", () => {
     await sleep(secondClaimDelay);
 
     // KOL claims remaining tokens
-    txHash = await program.methods
-      .resolveDeal()
-      .accounts({
-        deal: dealPda,
-        escrow: escrowPda,
-        signer: kolKp.publicKey,
-        vaultTokenAccount: vaultTokenAccountPda,
-        vaultAuthority: vaultAuthorityPda,
-        kolTokenAccount: kolTokenAccount.address,
-        tokenProgram: splToken.TOKEN_PROGRAM_ID,
-      })
-      .signers([kolKp])
-      .rpc({ commitment: "confirmed" });
+    txHash = await resolveDeal(dealPda);
 
     console.log(
       `KOL claimed all remaining tokens after ${
         secondClaimDelay / 1000
       } seconds. txHash: ${txHash}`
     );
+
+    const dealData = (await program.account.deal.fetch(dealPda)) as any;
+    assert.equal(dealData.releasedAmount.toString(), amount.toString());
+    assert.ok(dealData.status.completed, "Deal should be 'Completed'");
+  });
+
+  it("Should split claims between the referrer, the payees and the KOL", async () => {
+    const referrerKp = Keypair.generate();
+    const payeeKp = Keypair.generate();
+    const referrerTokenAccount = await createTokenAccount(referrerKp.publicKey);
+    const payeeTokenAccount = await createTokenAccount(payeeKp.publicKey);
+
+    // The referrer takes 5% of every claim, the payee 20% of what's left
+    dealPda = await createDeal(
+      "split-order",
+      tokens(100),
+      { none: {} },
+      new anchor.BN(0),
+      { referrer: referrerKp.publicKey, referrerBps: 500 }
+    );
+    await acceptDeal(dealPda, [
+      { wallet: payeeKp.publicKey, shareBps: 2000 },
+    ]);
+    await setEligibility(dealPda, { fullyEligible: {} });
+
+    const kolBalanceBefore = await tokenBalance(kolTokenAccount.address);
+    await resolveDeal(dealPda, kolTokenAccount.address, [payeeTokenAccount]);
+
+    assert.equal(await tokenBalance(referrerTokenAccount), 5 * 10 ** decimals);
+    assert.equal(await tokenBalance(payeeTokenAccount), 19 * 10 ** decimals);
+    assert.equal(
+      (await tokenBalance(kolTokenAccount.address)) - kolBalanceBefore,
+      76 * 10 ** decimals
+    );
+
+    const referrerStats = await program.account.referrerStats.fetch(
+      referrerStatsPda(referrerKp.publicKey)
+    );
+    assert.equal(referrerStats.totalEarned.toNumber(), 5 * 10 ** decimals);
+    assert.equal(referrerStats.dealsReferred.toNumber(), 1);
+  });
+
+  it("Should refuse a referrer that is a party to the deal", async () => {
+    await expectError(
+      createDeal("self-referral", tokens(100), { none: {} }, new anchor.BN(0), {
+        referrer: kolKp.publicKey,
+        referrerBps: 500,
+      }),
+      "InvalidReferrer"
+    );
+  });
+
+  it("Should amend the terms of a pending deal", async () => {
+    dealPda = await createDeal(
+      "amendment-order",
+      tokens(100),
+      { time: {} },
+      new anchor.BN(60)
+    );
+
+    // The KOL counters with a smaller amount and no vesting
+    const deadline = new anchor.BN(Math.floor(Date.now() / 1000) + 600);
+    await program.methods
+      .proposeAmendment(tokens(80), { none: {} }, new anchor.BN(0), deadline)
+      .accounts({ deal: dealPda, signer: kolKp.publicKey })
+      .signers([kolKp])
+      .rpc({ commitment: "confirmed" });
+
+    const proposed = (await program.account.deal.fetch(dealPda)) as any;
+    assert.equal(
+      proposed.pendingAmendment.proposer.toBase58(),
+      kolKp.publicKey.toBase58()
+    );
+
+    // The KOL can't accept their own proposal
+    await expectError(acceptAmendment(dealPda, kolKp), "UnauthorizedSigner");
+
+    const projectOwnerBalanceBefore = await tokenBalance(
+      projectOwnerTokenAccount.address
+    );
+    await acceptAmendment(dealPda, projectOwnerKp);
+
+    // The difference goes back to the project owner
+    assert.equal(
+      (await tokenBalance(projectOwnerTokenAccount.address)) -
+        projectOwnerBalanceBefore,
+      20 * 10 ** decimals
+    );
+
+    const amended = (await program.account.deal.fetch(dealPda)) as any;
+    assert.equal(amended.amount.toString(), tokens(80).toString());
+    assert.ok(amended.vestingType.none, "Vesting type should be 'None'");
+    assert.equal(amended.pendingAmendment, null);
   });
 
+  it("Should pay a bonus top-up before the deal is eligible", async () => {
+    dealPda = await createDeal(
+      "bonus-order",
+      tokens(100),
+      { time: {} },
+      new anchor.BN(60)
+    );
+    await acceptDeal(dealPda);
+
+    const volumeBefore = (
+      await program.account.mintStats.fetch(mintStatsPda)
+    ).volumeEscrowed;
+    const fundedBefore = (
+      await program.account.projectOwnerVolume.fetch(projectOwnerVolumePda())
+    ).totalFunded;
+    await program.methods
+      .addToDeal(tokens(10), true)
+      .accounts({
+        deal: dealPda,
+        projectOwner: projectOwnerKp.publicKey,
+        projectOwnerTokenAccount: projectOwnerTokenAccount.address,
+        vaultTokenAccount: vaultTokenAccountPda,
+        mintLedger: mintLedgerPda,
+        mintStats: mintStatsPda,
+        projectOwnerVolume: projectOwnerVolumePda(),
+        tokenProgram: splToken.TOKEN_PROGRAM_ID,
+      })
+      .signers([projectOwnerKp])
+      .rpc({ commitment: "confirmed" });
+
+    const toppedUp = (await program.account.deal.fetch(dealPda)) as any;
+    assert.equal(toppedUp.amount.toString(), tokens(110).toString());
+    assert.equal(toppedUp.bonusAmount.toString(), tokens(10).toString());
+
+    // Top-ups count towards the escrowed and funded volumes
+    const mintStats = await program.account.mintStats.fetch(mintStatsPda);
+    assert.equal(
+      mintStats.volumeEscrowed.sub(volumeBefore).toString(),
+      tokens(10).toString()
+    );
+    const volume = await program.account.projectOwnerVolume.fetch(
+      projectOwnerVolumePda()
+    );
+    assert.equal(
+      volume.totalFunded.sub(fundedBefore).toString(),
+      tokens(10).toString()
+    );
+
+    // Only the bonus is claimable while the KOL isn't eligible
+    const kolBalanceBefore = await tokenBalance(kolTokenAccount.address);
+    await resolveDeal(dealPda);
+    assert.equal(
+      (await tokenBalance(kolTokenAccount.address)) - kolBalanceBefore,
+      10 * 10 ** decimals
+    );
+
+    const claimed = (await program.account.deal.fetch(dealPda)) as any;
+    assert.equal(claimed.releasedBonusAmount.toString(), tokens(10).toString());
+  });
+
+  it("Should let anyone crank a claim for a capped fee", async () => {
+    await expectError(setCrankFee(101), "CrankFeeTooHigh");
+    await setCrankFee(50); // 0.5% of each cranked claim
+
+    dealPda = await createDeal(
+      "crank-order",
+      tokens(100),
+      { none: {} },
+      new anchor.BN(0)
+    );
+    await acceptDeal(dealPda);
+    await setEligibility(dealPda, { fullyEligible: {} });
+
+    // The wallet cranks the claim and is paid into its own token account
+    const crankerTokenAccount = splToken.getAssociatedTokenAddressSync(
+      mint,
+      adminKp.publicKey
+    );
+    const crankerBalanceBefore = await tokenBalance(crankerTokenAccount);
+    const kolBalanceBefore = await tokenBalance(kolTokenAccount.address);
+
+    await program.methods
+      .crankClaim()
+      .accounts({
+        deal: dealPda,
+        cranker: adminKp.publicKey,
+        escrow: escrowPda,
+        mint: mint,
+        vaultTokenAccount: vaultTokenAccountPda,
+        mintLedger: mintLedgerPda,
+        programStats: programStatsPda,
+        mintStats: mintStatsPda,
+        vaultAuthority: vaultAuthorityPda,
+        recipient: kolKp.publicKey,
+        recipientTokenAccount: kolTokenAccount.address,
+        kolProfile: kolProfilePda(),
+        projectOwnerProfile: projectOwnerProfilePda(),
+        kolEarnings: kolEarningsPda(),
+        receiptTokenAccount: receiptAccount(dealPda),
+        referrerTokenAccount: null,
+        referrerStats: null,
+        crankConfig: crankConfigPda,
+        crankerTokenAccount: crankerTokenAccount,
+        tokenProgram: splToken.TOKEN_PROGRAM_ID,
+        associatedTokenProgram: splToken.ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([adminKp])
+      .rpc({ commitment: "confirmed" });
+
+    assert.equal(
+      (await tokenBalance(crankerTokenAccount)) - crankerBalanceBefore,
+      0.5 * 10 ** decimals
+    );
+    assert.equal(
+      (await tokenBalance(kolTokenAccount.address)) - kolBalanceBefore,
+      99.5 * 10 ** decimals
+    );
+
+    await setCrankFee(0);
+  });
+
+  it("Should set eligibility in batch and resolve several deals at once", async () => {
+    const deals = [];
+    for (const orderId of ["batch-order-1", "batch-order-2"]) {
+      deals.push(
+        await createDeal(orderId, tokens(50), { none: {} }, new anchor.BN(0))
+      );
+    }
+    for (const deal of deals) {
+      await acceptDeal(deal);
+    }
+
+    await program.methods
+      .batchSetEligibilityStatus([{ fullyEligible: {} }, { fullyEligible: {} }])
+      .accounts({ signer: adminKp.publicKey, escrow: escrowPda })
+      .remainingAccounts(
+        deals.map((deal) => ({
+          pubkey: deal,
+          isWritable: true,
+          isSigner: false,
+        }))
+      )
+      .signers([adminKp])
+      .rpc({ commitment: "confirmed" });
+
+    // Each deal is followed by its receipt and its project owner's profile
+    const kolBalanceBefore = await tokenBalance(kolTokenAccount.address);
+    await program.methods
+      .resolveMany()
+      .accounts({
+        kol: kolKp.publicKey,
+        escrow: escrowPda,
+        mint: mint,
+        vaultTokenAccount: vaultTokenAccountPda,
+        mintLedger: mintLedgerPda,
+        programStats: programStatsPda,
+        mintStats: mintStatsPda,
+        kolTokenAccount: kolTokenAccount.address,
+        kolProfile: kolProfilePda(),
+        kolEarnings: kolEarningsPda(),
+        vaultAuthority: vaultAuthorityPda,
+        tokenProgram: splToken.TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .remainingAccounts(
+        deals.flatMap((deal) => [
+          { pubkey: deal, isWritable: true, isSigner: false },
+          { pubkey: receiptAccount(deal), isWritable: false, isSigner: false },
+          {
+            pubkey: projectOwnerProfilePda(),
+            isWritable: true,
+            isSigner: false,
+          },
+        ])
+      )
+      .signers([kolKp])
+      .rpc({ commitment: "confirmed" });
+
+    assert.equal(
+      (await tokenBalance(kolTokenAccount.address)) - kolBalanceBefore,
+      100 * 10 ** decimals
+    );
+    for (const deal of deals) {
+      const dealData = (await program.account.deal.fetch(deal)) as any;
+      assert.ok(dealData.status.completed, "Deal should be 'Completed'");
+    }
+  });
+
+  it("Should revoke eligibility and claw back the deal", async () => {
+    dealPda = await createDeal(
+      "clawback-order",
+      tokens(100),
+      { time: {} },
+      new anchor.BN(60)
+    );
+    await acceptDeal(dealPda);
+    await setEligibility(dealPda, { partiallyEligible: {} });
+
+    // Revocations have to say why
+    await expectError(
+      revokeEligibility(dealPda, { notEligible: {} }, { none: {} }),
+      "MissingRevocationReason"
+    );
+    await revokeEligibility(dealPda, { notEligible: {} }, { postDeleted: {} });
+
+    const revoked = (await program.account.deal.fetch(dealPda)) as any;
+    assert.ok(revoked.eligibilityStatus.notEligible);
+    assert.equal(revoked.revocationCount, 1);
+    assert.ok(revoked.lastRevocationReason.postDeleted);
+
+    // Nothing is vested, so the whole amount goes back to the project owner
+    const projectOwnerBalanceBefore = await tokenBalance(
+      projectOwnerTokenAccount.address
+    );
+    await clawback(dealPda, true);
+
+    assert.equal(
+      (await tokenBalance(projectOwnerTokenAccount.address)) -
+        projectOwnerBalanceBefore,
+      100 * 10 ** decimals
+    );
+    const clawedBack = (await program.account.deal.fetch(dealPda)) as any;
+    assert.ok(clawedBack.status.clawedBack, "Deal should be 'ClawedBack'");
+  });
+
+  it("Should split the vested payout of a clawback with the payees", async () => {
+    const payeeKp = Keypair.generate();
+    const payeeTokenAccount = await createTokenAccount(payeeKp.publicKey);
+
+    dealPda = await createDeal(
+      "clawback-split",
+      tokens(100),
+      { none: {} },
+      new anchor.BN(0)
+    );
+    await acceptDeal(dealPda, [
+      { wallet: payeeKp.publicKey, shareBps: 2000 },
+    ]);
+    await setEligibility(dealPda, { fullyEligible: {} });
+
+    // Everything is vested, the payee gets its 20% as on a regular claim
+    const kolBalanceBefore = await tokenBalance(kolTokenAccount.address);
+    const earnedBefore = (
+      await program.account.kolEarnings.fetch(kolEarningsPda())
+    ).totalEarned.toNumber();
+    await clawback(dealPda, true, [payeeTokenAccount]);
+
+    assert.equal(await tokenBalance(payeeTokenAccount), 20 * 10 ** decimals);
+    assert.equal(
+      (await tokenBalance(kolTokenAccount.address)) - kolBalanceBefore,
+      80 * 10 ** decimals
+    );
+    const earnings = await program.account.kolEarnings.fetch(kolEarningsPda());
+    assert.equal(
+      earnings.totalEarned.toNumber() - earnedBefore,
+      80 * 10 ** decimals
+    );
+  });
+
+  it("Should count the clawback of a disputed deal as a lost dispute", async () => {
+    dealPda = await createDeal(
+      "clawback-dispute",
+      tokens(100),
+      { none: {} },
+      new anchor.BN(0)
+    );
+    await acceptDeal(dealPda);
+
+    await program.methods
+      .openDispute({ unresolved: {} })
+      .accounts({
+        deal: dealPda,
+        signer: projectOwnerKp.publicKey,
+        projectOwnerProfile: projectOwnerProfilePda(),
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([projectOwnerKp])
+      .rpc({ commitment: "confirmed" });
+
+    const disputesLostBefore = (
+      await program.account.kolProfile.fetch(kolProfilePda())
+    ).disputesLost.toNumber();
+    await clawback(dealPda, false);
+
+    const kolProfile = await program.account.kolProfile.fetch(kolProfilePda());
+    assert.equal(kolProfile.disputesLost.toNumber(), disputesLostBefore + 1);
+  });
+
+  it("Should settle a dispute in favour of the project owner", async () => {
+    dealPda = await createDeal(
+      "dispute-order",
+      tokens(100),
+      { none: {} },
+      new anchor.BN(0)
+    );
+    await acceptDeal(dealPda);
+
+    await program.methods
+      .openDispute({ unresolved: {} })
+      .accounts({
+        deal: dealPda,
+        signer: projectOwnerKp.publicKey,
+        projectOwnerProfile: projectOwnerProfilePda(),
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([projectOwnerKp])
+      .rpc({ commitment: "confirmed" });
+
+    const disputed = (await program.account.deal.fetch(dealPda)) as any;
+    assert.ok(disputed.status.disputed, "Deal should be 'Disputed'");
+    assert.ok(disputed.disputeReason.unresolved);
+
+    const disputesLostBefore = (
+      await program.account.kolProfile.fetch(kolProfilePda())
+    ).disputesLost.toNumber();
+    const projectOwnerBalanceBefore = await tokenBalance(
+      projectOwnerTokenAccount.address
+    );
+
+    await program.methods
+      .settleDispute({ projectOwnerWins: {} })
+      .accounts({
+        deal: dealPda,
+        admin: adminKp.publicKey,
+        escrow: escrowPda,
+        kolProfile: kolProfilePda(),
+        projectOwnerProfile: projectOwnerProfilePda(),
+        vaultTokenAccount: vaultTokenAccountPda,
+        mintLedger: mintLedgerPda,
+        vaultAuthority: vaultAuthorityPda,
+        projectOwnerTokenAccount: projectOwnerTokenAccount.address,
+        tokenProgram: splToken.TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([adminKp])
+      .rpc({ commitment: "confirmed" });
+
+    assert.equal(
+      (await tokenBalance(projectOwnerTokenAccount.address)) -
+        projectOwnerBalanceBefore,
+      100 * 10 ** decimals
+    );
+    const settled = (await program.account.deal.fetch(dealPda)) as any;
+    assert.ok(settled.status.resolved, "Deal should be 'Resolved'");
+    const kolProfile = await program.account.kolProfile.fetch(kolProfilePda());
+    assert.equal(kolProfile.disputesLost.toNumber(), disputesLostBefore + 1);
+  });
+
+  it("Should hold the KOL stake until the deal is completed", async () => {
+    dealPda = await createDeal(
+      "stake-order",
+      tokens(100),
+      { none: {} },
+      new anchor.BN(0),
+      { requiredKolStake: tokens(10) }
+    );
+
+    // The KOL bonds the deal out of the tokens claimed in the earlier tests
+    const kolBalanceBefore = await tokenBalance(kolTokenAccount.address);
+    await acceptDeal(dealPda, [], kolTokenAccount.address);
+    assert.equal(
+      kolBalanceBefore - (await tokenBalance(kolTokenAccount.address)),
+      10 * 10 ** decimals
+    );
+    const staked = (await program.account.deal.fetch(dealPda)) as any;
+    assert.equal(staked.kolStake.toString(), tokens(10).toString());
+
+    await expectError(releaseKolStake(dealPda), "DealNotFinished");
+
+    await setEligibility(dealPda, { fullyEligible: {} });
+    await resolveDeal(dealPda);
+    await releaseKolStake(dealPda);
+
+    // The KOL got the full amount plus the stake back
+    assert.equal(
+      (await tokenBalance(kolTokenAccount.address)) - kolBalanceBefore,
+      100 * 10 ** decimals
+    );
+    const released = (await program.account.deal.fetch(dealPda)) as any;
+    assert.equal(released.kolStake.toNumber(), 0);
+  });
+
+  it("Should let both parties review a finished deal", async () => {
+    // Reviews the deal completed by the stake test
+    const kolRatingBefore = await program.account.kolProfile.fetch(
+      kolProfilePda()
+    );
+    const projectOwnerRatingBefore =
+      await program.account.projectOwnerProfile.fetch(projectOwnerProfilePda());
+
+    await expectError(
+      submitReview(dealPda, projectOwnerKp, 6),
+      "InvalidRating"
+    );
+    await submitReview(dealPda, projectOwnerKp, 5);
+    await submitReview(dealPda, kolKp, 4);
+
+    // The project owner's rating goes to the KOL and the KOL's to the project owner
+    const kolProfile = await program.account.kolProfile.fetch(kolProfilePda());
+    assert.equal(
+      kolProfile.ratingCount.toNumber(),
+      kolRatingBefore.ratingCount.toNumber() + 1
+    );
+    assert.equal(
+      kolProfile.ratingTotal.toNumber(),
+      kolRatingBefore.ratingTotal.toNumber() + 5
+    );
+    const projectOwnerProfile =
+      await program.account.projectOwnerProfile.fetch(projectOwnerProfilePda());
+    assert.equal(
+      projectOwnerProfile.ratingTotal.toNumber(),
+      projectOwnerRatingBefore.ratingTotal.toNumber() + 4
+    );
+
+    const review = await program.account.review.fetch(
+      reviewPda(dealPda, projectOwnerKp.publicKey)
+    );
+    assert.equal(review.reviewee.toBase58(), kolKp.publicKey.toBase58());
+
+    // One review per party and deal
+    await assert.rejects(submitReview(dealPda, projectOwnerKp, 1));
+  });
+
+  it("Should pay a deal's claims to its payout address", async () => {
+    const payoutKp = Keypair.generate();
+    const payoutTokenAccount = await createTokenAccount(payoutKp.publicKey);
+
+    dealPda = await createDeal(
+      "payout-order",
+      tokens(100),
+      { none: {} },
+      new anchor.BN(0)
+    );
+    await acceptDeal(dealPda);
+
+    await program.methods
+      .setPayoutAddress(payoutKp.publicKey)
+      .accounts({
+        kolProfile: kolProfilePda(),
+        deal: dealPda,
+        kol: kolKp.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([kolKp])
+      .rpc({ commitment: "confirmed" });
+
+    const dealData = (await program.account.deal.fetch(dealPda)) as any;
+    assert.equal(
+      dealData.payoutOwner.toBase58(),
+      payoutKp.publicKey.toBase58()
+    );
+
+    await setEligibility(dealPda, { fullyEligible: {} });
+
+    // Claims to the KOL's own account are refused once a payout address is set
+    await expectError(resolveDeal(dealPda), "InvalidPayoutAccount");
+    await resolveDeal(dealPda, payoutTokenAccount);
+    assert.equal(await tokenBalance(payoutTokenAccount), 100 * 10 ** decimals);
+  });
+
+  it("Should report claimable amounts and vesting projections", async () => {
+    // The first deal is still pending, nothing is claimable yet
+    const breakdown = await program.methods
+      .checkClaimableAmount()
+      .accounts({ deal: dealPdaFor(orderIdBuffer), escrow: escrowPda })
+      .view();
+    assert.equal(breakdown.claimableNow.toNumber(), 0);
+    assert.equal(breakdown.lockedAmount.toNumber(), 1000 * 10 ** decimals);
+
+    // Project new terms, partially then fully eligible from `now`
+    const now = Math.floor(Date.now() / 1000);
+    const projections = await program.methods
+      .simulateVesting(
+        {
+          amount: tokens(1000),
+          vestingType: { time: {} },
+          vestingDuration: new anchor.BN(60),
+        },
+        [
+          {
+            eligibilityStatus: { partiallyEligible: {} },
+            eligibleTime: new anchor.BN(now),
+          },
+          {
+            eligibilityStatus: { fullyEligible: {} },
+            eligibleTime: new anchor.BN(now),
+          },
+        ],
+        [new anchor.BN(now), new anchor.BN(now + 60)]
+      )
+      .accounts({ deal: null, escrow: escrowPda })
+      .view();
+
+    assert.equal(projections.length, 4);
+    // Partial eligibility caps the claim, full eligibility vests everything by the end
+    assert.equal(
+      projections[1].claimableAmount.toNumber(),
+      (1000 * 10 ** decimals * max_claimable_after_obligation) / 100
+    );
+    assert.equal(
+      projections[3].claimableAmount.toNumber(),
+      1000 * 10 ** decimals
+    );
+  });
+
+  it("Should keep the vault solvent", async () => {
+    await program.methods
+      .verifySolvency()
+      .accounts({
+        mint: mint,
+        mintLedger: mintLedgerPda,
+        vaultTokenAccount: vaultTokenAccountPda,
+      })
+      .simulate();
+
+    const mintLedger = await program.account.mintLedger.fetch(mintLedgerPda);
+    assert.ok(
+      mintLedger.outstandingLiability.toNumber() <=
+        (await tokenBalance(vaultTokenAccountPda))
+    );
+  });
+
+  it("Should refuse to migrate current accounts or recreate the ledger", async () => {
+    await expectError(
+      migrateAccount(dealPdaFor(orderIdBuffer)),
+      "AccountAlreadyMigrated"
+    );
+    await expectError(migrateAccount(escrowPda), "AccountNotMigratable");
+
+    // The ledger was created with the first deal in this mint
+    await assert.rejects(
+      program.methods
+        .initMintLedger()
+        .accounts({
+          payer: adminKp.publicKey,
+          mint: mint,
+          vaultTokenAccount: vaultTokenAccountPda,
+          mintLedger: mintLedgerPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([adminKp])
+        .rpc({ commitment: "confirmed" })
+    );
+  });
+
+  function pda(seeds: Buffer[]): PublicKey {
+    return PublicKey.findProgramAddressSync(seeds, program.programId)[0];
+  }
+
+  function dealPdaFor(orderIdBuffer: Buffer): PublicKey {
+    return pda([
+      Buffer.from("deal"),
+      orderIdBuffer,
+      projectOwnerKp.publicKey.toBuffer(),
+      kolKp.publicKey.toBuffer(),
+      mint.toBuffer(),
+    ]);
+  }
+
+  function kolProfilePda(): PublicKey {
+    return pda([Buffer.from("kol_profile"), kolKp.publicKey.toBuffer()]);
+  }
+
+  function kolEarningsPda(): PublicKey {
+    return pda([
+      Buffer.from("kol_earnings"),
+      kolKp.publicKey.toBuffer(),
+      mint.toBuffer(),
+    ]);
+  }
+
+  function projectOwnerProfilePda(): PublicKey {
+    return pda([
+      Buffer.from("project_owner_profile"),
+      projectOwnerKp.publicKey.toBuffer(),
+    ]);
+  }
+
+  function projectOwnerVolumePda(): PublicKey {
+    return pda([
+      Buffer.from("project_owner_volume"),
+      projectOwnerKp.publicKey.toBuffer(),
+      mint.toBuffer(),
+    ]);
+  }
+
+  function referrerStatsPda(referrer: PublicKey): PublicKey {
+    return pda([
+      Buffer.from("referrer_stats"),
+      referrer.toBuffer(),
+      mint.toBuffer(),
+    ]);
+  }
+
+  function reviewPda(deal: PublicKey, reviewer: PublicKey): PublicKey {
+    return pda([Buffer.from("review"), deal.toBuffer(), reviewer.toBuffer()]);
+  }
+
+  // The KOL's token account holding the deal's receipt NFT
+  function receiptAccount(deal: PublicKey): PublicKey {
+    const receiptMint = pda([Buffer.from("receipt"), deal.toBuffer()]);
+    return splToken.getAssociatedTokenAddressSync(
+      receiptMint,
+      kolKp.publicKey
+    );
+  }
+
+  function createDealAccounts(deal: PublicKey, referrer?: PublicKey) {
+    return {
+      escrow: escrowPda,
+      deal: deal,
+      projectOwner: projectOwnerKp.publicKey,
+      kol: kolKp.publicKey,
+      mint: mint,
+      projectOwnerTokenAccount: projectOwnerTokenAccount.address,
+      vaultTokenAccount: vaultTokenAccountPda,
+      mintLedger: mintLedgerPda,
+      programStats: programStatsPda,
+      mintStats: mintStatsPda,
+      projectOwnerProfile: projectOwnerProfilePda(),
+      projectOwnerVolume: projectOwnerVolumePda(),
+      vaultAuthority: vaultAuthorityPda,
+      referrerStats: referrer ? referrerStatsPda(referrer) : null,
+      tokenProgram: splToken.TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+      rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+    };
+  }
+
+  async function createDeal(
+    orderId: string,
+    amount: anchor.BN,
+    vestingType: any,
+    vestingDuration: anchor.BN,
+    options: {
+      referrer?: PublicKey;
+      referrerBps?: number;
+      requiredKolStake?: anchor.BN;
+    } = {}
+  ): Promise<PublicKey> {
+    const orderIdBuffer = prepareOrderId(orderId);
+    const deal = dealPdaFor(orderIdBuffer);
+
+    await program.methods
+      .createDeal(
+        amount,
+        vestingType,
+        vestingDuration,
+        Array.from(orderIdBuffer),
+        options.referrer ?? null,
+        options.referrerBps ?? 0,
+        options.requiredKolStake ?? null
+      )
+      .accounts(createDealAccounts(deal, options.referrer))
+      .signers([projectOwnerKp])
+      .rpc({ commitment: "confirmed" });
+
+    return deal;
+  }
+
+  async function acceptDeal(
+    deal: PublicKey,
+    payees: { wallet: PublicKey; shareBps: number }[] = [],
+    kolStakeTokenAccount: PublicKey | null = null
+  ) {
+    const receiptMint = pda([Buffer.from("receipt"), deal.toBuffer()]);
+
+    return program.methods
+      .acceptDeal(payees)
+      .accounts({
+        deal: deal,
+        signer: kolKp.publicKey, // KOL signs the transaction
+        escrow: escrowPda,
+        programStats: programStatsPda,
+        kol: kolKp.publicKey,
+        kolProfile: kolProfilePda(),
+        receiptMint: receiptMint,
+        kolReceiptAccount: receiptAccount(deal),
+        kolStakeTokenAccount: kolStakeTokenAccount,
+        vaultTokenAccount: vaultTokenAccountPda,
+        mintLedger: mintLedgerPda,
+        vaultAuthority: vaultAuthorityPda,
+        tokenProgram: splToken.TOKEN_PROGRAM_ID,
+        associatedTokenProgram: splToken.ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+      })
+      .signers([kolKp])
+      .rpc({ commitment: "confirmed" });
+  }
+
+  async function setEligibility(deal: PublicKey, newStatus: any) {
+    return program.methods
+      .setEligibilityStatus(newStatus)
+      .accounts({
+        deal: deal,
+        signer: adminKp.publicKey,
+        escrow: escrowPda,
+      })
+      .signers([adminKp])
+      .rpc({ commitment: "confirmed" });
+  }
+
+  async function revokeEligibility(
+    deal: PublicKey,
+    newStatus: any,
+    reason: any
+  ) {
+    return program.methods
+      .revokeEligibility(newStatus, reason)
+      .accounts({
+        deal: deal,
+        signer: adminKp.publicKey,
+        escrow: escrowPda,
+      })
+      .signers([adminKp])
+      .rpc({ commitment: "confirmed" });
+  }
+
+  // Claims for the KOL, passing the referrer accounts when the deal has one
+  // and the payee token accounts in the order the payees were registered
+  async function resolveDeal(
+    deal: PublicKey,
+    recipientTokenAccount: PublicKey = kolTokenAccount.address,
+    payeeTokenAccounts: PublicKey[] = []
+  ) {
+    const dealData = (await program.account.deal.fetch(deal)) as any;
+    const hasReferrer = dealData.referrerBps > 0;
+
+    return program.methods
+      .resolveDeal()
+      .accounts({
+        deal: deal,
+        signer: kolKp.publicKey,
+        escrow: escrowPda,
+        vaultTokenAccount: vaultTokenAccountPda,
+        mintLedger: mintLedgerPda,
+        programStats: programStatsPda,
+        mintStats: mintStatsPda,
+        kolTokenAccount: recipientTokenAccount,
+        kolProfile: kolProfilePda(),
+        projectOwnerProfile: projectOwnerProfilePda(),
+        kolEarnings: kolEarningsPda(),
+        receiptTokenAccount: receiptAccount(deal),
+        vaultAuthority: vaultAuthorityPda,
+        referrerTokenAccount: hasReferrer
+          ? splToken.getAssociatedTokenAddressSync(mint, dealData.referrer)
+          : null,
+        referrerStats: hasReferrer ? referrerStatsPda(dealData.referrer) : null,
+        tokenProgram: splToken.TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .remainingAccounts(
+        payeeTokenAccounts.map((pubkey) => ({
+          pubkey,
+          isWritable: true,
+          isSigner: false,
+        }))
+      )
+      .signers([kolKp])
+      .rpc({ commitment: "confirmed" });
+  }
+
+  // The vested part is paid like `resolveDeal` would, the rest is refunded
+  async function clawback(
+    deal: PublicKey,
+    payVested: boolean,
+    payeeTokenAccounts: PublicKey[] = []
+  ) {
+    return program.methods
+      .clawback(payVested)
+      .accounts({
+        deal: deal,
+        admin: adminKp.publicKey,
+        escrow: escrowPda,
+        vaultTokenAccount: vaultTokenAccountPda,
+        mintLedger: mintLedgerPda,
+        vaultAuthority: vaultAuthorityPda,
+        kolTokenAccount: kolTokenAccount.address,
+        kolProfile: kolProfilePda(),
+        kolEarnings: kolEarningsPda(),
+        receiptTokenAccount: receiptAccount(deal),
+        projectOwnerTokenAccount: projectOwnerTokenAccount.address,
+        referrerTokenAccount: null,
+        referrerStats: null,
+        tokenProgram: splToken.TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .remainingAccounts(
+        payeeTokenAccounts.map((pubkey) => ({
+          pubkey,
+          isWritable: true,
+          isSigner: false,
+        }))
+      )
+      .signers([adminKp])
+      .rpc({ commitment: "confirmed" });
+  }
+
+  async function acceptAmendment(deal: PublicKey, signerKp: Keypair) {
+    return program.methods
+      .acceptAmendment()
+      .accounts({
+        deal: deal,
+        signer: signerKp.publicKey,
+        projectOwner: projectOwnerKp.publicKey,
+        projectOwnerTokenAccount: projectOwnerTokenAccount.address,
+        vaultTokenAccount: vaultTokenAccountPda,
+        mintLedger: mintLedgerPda,
+        mintStats: mintStatsPda,
+        projectOwnerVolume: projectOwnerVolumePda(),
+        vaultAuthority: vaultAuthorityPda,
+        tokenProgram: splToken.TOKEN_PROGRAM_ID,
+      })
+      .signers([signerKp])
+      .rpc({ commitment: "confirmed" });
+  }
+
+  async function setCrankFee(feeBps: number) {
+    return program.methods
+      .setCrankFee(feeBps)
+      .accounts({
+        escrow: escrowPda,
+        admin: adminKp.publicKey,
+        mint: mint,
+        crankConfig: crankConfigPda,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([adminKp])
+      .rpc({ commitment: "confirmed" });
+  }
+
+  // Anyone can release the stake, the wallet pays for it here
+  async function releaseKolStake(deal: PublicKey) {
+    return program.methods
+      .releaseKolStake()
+      .accounts({
+        deal: deal,
+        signer: adminKp.publicKey,
+        mint: mint,
+        kolProfile: kolProfilePda(),
+        recipient: kolKp.publicKey,
+        recipientTokenAccount: kolTokenAccount.address,
+        vaultTokenAccount: vaultTokenAccountPda,
+        mintLedger: mintLedgerPda,
+        vaultAuthority: vaultAuthorityPda,
+        tokenProgram: splToken.TOKEN_PROGRAM_ID,
+        associatedTokenProgram: splToken.ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([adminKp])
+      .rpc({ commitment: "confirmed" });
+  }
+
+  async function submitReview(
+    deal: PublicKey,
+    reviewerKp: Keypair,
+    rating: number
+  ) {
+    return program.methods
+      .submitReview(rating, Array.from(Buffer.alloc(32, rating)))
+      .accounts({
+        deal: deal,
+        reviewer: reviewerKp.publicKey,
+        review: reviewPda(deal, reviewerKp.publicKey),
+        kolProfile: kolProfilePda(),
+        projectOwnerProfile: projectOwnerProfilePda(),
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([reviewerKp])
+      .rpc({ commitment: "confirmed" });
+  }
+
+  async function migrateAccount(account: PublicKey) {
+    return program.methods
+      .migrateAccount()
+      .accounts({
+        payer: adminKp.publicKey,
+        account: account,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([adminKp])
+      .rpc({ commitment: "confirmed" });
+  }
+
+  async function createTokenAccount(owner: PublicKey): Promise<PublicKey> {
+    const tokenAccount = await splToken.getOrCreateAssociatedTokenAccount(
+      connection,
+      adminKp,
+      mint,
+      owner
+    );
+    return tokenAccount.address;
+  }
+
+  async function tokenBalance(address: PublicKey): Promise<number> {
+    return Number((await splToken.getAccount(connection, address)).amount);
+  }
+
+  async function expectError(promise: Promise<unknown>, code: string) {
+    try {
+      await promise;
+    } catch (e) {
+      assert.equal(e.error?.errorCode?.code, code, e.toString());
+      return;
+    }
+    assert.fail(`Expected the transaction to fail with ${code}`);
+  }
+
+  function tokens(amount: number): anchor.BN {
+    return new anchor.BN(amount * 10 ** decimals);
+  }
+
+  function sleep(ms: number) {
+    return new Promise((resolve) => setTimeout(resolve, ms));
+  }
+
   function prepareOrderId(orderId: string): Buffer {
     let orderIdBuffer = Buffer.from(orderId, "utf-8");
     if (orderIdBuffer.length > 16) {